@@ -1,7 +1,6 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod commands;
 mod modules;
 mod plugins;
 mod setup;
@@ -20,12 +19,19 @@ fn main() {
         .plugin(plugins::store::register())
         // Stronghold plugin
         .plugin(plugins::stronghold::register())
-        // Setup
-        .setup(setup::setup)
-        // Invoke handlers
+        // API client
+        .manage(modules::api::ApiState::default())
         .invoke_handler(tauri::generate_handler![
-            commands::helpers::get_image_dominant
+            modules::api::connect,
+            modules::api::login,
+            modules::api::verify_two_factor,
+            modules::api::logout,
+            modules::api::me,
+            modules::api::get_games,
+            modules::api::get_game,
         ])
+        // Setup
+        .setup(setup::setup)
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1 +1,97 @@
+use std::sync::{Arc, Mutex};
 
+use game_sync_api_client::{
+    models::{GameDetails, GameQuery, GameSearchResults, User},
+    ApiClient, ApiError, LoginOutcome,
+};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+/// Client of the configured server, `None` until the frontend connects to one
+#[derive(Default)]
+pub struct ApiState(Mutex<Option<ApiClient>>);
+
+impl ApiState {
+    fn client(&self) -> Result<ApiClient, String> {
+        self.0
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "No server configured".to_string())
+    }
+}
+
+fn to_message(error: ApiError) -> String {
+    error.to_string()
+}
+
+/// Use the given server, with the session token saved by the frontend. The renewed tokens are
+/// sent back with the `api-token` event so the frontend can save them.
+#[tauri::command]
+pub fn connect<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, ApiState>,
+    server_url: String,
+    token: Option<String>,
+) -> Result<(), String> {
+    let client = ApiClient::new(&server_url)
+        .map_err(to_message)?
+        .with_token(token)
+        .on_token_change(Arc::new(move |token| {
+            let _ = app.emit_all("api-token", token);
+        }));
+
+    *state.0.lock().unwrap() = Some(client);
+
+    Ok(())
+}
+
+/// `None` when the code of the second factor is required
+#[tauri::command]
+pub async fn login(
+    state: State<'_, ApiState>,
+    email: String,
+    password: String,
+) -> Result<Option<User>, String> {
+    let outcome = state
+        .client()?
+        .login(&email, &password)
+        .await
+        .map_err(to_message)?;
+
+    Ok(match outcome {
+        LoginOutcome::LoggedIn(user) => Some(user),
+        LoginOutcome::TwoFactorRequired => None,
+    })
+}
+
+#[tauri::command]
+pub async fn verify_two_factor(state: State<'_, ApiState>, code: String) -> Result<User, String> {
+    state
+        .client()?
+        .verify_two_factor(&code)
+        .await
+        .map_err(to_message)
+}
+
+#[tauri::command]
+pub async fn logout(state: State<'_, ApiState>) -> Result<(), String> {
+    state.client()?.logout().await.map_err(to_message)
+}
+
+#[tauri::command]
+pub async fn me(state: State<'_, ApiState>) -> Result<User, String> {
+    state.client()?.me().await.map_err(to_message)
+}
+
+#[tauri::command]
+pub async fn get_games(
+    state: State<'_, ApiState>,
+    query: GameQuery,
+) -> Result<GameSearchResults, String> {
+    state.client()?.games(&query).await.map_err(to_message)
+}
+
+#[tauri::command]
+pub async fn get_game(state: State<'_, ApiState>, id: i32) -> Result<GameDetails, String> {
+    state.client()?.game(id).await.map_err(to_message)
+}
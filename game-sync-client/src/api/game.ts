@@ -72,6 +72,9 @@ export async function getGames({
     if (pagination?.page) params.set("page", pagination.page.toString());
     if (pagination?.perPage)
       params.set("per_page", pagination.perPage.toString());
+    if (pagination?.cursor !== undefined)
+      params.set("cursor", pagination.cursor);
+    if (pagination?.withTotal) params.set("with_total", "true");
 
     params.set("sort_order", sortOrder);
 
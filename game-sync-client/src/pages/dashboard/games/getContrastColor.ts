@@ -1,22 +1,17 @@
 import { GameBanner, GameBannerType } from "@/types/game";
-import { Color, getImageDominantColor, getLuminance } from "@/utils/colors";
+import { Color, getLuminance } from "@/utils/colors";
 
 /**
-* Get contrast color from a hex color or an image
-* @param banner_data The banner data with type (image or color) and value (hex color or image url)
+* Get contrast color from the banner accent color
+* @param banner_data The banner data with type (image or color), value and accent color
 * @returns The contrast color
 */
-export async function getContrastColor(banner_data: GameBanner): string {
-let color: Color;
-
-// If the banner is a color, we can get the contrast color
-if (banner_data.banner_type === GameBannerType.Color) {
-const hex = banner_data.value;
-color = Color.fromHex(hex);
-} else {
-// If the banner is an image, get the average color of the image
-color = await getImageDominantColor(banner_data.value);
-}
+export function getContrastColor(banner_data: GameBanner): string {
+// The accent color is computed by the server, it's the dominant color for images
+const hex =
+banner_data.accent_color ??
+(banner_data.banner_type === GameBannerType.Color ? banner_data.value : "#000000");
+const color = Color.fromHex(hex);
 
 // Counting the perceptive luminance - human eye favors green color...
 // https://stackoverflow.com/a/3943023/1232793
@@ -1,6 +1,39 @@
+export type GamePlatform = "windows" | "macos" | "linux";
+
+export interface SystemRequirements {
+  os?: string;
+  processor?: string;
+  graphics?: string;
+  /** Memory in megabytes */
+  memory?: number;
+  /** Storage in megabytes */
+  storage?: number;
+}
+
 export interface GameInput {
   name: string;
   description?: string;
+  developer?: string;
+  publisher?: string;
+  /** Formatted as `YYYY-MM-DD` */
+  release_date?: string;
+  tags?: string[];
+  genres?: string[];
+  platforms?: GamePlatform[];
+  system_requirements?: SystemRequirements;
+}
+
+export interface FacetCount {
+  value: string;
+  count: number;
+}
+
+export interface GameFacets {
+  developers: FacetCount[];
+  publishers: FacetCount[];
+  tags: FacetCount[];
+  genres: FacetCount[];
+  platforms: FacetCount[];
 }
 
 export enum GameBannerType {
@@ -8,19 +41,60 @@ export enum GameBannerType {
   Image = "image",
 }
 
+export type BannerVariantSize = "full" | "medium" | "thumbnail";
+
+export interface BannerVariant {
+  size: BannerVariantSize;
+  format: "webp" | "avif";
+  width: number;
+  height: number;
+  url: string;
+}
+
 export interface GameBanner {
   id: number;
   game_id: number;
   banner_type: GameBannerType;
   value: string;
+  accent_color?: string;
+  variants: BannerVariant[];
+}
+
+export type GameMediaKind = "icon" | "logo" | "hero" | "screenshot" | "trailer";
+
+export interface GameMedia {
+  id: number;
+  kind: GameMediaKind;
+  position: number;
+  url: string;
+  accent_color?: string;
+  variants: BannerVariant[];
+}
+
+export interface GameMediaSet {
+  icon?: GameMedia;
+  logo?: GameMedia;
+  hero?: GameMedia;
+  trailer?: GameMedia;
+  screenshots: GameMedia[];
 }
 
 export interface Game {
   id: number;
   name: string;
   description?: string;
+  developer?: string;
+  publisher?: string;
+  release_date?: string;
   created_at: string;
   updated_at: string;
 
+  // Only returned by the game details
+  system_requirements?: SystemRequirements;
+  tags?: string[];
+  genres?: string[];
+  platforms?: GamePlatform[];
+
   banner?: GameBanner;
+  media?: GameMediaSet;
 }
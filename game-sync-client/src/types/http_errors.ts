@@ -1,3 +1,22 @@
+/** Body of the error responses of the server, following RFC 7807 */
+export interface ProblemDetails<K extends string = string> {
+  type: string;
+  title: string;
+  status: number;
+  detail: string;
+  /** Stable code of the error, e.g. `validation_failed` */
+  code: string;
+  correlation_id?: string;
+  /** Only set on validation errors */
+  fields?: Record<K, FieldError[]>;
+}
+
+export interface FieldError {
+  /** Rule which failed, e.g. `length` or `email` */
+  code: string;
+  message: string;
+}
+
 export class HttpError extends Error {
   public readonly response: Response;
 
@@ -18,11 +37,14 @@ export class HttpValidationError<K extends string> extends Error {
   public static async fromResponse<T extends string>(
     response: Response,
   ): Promise<HttpValidationError<T>> {
-    const data = (await response.json()) as {
-      message: string;
-      fields: Record<T, string[]>;
-    };
-    return new HttpValidationError(data.fields);
+    const problem = (await response.json()) as ProblemDetails<T>;
+    const fields = {} as Record<T, string[]>;
+    for (const [field, errors] of Object.entries<FieldError[]>(
+      problem.fields ?? {},
+    )) {
+      fields[field as T] = errors.map((error) => error.message);
+    }
+    return new HttpValidationError(fields);
   }
 
   public static assert<T extends string>(
@@ -1,13 +1,24 @@
 export interface PaginationQuery {
   page?: number;
   perPage?: number;
+  /**
+   * Paginate by cursor instead of page, use an empty cursor for the first page
+   * and then the `next_cursor` of the previous page.
+   */
+  cursor?: string;
+  /** Count the items when paginating by cursor */
+  withTotal?: boolean;
 }
 
 export interface PaginationMeta {
-  current_page: number;
-  total_items: number;
-  total_pages: number;
+  /** Only set when paginating by page */
+  current_page?: number;
+  total_items?: number;
+  /** Only set when paginating by page */
+  total_pages?: number;
   per_page: number;
+  /** Missing on the last page */
+  next_cursor?: string;
 }
 
 export interface PaginationResponse<T> {
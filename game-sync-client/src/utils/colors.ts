@@ -1,5 +1,3 @@
-import { invoke } from "@tauri-apps/api";
-
 export class Color {
   public constructor(
     public r: number,
@@ -25,11 +23,3 @@ export class Color {
 export function getLuminance(color: Color): number {
   return 0.299 * color.r + 0.587 * color.g + 0.114 * color.b;
 }
-
-export async function getImageDominantColor(src: string): Promise<Color> {
-  const result = await invoke<Color>("get_image_dominant", { src });
-
-  console.log("result", result);
-
-  return result;
-}
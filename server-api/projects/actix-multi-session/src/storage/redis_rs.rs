@@ -199,6 +199,12 @@ impl SessionStore for RedisSessionStore {
 }
 
 impl RedisSessionStore {
+    /// Check that Redis answers, e.g. for health checks.
+    pub async fn ping(&self) -> Result<(), anyhow::Error> {
+        let _: String = self.execute_command(&mut redis::cmd("PING")).await?;
+        Ok(())
+    }
+
     /// Execute Redis command and retry once in certain cases.
     ///
     /// `ConnectionManager` automatically reconnects when it encounters an error talking to Redis.
@@ -0,0 +1,452 @@
+use std::sync::{Arc, RwLock};
+
+use reqwest::{
+    header::{RETRY_AFTER, WWW_AUTHENTICATE},
+    multipart::{Form, Part},
+    Method, RequestBuilder, Response, StatusCode,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use url::Url;
+
+use crate::{
+    error::{ApiError, ApiResult},
+    models::{
+        DownloadUrl, Game, GameDetails, GameInput, GameMedia, GameQuery, GameSearchResults,
+        MediaKind, OidcAuthorization, PresignedUrl, ServerStatus, UploadMode, User,
+    },
+};
+
+/// Called with the new session token whenever it changes, or `None` once the session is over
+pub type TokenListener = Arc<dyn Fn(Option<&str>) + Send + Sync>;
+
+/// Result of a login with a password
+#[derive(Debug, Clone)]
+pub enum LoginOutcome {
+    LoggedIn(User),
+    /// The code of the second factor must be sent to [`ApiClient::verify_two_factor`]
+    TwoFactorRequired,
+}
+
+/// Client of the Game Sync API. Cloning it is cheap, the clones share the session token.
+#[derive(Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: Url,
+    token: Arc<RwLock<Option<String>>>,
+    on_token_change: Option<TokenListener>,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str) -> ApiResult<Self> {
+        // Without a trailing slash, the last segment of the URL would be replaced when joining
+        let base_url = if base_url.ends_with('/') {
+            Url::parse(base_url)?
+        } else {
+            Url::parse(&format!("{}/", base_url))?
+        };
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url,
+            token: Arc::new(RwLock::new(None)),
+            on_token_change: None,
+        })
+    }
+
+    /// Restore a session token saved previously
+    pub fn with_token(self, token: Option<String>) -> Self {
+        *self.token.write().unwrap() = token;
+        self
+    }
+
+    pub fn on_token_change(mut self, listener: TokenListener) -> Self {
+        self.on_token_change = Some(listener);
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.read().unwrap().clone()
+    }
+
+    pub fn set_token(&self, token: Option<String>) {
+        {
+            let mut current = self.token.write().unwrap();
+            if *current == token {
+                return;
+            }
+            *current = token.clone();
+        }
+
+        if let Some(listener) = &self.on_token_change {
+            listener(token.as_deref());
+        }
+    }
+
+    // Status
+
+    pub async fn status(&self) -> ApiResult<ServerStatus> {
+        let response = self.send(self.request(Method::GET, "")?).await?;
+        Ok(response.json().await?)
+    }
+
+    // Auth
+
+    pub async fn login(&self, email: &str, password: &str) -> ApiResult<LoginOutcome> {
+        let request = self
+            .request(Method::POST, "api/auth")?
+            .json(&json!({ "email": email, "password": password }));
+        let response = self.send(request).await?;
+
+        if response.status() == StatusCode::ACCEPTED {
+            return Ok(LoginOutcome::TwoFactorRequired);
+        }
+
+        Ok(LoginOutcome::LoggedIn(response.json().await?))
+    }
+
+    /// Finish a login with the TOTP code of the user, or one of their recovery codes
+    pub async fn verify_two_factor(&self, code: &str) -> ApiResult<User> {
+        let request = self
+            .request(Method::POST, "api/auth/two-factor")?
+            .json(&json!({ "code": code }));
+        self.json(request).await
+    }
+
+    pub async fn me(&self) -> ApiResult<User> {
+        self.json(self.request(Method::GET, "api/auth")?).await
+    }
+
+    pub async fn logout(&self) -> ApiResult<()> {
+        let result = self.send(self.request(Method::DELETE, "api/auth")?).await;
+
+        // The session is over for the client, even if the server couldn't be reached
+        self.set_token(None);
+
+        result.map(|_| ())
+    }
+
+    /// URL of the login page of the identity provider, which redirects to `redirect_uri`
+    pub async fn oidc_authorize(&self, redirect_uri: &str) -> ApiResult<OidcAuthorization> {
+        let request = self
+            .request(Method::POST, "api/auth/oidc")?
+            .json(&json!({ "redirect_uri": redirect_uri }));
+        self.json(request).await
+    }
+
+    /// Log in with the parameters given to the redirect URI by the identity provider
+    pub async fn oidc_callback(&self, code: &str, state: &str) -> ApiResult<LoginOutcome> {
+        let request = self
+            .request(Method::POST, "api/auth/oidc/callback")?
+            .json(&json!({ "code": code, "state": state }));
+        let response = self.send(request).await?;
+
+        if response.status() == StatusCode::ACCEPTED {
+            return Ok(LoginOutcome::TwoFactorRequired);
+        }
+
+        Ok(LoginOutcome::LoggedIn(response.json().await?))
+    }
+
+    // Games
+
+    pub async fn games(&self, query: &GameQuery) -> ApiResult<GameSearchResults> {
+        let request = self
+            .request(Method::GET, "api/games")?
+            .query(&query.to_params());
+        self.json(request).await
+    }
+
+    /// Deleted games which can still be restored
+    pub async fn deleted_games(&self) -> ApiResult<Vec<Game>> {
+        self.json(self.request(Method::GET, "api/games/trash")?)
+            .await
+    }
+
+    pub async fn game(&self, id: i32) -> ApiResult<GameDetails> {
+        self.json(self.request(Method::GET, &format!("api/games/{}", id))?)
+            .await
+    }
+
+    pub async fn create_game(&self, input: &GameInput) -> ApiResult<Game> {
+        let request = self.request(Method::POST, "api/games")?.json(input);
+        self.json(request).await
+    }
+
+    pub async fn update_game(&self, id: i32, input: &GameInput) -> ApiResult<Game> {
+        let request = self
+            .request(Method::PUT, &format!("api/games/{}", id))?
+            .json(input);
+        self.json(request).await
+    }
+
+    pub async fn delete_game(&self, id: i32) -> ApiResult<Game> {
+        self.json(self.request(Method::DELETE, &format!("api/games/{}", id))?)
+            .await
+    }
+
+    pub async fn restore_game(&self, id: i32) -> ApiResult<Game> {
+        self.json(self.request(Method::POST, &format!("api/games/{}/restore", id))?)
+            .await
+    }
+
+    // Game media
+
+    pub async fn upload_banner_image(
+        &self,
+        game_id: i32,
+        image: Vec<u8>,
+        filename: &str,
+    ) -> ApiResult<()> {
+        let form = Form::new().part("value", Part::bytes(image).file_name(filename.to_owned()));
+        let request = self
+            .request(Method::POST, &format!("api/games/{}/banner", game_id))?
+            .multipart(form);
+        self.send(request).await.map(|_| ())
+    }
+
+    /// Hexadecimal color, e.g. `#1e1e2e`
+    pub async fn set_banner_color(&self, game_id: i32, color: &str) -> ApiResult<()> {
+        let form = Form::new().text("color", color.to_owned());
+        let request = self
+            .request(Method::POST, &format!("api/games/{}/banner", game_id))?
+            .multipart(form);
+        self.send(request).await.map(|_| ())
+    }
+
+    /// Reset the banner to a color
+    pub async fn delete_banner(&self, game_id: i32) -> ApiResult<()> {
+        let request = self.request(Method::DELETE, &format!("api/games/{}/banner", game_id))?;
+        self.send(request).await.map(|_| ())
+    }
+
+    /// Upload an image, replacing the previous one except for screenshots
+    pub async fn upload_media(
+        &self,
+        game_id: i32,
+        kind: MediaKind,
+        image: Vec<u8>,
+        filename: &str,
+    ) -> ApiResult<GameMedia> {
+        let form = Form::new()
+            .text("kind", kind.as_str())
+            .part("file", Part::bytes(image).file_name(filename.to_owned()));
+        let request = self
+            .request(Method::POST, &format!("api/games/{}/media", game_id))?
+            .multipart(form);
+        self.json(request).await
+    }
+
+    pub async fn set_trailer(&self, game_id: i32, url: &str) -> ApiResult<GameMedia> {
+        let request = self
+            .request(Method::PUT, &format!("api/games/{}/media/trailer", game_id))?
+            .json(&json!({ "url": url }));
+        self.json(request).await
+    }
+
+    /// Order the screenshots of a game, all their IDs must be given
+    pub async fn reorder_screenshots(
+        &self,
+        game_id: i32,
+        ids: &[i32],
+    ) -> ApiResult<Vec<GameMedia>> {
+        let request = self
+            .request(Method::PUT, &format!("api/games/{}/media/order", game_id))?
+            .json(&json!({ "ids": ids }));
+        self.json(request).await
+    }
+
+    pub async fn delete_media(&self, game_id: i32, media_id: i32) -> ApiResult<()> {
+        let request = self.request(
+            Method::DELETE,
+            &format!("api/games/{}/media/{}", game_id, media_id),
+        )?;
+        self.send(request).await.map(|_| ())
+    }
+
+    // Versions
+
+    /// Presigned URL to upload a version of a game to the storage
+    pub async fn create_upload(
+        &self,
+        game_id: i32,
+        filename: &str,
+        file_size: usize,
+    ) -> ApiResult<PresignedUrl> {
+        let request = self
+            .request(Method::POST, &format!("api/game/{}/version", game_id))?
+            .query(&[
+                ("filename", filename),
+                ("file_size", &file_size.to_string()),
+            ]);
+        self.json(request).await
+    }
+
+    /// Upload a version of a game, directly to the storage
+    pub async fn upload_version(
+        &self,
+        game_id: i32,
+        filename: &str,
+        content: Vec<u8>,
+    ) -> ApiResult<()> {
+        let presigned = self.create_upload(game_id, filename, content.len()).await?;
+        self.upload_to_storage(presigned, filename, content).await
+    }
+
+    /// Presigned URL to download a version of a game from the storage
+    pub async fn version_download_url(
+        &self,
+        game_id: i32,
+        filename: &str,
+    ) -> ApiResult<DownloadUrl> {
+        let request = self
+            .request(Method::GET, &format!("api/game/{}/version", game_id))?
+            .query(&[("filename", filename)]);
+        self.json(request).await
+    }
+
+    /// Download a version of a game, directly from the storage
+    pub async fn download_version(&self, game_id: i32, filename: &str) -> ApiResult<Vec<u8>> {
+        let download = self.version_download_url(game_id, filename).await?;
+        self.download_from_storage(&download).await
+    }
+
+    // Saves
+
+    /// Presigned URL to upload the save of the user to the storage
+    pub async fn create_save_upload(
+        &self,
+        game_id: i32,
+        file_size: usize,
+    ) -> ApiResult<PresignedUrl> {
+        let request = self
+            .request(Method::POST, &format!("api/game/{}/save", game_id))?
+            .query(&[("file_size", file_size.to_string())]);
+        self.json(request).await
+    }
+
+    /// Upload the save of the user, replacing the previous one
+    pub async fn upload_save(&self, game_id: i32, content: Vec<u8>) -> ApiResult<()> {
+        let presigned = self.create_save_upload(game_id, content.len()).await?;
+        self.upload_to_storage(presigned, "save", content).await
+    }
+
+    /// Presigned URL to download the save of the user, `NotFound` when there is none yet
+    pub async fn save_download_url(&self, game_id: i32) -> ApiResult<DownloadUrl> {
+        self.json(self.request(Method::GET, &format!("api/game/{}/save", game_id))?)
+            .await
+    }
+
+    /// Download the save of the user, directly from the storage
+    pub async fn download_save(&self, game_id: i32) -> ApiResult<Vec<u8>> {
+        let download = self.save_download_url(game_id).await?;
+        self.download_from_storage(&download).await
+    }
+
+    // Helpers
+
+    // The storage isn't part of the API, its requests don't carry the session token
+
+    async fn upload_to_storage(
+        &self,
+        presigned: PresignedUrl,
+        filename: &str,
+        content: Vec<u8>,
+    ) -> ApiResult<()> {
+        let request = match presigned.upload_mode {
+            UploadMode::SingleUpload => {
+                let form = presigned
+                    .fields
+                    .unwrap_or_default()
+                    .into_iter()
+                    .fold(Form::new(), |form, (name, value)| form.text(name, value))
+                    .part("file", Part::bytes(content).file_name(filename.to_owned()));
+
+                self.http.post(&presigned.url).multipart(form)
+            }
+            UploadMode::MultipartUpload => self.http.put(&presigned.url).body(content),
+        };
+
+        Self::check_storage(request.send().await?).await?;
+
+        Ok(())
+    }
+
+    async fn download_from_storage(&self, download: &DownloadUrl) -> ApiResult<Vec<u8>> {
+        let response = Self::check_storage(self.http.get(&download.url).send().await?).await?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn check_storage(response: Response) -> ApiResult<Response> {
+        if !response.status().is_success() {
+            return Err(ApiError::Storage {
+                status: response.status().as_u16(),
+                message: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        Ok(response)
+    }
+
+    fn request(&self, method: Method, path: &str) -> ApiResult<RequestBuilder> {
+        let url = self.base_url.join(path)?;
+        let request = self.http.request(method, url);
+
+        Ok(match self.token() {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        })
+    }
+
+    /// Send a request to the API, keeping track of the session token and mapping the errors
+    async fn send(&self, request: RequestBuilder) -> ApiResult<Response> {
+        let response = request.send().await?;
+
+        // The session is created or renewed
+        if let Some(token) = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        {
+            self.set_token(Some(token.to_owned()));
+        }
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        if status == StatusCode::UNAUTHORIZED {
+            self.set_token(None);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        let body = response.text().await.unwrap_or_default();
+
+        Err(ApiError::from_response(status.as_u16(), &body, retry_after))
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> ApiResult<T> {
+        Ok(self.send(request).await?.json().await?)
+    }
+}
+
+impl std::fmt::Debug for ApiClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The token is a secret
+        f.debug_struct("ApiClient")
+            .field("base_url", &self.base_url)
+            .field("logged_in", &self.token().is_some())
+            .finish()
+    }
+}
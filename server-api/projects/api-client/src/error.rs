@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use game_sync_common::errors::{ErrorCode, FieldError, ProblemDetails};
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Invalid server URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+
+    #[error("HTTP Error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// Error returned by the server. The token is forgotten on `Unauthorized` errors.
+    #[error("{} ({})", .problem.detail, .problem.code.as_str())]
+    Api {
+        problem: ProblemDetails,
+        /// Seconds to wait before retrying, on `RateLimited` errors
+        retry_after: Option<u64>,
+    },
+
+    /// The storage refused the upload or the download of a file
+    #[error("Storage Error ({status}): {message}")]
+    Storage { status: u16, message: String },
+}
+
+impl ApiError {
+    /// Code of the errors returned by the server
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            ApiError::Api { problem, .. } => Some(problem.code),
+            _ => None,
+        }
+    }
+
+    /// Errors of each invalid field, empty unless the code is `ValidationFailed`
+    pub fn fields(&self) -> Option<&BTreeMap<String, Vec<FieldError>>> {
+        match self {
+            ApiError::Api { problem, .. } => Some(&problem.fields),
+            _ => None,
+        }
+    }
+
+    /// Build the error of a response, whose body isn't always a problem when it comes from a
+    /// proxy in front of the server
+    pub(crate) fn from_response(status: u16, body: &str, retry_after: Option<u64>) -> Self {
+        let problem = serde_json::from_str(body).unwrap_or_else(|_| {
+            let title = reqwest::StatusCode::from_u16(status)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or_default();
+
+            ProblemDetails::new(status, title, ErrorCode::Unknown, body)
+        });
+
+        ApiError::Api {
+            problem,
+            retry_after,
+        }
+    }
+}
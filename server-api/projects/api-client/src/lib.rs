@@ -0,0 +1,14 @@
+//! Typed client of the Game Sync API, used by the desktop client.
+//!
+//! The session token is sent as a bearer token. The server sends it back in the
+//! `WWW-Authenticate` header whenever the session is created or renewed, the client keeps the
+//! latest one and notifies the listener given to [`ApiClient::on_token_change`] so it can be
+//! persisted.
+
+mod client;
+mod error;
+pub mod models;
+
+pub use client::{ApiClient, LoginOutcome, TokenListener};
+pub use error::{ApiError, ApiResult};
+pub use game_sync_common as common;
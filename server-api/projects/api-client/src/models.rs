@@ -0,0 +1,40 @@
+//! Types of the API. They come from `game_sync_common`, shared with the server, except the
+//! status of the server.
+
+use serde::{Deserialize, Serialize};
+
+pub use game_sync_common::{
+    auth::{OidcAuthorization, TwoFactorChallenge},
+    errors::{ErrorCode, FieldError, ProblemDetails},
+    games::{
+        BannerType, FacetCount, Game, GameBanner, GameBannerInput, GameDetails, GameFacets,
+        GameInput, GameMedia, GameMediaSet, GameQuery, GameSearchResults, GameTags, ImageFormat,
+        ImageVariant, MediaKind, Platform, SortBy, SortOrder, SystemRequirements,
+    },
+    pagination::{Paginated, PaginationMeta},
+    uploads::{DownloadUrl, PresignedUrl, UploadMode},
+    users::{User, UserRole},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationMode {
+    Closed,
+    Open,
+    InviteOnly,
+}
+
+/// Returned by `GET /`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub name: String,
+    pub version: String,
+    pub status: String,
+    pub configured: bool,
+    pub password_login: bool,
+    pub registration: RegistrationMode,
+    pub oidc: bool,
+    /// Last migration applied to the database, unset by older servers
+    #[serde(default)]
+    pub schema_version: Option<String>,
+}
@@ -1,9 +1,12 @@
 use crate::{
     bootstrap,
     core::{errors::AppError, setup},
+    middlewares::{metrics::HttpMetrics, request_id::RequestId},
+    routes, tasks,
 };
 use actix_web::{
     middleware::{Compress, Logger},
+    web::Data,
     App, HttpServer,
 };
 use tracing::info;
@@ -14,18 +17,53 @@ pub async fn run() -> Result<(), AppError> {
 
     // Load configuration
     let addrs = app_data.get_server_address()?;
+    let metrics_addrs = app_data.get_metrics_address()?;
+
+    // Start background tasks
+    tasks::spawn_tasks(&app_data);
 
     // Start server
     info!("Starting server at http://{}:{}", addrs.ip(), addrs.port());
 
-    HttpServer::new(move || {
+    let server_data = app_data.clone();
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(Logger::default())
+            .wrap(Logger::new(
+                r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}o"#,
+            ))
             .wrap(Compress::default())
-            .configure(|cfg| setup::server_setup(cfg, app_data.clone()))
+            .wrap(HttpMetrics::new(server_data.metrics.clone()))
+            .wrap(RequestId)
+            .configure(|cfg| setup::server_setup(cfg, server_data.clone()))
     })
     .bind(addrs)?
-    .run()
-    .await
-    .map_err(AppError::from)
+    .run();
+
+    let Some(metrics_addrs) = metrics_addrs else {
+        if app_data.config.server.metrics_token.is_none() {
+            info!("Metrics are disabled, set a metrics address or token to expose them");
+        }
+
+        return server.await.map_err(AppError::from);
+    };
+
+    // Metrics server, kept apart from the public routes
+    info!(
+        "Starting metrics server at http://{}:{}",
+        metrics_addrs.ip(),
+        metrics_addrs.port()
+    );
+
+    let metrics_server = HttpServer::new(move || {
+        App::new()
+            .app_data(Data::new(app_data.clone()))
+            .configure(routes::setup_metrics_routes)
+    })
+    .workers(1)
+    .bind(metrics_addrs)?
+    .run();
+
+    futures::try_join!(server, metrics_server)?;
+
+    Ok(())
 }
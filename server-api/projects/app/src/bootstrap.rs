@@ -1,9 +1,23 @@
+use std::{future::Future, time::Duration};
+
 use actix_multi_session::storage::RedisSessionStore;
 use tera::Tera;
 
 use crate::{
-    core::{config::AppConfig, database, errors::AppError, s3},
+    core::{
+        config::AppConfig,
+        database::{self, DbPool},
+        errors::AppError,
+        jobs::JobTracker,
+        mail,
+        metrics::{MeteredSessionStore, Metrics},
+        oidc,
+        rate_limit::RateLimiter,
+        s3::{self, S3Client},
+        webauthn,
+    },
     data::AppData,
+    repositories,
 };
 
 pub async fn init() -> Result<AppData, AppError> {
@@ -13,18 +27,51 @@ pub async fn init() -> Result<AppData, AppError> {
     // Initialize template engine (Tera)
     let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
 
+    // Initialize metrics, updated by the other services
+    let metrics = Metrics::new()?;
+
     // Initialize database
-    let pool = database::init_pool(&config.database.url).await?;
-    database::seed_database(&pool).await?;
+    let pool = init_database(&config).await?;
+
+    // The first admin is created through the setup endpoint, protected by this token
+    let setup_token = repositories::app::generate_setup_token(&pool).await?;
+    if let Some(token) = &setup_token {
+        tracing::warn!(
+            "The application is not configured yet, use this token to create the first admin: {}",
+            token
+        );
+    }
 
     // Initialize S3 client
-    let mut s3_client = s3::init_client(&config.storage).await?;
-    s3_client.prepare_bucket().await?;
+    let s3_client = init_storage(&config, &metrics).await?;
 
     // Initialize session middleware
-    let redis_store = RedisSessionStore::new(&config.redis.url)
-        .await
-        .map_err(AppError::from)?;
+    let redis_url = config.redis.url.as_str();
+    let redis_store = with_retries("redis", || async move {
+        RedisSessionStore::new(redis_url)
+            .await
+            .map_err(AppError::from)
+    })
+    .await?;
+    let session_store = MeteredSessionStore::new(redis_store, metrics.clone());
+
+    // Initialize mail transport
+    let mailer = mail::init_mailer(&config.mail)?;
+
+    // Initialize passkeys support
+    let webauthn = config
+        .webauthn
+        .as_ref()
+        .map(webauthn::init_webauthn)
+        .transpose()?;
+
+    // Initialize single sign-on, discovering the provider configuration
+    let oidc = match &config.oidc {
+        Some(oidc_config) => {
+            Some(with_retries("identity provider", || oidc::init_client(oidc_config)).await?)
+        }
+        None => None,
+    };
 
     // Create secret key
     let secret_key = config.server.secret_key.clone();
@@ -33,11 +80,75 @@ pub async fn init() -> Result<AppData, AppError> {
     let app_data = AppData {
         tera,
         db: pool,
-        session_store: redis_store,
+        session_store,
         config,
         secret_key,
         s3: s3_client,
+        mailer,
+        rate_limiter: RateLimiter::new(),
+        jobs: JobTracker::new(),
+        metrics,
+        webauthn,
+        oidc,
+        setup_token,
     };
 
     Ok(app_data)
 }
+
+/// Connect to the database and check its schema, shared with the commands
+pub async fn init_database(config: &AppConfig) -> Result<DbPool, AppError> {
+    let database_url = config.database.url.as_str();
+    let pool = with_retries("database", || database::init_pool(database_url)).await?;
+
+    database::migrate(&pool, config.database.auto_migrate).await?;
+
+    Ok(pool)
+}
+
+/// Connect to the bucket, creating it if needed, shared with the commands
+pub async fn init_storage(config: &AppConfig, metrics: &Metrics) -> Result<S3Client, AppError> {
+    let storage_config = &config.storage;
+
+    with_retries("storage", || async move {
+        let mut s3_client = s3::init_client(storage_config, metrics.clone()).await?;
+        s3_client.prepare_bucket().await?;
+        Ok(s3_client)
+    })
+    .await
+}
+
+/// Attempts to reach a dependency at startup, it may still be starting with the server
+const STARTUP_ATTEMPTS: u32 = 10;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Run `init` until it succeeds, waiting longer after each failed attempt
+async fn with_retries<T, F, Fut>(name: &str, mut init: F) -> Result<T, AppError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let mut delay = Duration::from_secs(1);
+    let mut attempt = 1;
+
+    loop {
+        match init().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < STARTUP_ATTEMPTS => {
+                tracing::warn!(
+                    "Failed to reach the {} (attempt {}/{}), retrying in {:?}: {}",
+                    name,
+                    attempt,
+                    STARTUP_ATTEMPTS,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Start the server, this is the default command
+    Serve,
+    /// Apply the pending database migrations
+    Migrate,
+    /// Create the first admin without going through the setup wizard
+    CreateAdmin {
+        #[arg(long)]
+        email: String,
+        /// Prefer the environment variable, arguments are visible to other processes
+        #[arg(long, env = "GAME_SYNC_ADMIN_PASSWORD", hide_env_values = true)]
+        password: String,
+    },
+    /// Set the password of a user
+    ResetPassword {
+        #[arg(long)]
+        email: String,
+        /// Prefer the environment variable, arguments are visible to other processes
+        #[arg(long, env = "GAME_SYNC_USER_PASSWORD", hide_env_values = true)]
+        password: String,
+    },
+    /// List the users of the instance
+    ListUsers,
+    /// Delete the files of the bucket which aren't referenced anymore
+    GcStorage {
+        /// Only list the files which would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write the content of the database to a JSON file, without the files of the bucket
+    Export {
+        /// File to write
+        path: PathBuf,
+    },
+    /// Load a JSON file written by `export` into an empty instance
+    Import {
+        /// File to read
+        path: PathBuf,
+    },
+    /// Load the configuration and report the errors, without starting the server
+    CheckConfig,
+}
@@ -0,0 +1,214 @@
+use std::{fs::OpenOptions, io::Write, path::Path};
+#[cfg(unix)]
+use std::{
+    fs::Permissions,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+};
+
+use validator::Validate;
+
+use crate::{
+    bootstrap,
+    core::{
+        audit::AuditContext,
+        config::AppConfig,
+        database,
+        errors::{AppError, AppResult},
+        mail,
+        metrics::Metrics,
+        webauthn,
+    },
+    models::{export::InstanceExport, user::UserPasswordInput},
+    repositories,
+};
+
+/// Apply the pending migrations, whatever `database.auto_migrate` is
+pub async fn migrate() -> AppResult<()> {
+    let config = AppConfig::from_env()?;
+    let pool = database::init_pool(&config.database.url).await?;
+
+    database::migrate(&pool, true).await?;
+
+    let version = database::schema_version(&pool).await?;
+    tracing::info!(
+        "Database schema is up to date ({})",
+        version.as_deref().unwrap_or("empty")
+    );
+
+    Ok(())
+}
+
+/// Create the first admin from the command line, only the database is needed
+pub async fn create_admin(email: &str, password: &str) -> AppResult<()> {
+    if !validator::validate_email(email) {
+        return Err(AppError::BadRequest("Invalid email".to_string()));
+    }
+
+    let input = UserPasswordInput {
+        password: password.to_owned(),
+    };
+    input
+        .validate()
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let config = AppConfig::from_env()?;
+    let pool = bootstrap::init_database(&config).await?;
+
+    let user = repositories::app::create_first_admin(&pool, email, &input.password).await?;
+
+    tracing::info!(
+        "Admin {} created, the application is now configured",
+        user.email
+    );
+
+    Ok(())
+}
+
+pub async fn reset_password(email: &str, password: &str) -> AppResult<()> {
+    let input = UserPasswordInput {
+        password: password.to_owned(),
+    };
+    input
+        .validate()
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let config = AppConfig::from_env()?;
+    let pool = bootstrap::init_database(&config).await?;
+
+    let user = repositories::user::get_user_from_email(&pool, &email.to_owned())
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    let user =
+        repositories::user::change_password(&pool, &AuditContext::system(), user, &input.password)
+            .await?;
+
+    tracing::info!("Password of {} changed", user.email);
+
+    Ok(())
+}
+
+pub async fn list_users() -> AppResult<()> {
+    let config = AppConfig::from_env()?;
+    let pool = bootstrap::init_database(&config).await?;
+
+    let users = repositories::user::get_users(&pool).await?;
+
+    println!(
+        "{:<6} {:<40} {:<10} {:<9} {:<9}",
+        "ID", "EMAIL", "ROLE", "VERIFIED", "DISABLED"
+    );
+    for user in &users {
+        println!(
+            "{:<6} {:<40} {:<10} {:<9} {:<9}",
+            user.id,
+            user.email,
+            format!("{:?}", user.role),
+            user.email_verified_at.is_some(),
+            user.is_disabled()
+        );
+    }
+
+    Ok(())
+}
+
+/// Same as the storage garbage collector task, run once
+pub async fn gc_storage(dry_run: bool) -> AppResult<()> {
+    let config = AppConfig::from_env()?;
+    let pool = bootstrap::init_database(&config).await?;
+    let s3 = bootstrap::init_storage(&config, &Metrics::new()?).await?;
+
+    let report =
+        repositories::storage::find_orphans(&pool, &s3, config.gc.grace_period_hours).await?;
+
+    for object in &report.orphans {
+        if dry_run {
+            println!("{} ({} bytes)", object.key, object.size);
+        } else {
+            s3.delete_file(&object.key).await?;
+        }
+    }
+
+    if dry_run {
+        for upload in &report.aborted_uploads {
+            println!("{} (aborted upload)", upload.key);
+        }
+    } else {
+        repositories::storage::resolve_pending_uploads(&pool, &report).await?;
+    }
+
+    let action = if dry_run { "Would delete" } else { "Deleted" };
+    tracing::info!(
+        "{} {} files ({} bytes) out of {}, {} uploads finished and {} aborted",
+        action,
+        report.orphans.len(),
+        report.orphans_size,
+        report.scanned,
+        report.finished_uploads.len(),
+        report.aborted_uploads.len()
+    );
+
+    Ok(())
+}
+
+pub async fn export(path: &Path) -> AppResult<()> {
+    let config = AppConfig::from_env()?;
+    let pool = bootstrap::init_database(&config).await?;
+
+    let export = repositories::export::export(&pool).await?;
+    let content = serde_json::to_vec_pretty(&export).map_err(|e| AppError::Other(e.into()))?;
+
+    // The export holds the password hashes and the two-factor secrets, only the owner can read it
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path)?;
+    // The mode is only applied when the file is created
+    #[cfg(unix)]
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    file.write_all(&content)?;
+
+    tracing::info!("Instance exported to {}", path.display());
+
+    Ok(())
+}
+
+pub async fn import(path: &Path) -> AppResult<()> {
+    let content = std::fs::read(path)?;
+    let export: InstanceExport =
+        serde_json::from_slice(&content).map_err(|e| AppError::Other(e.into()))?;
+
+    let config = AppConfig::from_env()?;
+    let pool = bootstrap::init_database(&config).await?;
+
+    repositories::export::import(&pool, &export).await?;
+
+    tracing::info!(
+        "Instance imported from {}, exported at {}",
+        path.display(),
+        export.exported_at
+    );
+
+    Ok(())
+}
+
+/// Check what can be checked without reaching the services, and print the effective
+/// configuration without its secrets
+pub async fn check_config() -> AppResult<()> {
+    let config = AppConfig::from_env()?;
+
+    mail::init_mailer(&config.mail)?;
+    if let Some(webauthn_config) = &config.webauthn {
+        webauthn::init_webauthn(webauthn_config)?;
+    }
+
+    let redacted =
+        serde_json::to_string_pretty(&config.redacted()).map_err(|e| AppError::Other(e.into()))?;
+    println!("{}", redacted);
+
+    tracing::info!("Configuration is valid");
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use actix_web::{web::Data, HttpResponse, Responder};
+use tera::Context;
+
+use crate::{
+    core::{audit::AuditContext, errors::AppResult, types::ValidatedJson},
+    data::AppData,
+    entities::{user::Model as UserModel, user_token::UserTokenKind},
+    models::account::{EmailInput, ResetPasswordInput, VerifyEmailInput},
+    repositories,
+};
+
+/// Create a token for the user and mail it in the background, so the response
+/// time doesn't tell whether an account exists for the email
+pub async fn send_token_mail(
+    app_data: Data<AppData>,
+    user: UserModel,
+    kind: UserTokenKind,
+) -> AppResult<()> {
+    let token =
+        repositories::user_tokens::create_token(&app_data.db, user.id, kind.clone()).await?;
+
+    let (subject, template, path) = match kind {
+        UserTokenKind::EmailVerification => ("Verify your email", "verify_email", "verify-email"),
+        UserTokenKind::PasswordReset => ("Reset your password", "reset_password", "reset-password"),
+    };
+
+    let mut context = Context::new();
+    context.insert(
+        "url",
+        &format!(
+            "{}/{}?token={}",
+            app_data.config.mail.public_url.trim_end_matches('/'),
+            path,
+            token
+        ),
+    );
+
+    actix_web::rt::spawn(async move {
+        if let Err(e) = app_data
+            .mailer
+            .send_template(&app_data.tera, &user.email, subject, template, &context)
+            .await
+        {
+            tracing::error!(
+                "Failed to send {} mail to user {}: {}",
+                template,
+                user.id,
+                e
+            );
+        }
+    });
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/verify-email",
+    tag = "Admin auth",
+    request_body = VerifyEmailInput,
+    responses(
+        (status = 200, description = "Verified user", body = UserModel),
+        (status = 400, description = "Invalid or expired token", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument("POST /admin/auth/verify-email", skip(input, app_data))]
+pub async fn verify_email(
+    input: ValidatedJson<VerifyEmailInput>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    let token = repositories::user_tokens::consume_token(
+        &app_data.db,
+        UserTokenKind::EmailVerification,
+        &input.token,
+    )
+    .await?;
+
+    let user = repositories::user::verify_email(&app_data.db, token.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/verify-email/resend",
+    tag = "Admin auth",
+    request_body = EmailInput,
+    responses(
+        (status = 204, description = "Sent if the address belongs to an unverified user"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument("POST /admin/auth/verify-email/resend", skip(input, app_data))]
+pub async fn resend_verification(
+    input: ValidatedJson<EmailInput>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    let user = repositories::user::get_user_from_email(&app_data.db, &input.email).await?;
+
+    if let Some(user) = user.filter(|user| user.email_verified_at.is_none()) {
+        send_token_mail(app_data, user, UserTokenKind::EmailVerification).await?;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Always succeeds, to avoid disclosing which emails have an account
+#[utoipa::path(
+    post,
+    path = "/admin/auth/forgot-password",
+    tag = "Admin auth",
+    request_body = EmailInput,
+    responses(
+        (status = 204, description = "Sent if the address belongs to a user"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument("POST /admin/auth/forgot-password", skip(input, app_data))]
+pub async fn forgot_password(
+    input: ValidatedJson<EmailInput>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    let user = repositories::user::get_user_from_email(&app_data.db, &input.email).await?;
+
+    if let Some(user) = user.filter(|user| !user.is_disabled()) {
+        send_token_mail(app_data, user, UserTokenKind::PasswordReset).await?;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/reset-password",
+    tag = "Admin auth",
+    request_body = ResetPasswordInput,
+    responses(
+        (status = 204, description = "Password changed, the sessions of the user are revoked"),
+        (status = 400, description = "Invalid or expired token", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument("POST /admin/auth/reset-password", skip(input, app_data, ctx))]
+pub async fn reset_password(
+    input: ValidatedJson<ResetPasswordInput>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let token = repositories::user_tokens::consume_token(
+        &app_data.db,
+        UserTokenKind::PasswordReset,
+        &input.token,
+    )
+    .await?;
+
+    repositories::user::reset_password(&app_data.db, &ctx, token.user_id, &input.password).await?;
+
+    // Other links sent before this reset must not be usable anymore
+    repositories::user_tokens::revoke_tokens(
+        &app_data.db,
+        token.user_id,
+        UserTokenKind::PasswordReset,
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
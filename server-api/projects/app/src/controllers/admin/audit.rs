@@ -0,0 +1,31 @@
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::{
+    core::{errors::AppResult, types::ValidatedQuery},
+    data::AppData,
+    models::{audit::AuditLogFilter, pagination::Pagination},
+    repositories,
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "Maintenance",
+    params(Pagination, AuditLogFilter),
+    responses(
+        (status = 200, description = "Audit log entries, the most recent first", body = PaginatedAuditLogs),
+        (status = 403, description = "Not an admin, or two-factor authentication required", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/audit", skip(app_data))]
+pub async fn get_audit_logs(
+    pagination_query: ValidatedQuery<Pagination>,
+    filter: ValidatedQuery<AuditLogFilter>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    let entries =
+        repositories::audit::paginate_audit_logs(&app_data.db, &pagination_query, &filter).await?;
+
+    Ok(HttpResponse::Ok().json(entries))
+}
@@ -5,43 +5,119 @@ use actix_web::{
 };
 
 use crate::{
+    controllers::admin::account,
     core::{
+        config::RegistrationMode,
         errors::{AppError, AppResult},
         types::ValidatedJson,
     },
     data::AppData,
-    models::user::{UserCreateInput, UserLoginRequest},
+    entities::user_token::UserTokenKind,
+    helpers::sessions,
+    models::{
+        two_factor::TwoFactorChallenge,
+        user::{UserCreateInput, UserLoginRequest},
+    },
     repositories,
 };
 
+#[utoipa::path(
+    post,
+    path = "/admin/auth/register",
+    tag = "Admin auth",
+    request_body = UserCreateInput,
+    responses(
+        (status = 201, description = "Registered user, the email address must be verified before logging in", body = UserModel),
+        (status = 400, description = "Invalid or expired invitation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Registration is disabled", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 pub async fn register(
     form_data: ValidatedJson<UserCreateInput>,
     app_data: Data<AppData>,
-    session: Session,
 ) -> AppResult<impl Responder> {
-    let user = repositories::user::create_user(&app_data.db, &form_data).await?;
+    if !app_data.config.enable_password_login {
+        return Err(AppError::PasswordLoginDisabled);
+    }
 
-    // Create a session for the user
-    session.insert("user_id", user.id)?;
+    let user = match (
+        app_data.config.registration_mode(),
+        &form_data.invitation_code,
+    ) {
+        (RegistrationMode::Closed, _) => return Err(AppError::RegistrationDisabled),
+        (RegistrationMode::InviteOnly, None) => return Err(AppError::InvalidInvitation),
+        (_, Some(code)) => {
+            repositories::invitations::register_with_invitation(&app_data.db, &form_data, code)
+                .await?
+        }
+        (RegistrationMode::Open, None) => {
+            repositories::user::create_user(&app_data.db, &form_data).await?
+        }
+    };
+
+    // The user will be able to login once its email is verified
+    account::send_token_mail(app_data, user.clone(), UserTokenKind::EmailVerification).await?;
 
     // Return the user
-    Ok(HttpResponse::Ok().json(user))
+    Ok(HttpResponse::Created().json(user))
 }
 
+#[utoipa::path(
+    post,
+    path = "/admin/auth",
+    tag = "Admin auth",
+    operation_id = "admin_login",
+    request_body = UserLoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = UserModel),
+        (status = 202, description = "The code of the second factor is required", body = TwoFactorChallenge),
+        (status = 401, description = "Invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Email address not verified, account disabled or password login disabled", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 pub async fn login(
     form_data: ValidatedJson<UserLoginRequest>,
     app_data: web::Data<AppData>,
     session: Session,
 ) -> AppResult<impl Responder> {
-    let user = repositories::user::login(&app_data.db, &form_data).await?;
+    if !app_data.config.enable_password_login {
+        return Err(AppError::PasswordLoginDisabled);
+    }
+
+    let user = app_data.metrics.track_login(
+        "password",
+        repositories::user::login(&app_data.db, &form_data).await,
+    )?;
+
+    // The user will only be logged in once the second factor is validated
+    if user.has_two_factor() {
+        session.insert("two_factor_user_id", user.id)?;
+
+        return Ok(HttpResponse::Accepted().json(TwoFactorChallenge {
+            two_factor_required: true,
+        }));
+    }
 
     // Create a session for the user
-    session.insert("user_id", user.id)?;
+    sessions::log_in(&session, &user)?;
 
     // Return the user
     Ok(HttpResponse::Ok().json(user))
 }
 
+#[utoipa::path(
+    get,
+    path = "/admin/auth",
+    tag = "Admin auth",
+    operation_id = "admin_me",
+    responses(
+        (status = 200, description = "Current user", body = UserModel),
+        (status = 401, description = "Not logged in", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
 #[tracing::instrument("GET /admin/auth/me", skip(session, app_data))]
 pub async fn me(session: Session, app_data: web::Data<AppData>) -> AppResult<impl Responder> {
     let user_id = session.get::<i32>("user_id")?;
@@ -63,6 +139,14 @@ pub async fn me(session: Session, app_data: web::Data<AppData>) -> AppResult<imp
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/admin/auth",
+    tag = "Admin auth",
+    operation_id = "admin_logout",
+    responses((status = 200, description = "Logged out")),
+    security(("session_cookie" = []))
+)]
 #[tracing::instrument("DELETE /admin/auth", skip(session))]
 pub async fn logout(session: Session) -> AppResult<impl Responder> {
     session.purge();
@@ -0,0 +1,41 @@
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::{
+    core::{
+        audit::AuditContext,
+        errors::{AppError, AppResult},
+        types::ValidatedPath,
+    },
+    data::AppData,
+    models::{admin::JobStarted, games::GameViewPath},
+    repositories, tasks,
+};
+
+/// Purge a deleted game without waiting for the end of its restore window
+#[utoipa::path(
+    delete,
+    path = "/admin/games/{id}",
+    tag = "Maintenance",
+    params(GameViewPath),
+    responses(
+        (status = 202, description = "Purge job started", body = JobStarted),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("DELETE /admin/games/{id}", skip(app_data, ctx))]
+pub async fn purge_game(
+    path: ValidatedPath<GameViewPath>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let game = repositories::games::get_deleted_game(&app_data.db, path.id)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    let job_id = tasks::game_purge::start(app_data.get_ref().clone(), game, ctx).ok_or(
+        AppError::AlreadyExists("The game is already being purged".to_string()),
+    )?;
+
+    Ok(HttpResponse::Accepted().json(JobStarted { job_id }))
+}
@@ -0,0 +1,74 @@
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::{
+    core::{
+        audit::AuditContext,
+        errors::AppResult,
+        types::{ValidatedJson, ValidatedPath},
+    },
+    data::AppData,
+    models::invitation::{InvitationCreateInput, InvitationCreated, InvitationViewPath},
+    repositories,
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/invitations",
+    tag = "Invitations",
+    responses(
+        (status = 200, description = "Invitations", body = [InvitationModel]),
+        (status = 403, description = "Not an admin, or two-factor authentication required", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/invitations", skip(app_data))]
+pub async fn get_invitations(app_data: Data<AppData>) -> AppResult<impl Responder> {
+    let invitations = repositories::invitations::get_invitations(&app_data.db).await?;
+
+    Ok(HttpResponse::Ok().json(invitations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/invitations",
+    tag = "Invitations",
+    request_body = InvitationCreateInput,
+    responses(
+        (status = 201, description = "Created invitation, with its code", body = InvitationCreated),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("POST /admin/invitations", skip(app_data, ctx))]
+pub async fn create_invitation(
+    input: ValidatedJson<InvitationCreateInput>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let (invitation, code) =
+        repositories::invitations::create_invitation(&app_data.db, &ctx, &input).await?;
+
+    Ok(HttpResponse::Created().json(InvitationCreated { invitation, code }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/invitations/{id}",
+    tag = "Invitations",
+    params(InvitationViewPath),
+    responses(
+        (status = 204, description = "Invitation deleted"),
+        (status = 404, description = "Invitation not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("DELETE /admin/invitations/{id}", skip(app_data, ctx))]
+pub async fn delete_invitation(
+    path: ValidatedPath<InvitationViewPath>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    repositories::invitations::delete_invitation(&app_data.db, &ctx, path.id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
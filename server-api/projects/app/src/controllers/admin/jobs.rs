@@ -0,0 +1,18 @@
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::data::AppData;
+
+#[utoipa::path(
+    get,
+    path = "/admin/jobs",
+    tag = "Maintenance",
+    responses(
+        (status = 200, description = "Running and recently finished jobs", body = [JobProgress]),
+        (status = 403, description = "Not an admin, or two-factor authentication required", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/jobs", skip(app_data))]
+pub async fn get_jobs(app_data: Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(app_data.jobs.list())
+}
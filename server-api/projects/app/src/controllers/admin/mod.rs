@@ -1,2 +1,15 @@
+pub mod account;
 pub mod admin;
+pub mod audit;
 pub mod auth;
+pub mod games;
+pub mod invitations;
+pub mod jobs;
+pub mod oidc;
+pub mod passkeys;
+pub mod profile;
+pub mod rate_limits;
+pub mod setup;
+pub mod storage;
+pub mod two_factor;
+pub mod users;
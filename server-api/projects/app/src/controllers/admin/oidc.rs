@@ -0,0 +1,86 @@
+use actix_multi_session::Session;
+use actix_web::{
+    web::{Data, Redirect},
+    Responder,
+};
+
+use crate::{
+    core::{
+        errors::{AppError, AppResult},
+        oidc::OidcState,
+        types::ValidatedQuery,
+    },
+    data::AppData,
+    helpers::sessions,
+    models::oidc::OidcCallbackInput,
+    repositories,
+};
+
+/// Redirect the user to the login page of the identity provider
+#[utoipa::path(
+    get,
+    path = "/admin/auth/oidc",
+    tag = "Admin auth",
+    operation_id = "admin_oidc_authorize",
+    responses((status = 307, description = "Redirect to the login page of the identity provider"))
+)]
+#[tracing::instrument("GET /admin/auth/oidc", skip(app_data, session))]
+pub async fn authorize(app_data: Data<AppData>, session: Session) -> AppResult<impl Responder> {
+    let oidc = app_data.oidc.as_ref().ok_or(AppError::NotFoundError)?;
+
+    let (authorization_url, state) = oidc.authorize(None);
+
+    session.insert("oidc_state", state)?;
+
+    Ok(Redirect::to(authorization_url).temporary())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/auth/oidc/callback",
+    tag = "Admin auth",
+    operation_id = "admin_oidc_callback",
+    params(OidcCallbackInput),
+    responses(
+        (status = 307, description = "Logged in, redirect to the panel, or to its login page when the code of the second factor is required"),
+        (status = 400, description = "Invalid state or code", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Email address not verified by the identity provider, or account disabled", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument("GET /admin/auth/oidc/callback", skip(query, app_data, session))]
+pub async fn callback(
+    query: ValidatedQuery<OidcCallbackInput>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let (Some(oidc), Some(oidc_config)) = (&app_data.oidc, &app_data.config.oidc) else {
+        return Err(AppError::NotFoundError);
+    };
+
+    let state = session
+        .remove_as::<OidcState>("oidc_state")
+        .and_then(Result::ok)
+        .ok_or(AppError::Unauthorized)?;
+
+    let identity = oidc
+        .exchange(query.code.clone(), &query.state, state)
+        .await?;
+
+    let user = app_data.metrics.track_login(
+        "oidc",
+        repositories::user::login_oidc(&app_data.db, oidc_config, &identity).await,
+    )?;
+
+    // The user will only be logged in once the second factor is validated
+    if user.has_two_factor() {
+        session.insert("two_factor_user_id", user.id)?;
+
+        return Ok(Redirect::to("/login?two_factor=required").temporary());
+    }
+
+    // Create a session for the user
+    sessions::log_in(&session, &user)?;
+
+    // Go back to the panel
+    Ok(Redirect::to("/").temporary())
+}
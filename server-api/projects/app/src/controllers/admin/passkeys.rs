@@ -0,0 +1,545 @@
+use std::sync::Arc;
+
+use actix_multi_session::Session;
+use actix_web::{
+    web::{Data, Json, ReqData},
+    HttpResponse, Responder,
+};
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential, RequestChallengeResponse,
+    Webauthn,
+};
+
+use crate::{
+    core::{
+        database::DbPool,
+        errors::{AppError, AppResult},
+        types::{ValidatedJson, ValidatedPath},
+    },
+    data::AppData,
+    entities::user::Model as UserModel,
+    helpers::sessions,
+    models::passkey::{PasskeyLoginStartInput, PasskeyPath, PasskeyRegisterInput},
+    repositories,
+};
+
+/// Passkeys routes are not available when WebAuthn is not configured
+fn get_webauthn(app_data: &AppData) -> AppResult<&Arc<Webauthn>> {
+    app_data.webauthn.as_ref().ok_or(AppError::NotFoundError)
+}
+
+/// Stable WebAuthn user handle, shared by all the passkeys of the user
+fn user_handle(user: &UserModel) -> Uuid {
+    Uuid::from_u128(user.id as u128)
+}
+
+/// Pending login stored in the session, `None` when the challenge can't be answered
+type PendingAuthentication = Option<(i32, PasskeyAuthentication)>;
+
+/// Start the login of the user owning the email. The unknown emails and the users without
+/// passkeys get a challenge too, without allowed credentials, so the accounts can't be enumerated.
+async fn start_login(
+    webauthn: &Webauthn,
+    db: &DbPool,
+    session: &Session,
+    email: &String,
+) -> AppResult<RequestChallengeResponse> {
+    let user = repositories::user::get_user_from_email(db, email).await?;
+
+    let passkeys = match &user {
+        Some(user) => repositories::passkeys::get_user_passkeys(db, user.id).await?,
+        None => Vec::new(),
+    };
+    let credentials = repositories::passkeys::to_credentials(&passkeys)?;
+
+    match user {
+        Some(user) if !credentials.is_empty() => {
+            let (challenge, authentication) =
+                webauthn.start_passkey_authentication(&credentials)?;
+
+            let pending: PendingAuthentication = Some((user.id, authentication));
+            session.insert("passkey_authentication", pending)?;
+
+            Ok(challenge)
+        }
+        _ => {
+            let (mut challenge, _) = webauthn.start_discoverable_authentication()?;
+            // Requested like the other challenges, not through the autofill of the browser
+            challenge.mediation = None;
+
+            let pending: PendingAuthentication = None;
+            session.insert("passkey_authentication", pending)?;
+
+            Ok(challenge)
+        }
+    }
+}
+
+/// Check the assertion against the pending login, then store the updated credential
+async fn finish_login(
+    webauthn: &Webauthn,
+    db: &DbPool,
+    session: &Session,
+    credential: &PublicKeyCredential,
+) -> AppResult<UserModel> {
+    let pending = session.get::<PendingAuthentication>("passkey_authentication")?;
+    session.remove("passkey_authentication");
+
+    let (user_id, authentication) = pending.flatten().ok_or(AppError::Unauthorized)?;
+
+    let result = webauthn
+        .finish_passkey_authentication(credential, &authentication)
+        .map_err(|e| {
+            tracing::debug!("Passkey authentication failed: {}", e);
+            AppError::Unauthorized
+        })?;
+
+    let user = repositories::user::get_user_from_id(db, user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    if user.is_disabled() {
+        return Err(AppError::AccountDisabled);
+    }
+
+    if user.email_verified_at.is_none() {
+        return Err(AppError::EmailNotVerified);
+    }
+
+    let passkeys = repositories::passkeys::get_user_passkeys(db, user.id).await?;
+    repositories::passkeys::update_passkey_usage(db, passkeys, &result).await?;
+
+    Ok(user)
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/auth/passkeys",
+    tag = "Passkeys",
+    responses(
+        (status = 200, description = "Passkeys of the current user", body = [PasskeyModel]),
+        (status = 401, description = "Not logged in", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/auth/passkeys", skip(user, app_data))]
+pub async fn get_passkeys(
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    let passkeys = repositories::passkeys::get_user_passkeys(&app_data.db, user.id).await?;
+
+    Ok(HttpResponse::Ok().json(passkeys))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/passkeys/register/start",
+    tag = "Passkeys",
+    responses(
+        (status = 200, description = "WebAuthn creation challenge", body = Object),
+        (status = 401, description = "Not logged in", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument(
+    "POST /admin/auth/passkeys/register/start",
+    skip(user, app_data, session)
+)]
+pub async fn start_registration(
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let webauthn = get_webauthn(&app_data)?;
+
+    // Prevent the same authenticator from being registered twice
+    let passkeys = repositories::passkeys::get_user_passkeys(&app_data.db, user.id).await?;
+    let exclude_credentials = repositories::passkeys::to_credentials(&passkeys)?
+        .iter()
+        .map(|credential| credential.cred_id().clone())
+        .collect();
+
+    let (challenge, registration) = webauthn.start_passkey_registration(
+        user_handle(&user),
+        &user.email,
+        &user.email,
+        Some(exclude_credentials),
+    )?;
+
+    session.insert("passkey_registration", registration)?;
+
+    Ok(HttpResponse::Ok().json(challenge))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/passkeys/register/finish",
+    tag = "Passkeys",
+    request_body = PasskeyRegisterInput,
+    responses(
+        (status = 200, description = "Registered passkey", body = PasskeyModel),
+        (status = 400, description = "Invalid credential or no pending registration", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument(
+    "POST /admin/auth/passkeys/register/finish",
+    skip(input, user, app_data, session)
+)]
+pub async fn finish_registration(
+    input: ValidatedJson<PasskeyRegisterInput>,
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let webauthn = get_webauthn(&app_data)?;
+
+    let registration = session
+        .get::<PasskeyRegistration>("passkey_registration")?
+        .ok_or(AppError::BadRequest(
+            "Passkey registration has not been started".to_string(),
+        ))?;
+
+    session.remove("passkey_registration");
+
+    let credential = webauthn.finish_passkey_registration(&input.credential, &registration)?;
+
+    let passkey =
+        repositories::passkeys::create_passkey(&app_data.db, user.id, &input.name, &credential)
+            .await?;
+
+    Ok(HttpResponse::Ok().json(passkey))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/auth/passkeys/{id}",
+    tag = "Passkeys",
+    params(PasskeyPath),
+    responses(
+        (status = 204, description = "Passkey deleted"),
+        (status = 404, description = "Passkey not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("DELETE /admin/auth/passkeys/{id}", skip(user, app_data))]
+pub async fn delete_passkey(
+    path: ValidatedPath<PasskeyPath>,
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    repositories::passkeys::delete_passkey(&app_data.db, user.id, path.id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/passkeys/login/start",
+    tag = "Passkeys",
+    request_body = PasskeyLoginStartInput,
+    responses(
+        (status = 200, description = "WebAuthn request challenge, also returned for the unknown emails", body = Object),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    "POST /admin/auth/passkeys/login/start",
+    skip(input, app_data, session)
+)]
+pub async fn start_authentication(
+    input: ValidatedJson<PasskeyLoginStartInput>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let webauthn = get_webauthn(&app_data)?;
+
+    let challenge = start_login(webauthn, &app_data.db, &session, &input.email).await?;
+
+    Ok(HttpResponse::Ok().json(challenge))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/passkeys/login/finish",
+    tag = "Passkeys",
+    request_body(content = Object, description = "WebAuthn assertion returned by the authenticator"),
+    responses(
+        (status = 200, description = "Logged in", body = UserModel),
+        (status = 401, description = "Invalid credential or no pending login", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Email address not verified or account disabled", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    "POST /admin/auth/passkeys/login/finish",
+    skip(credential, app_data, session)
+)]
+pub async fn finish_authentication(
+    credential: Json<PublicKeyCredential>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let webauthn = get_webauthn(&app_data)?;
+
+    let user = app_data.metrics.track_login(
+        "passkey",
+        finish_login(webauthn, &app_data.db, &session, &credential).await,
+    )?;
+
+    // A passkey already proves both possession and user verification, no second factor is needed
+    sessions::log_in(&session, &user)?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_multi_session::{Session, SessionExt};
+    use actix_web::test::TestRequest;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use time::OffsetDateTime;
+    use webauthn_authenticator_rs::{softpasskey::SoftPasskey, WebauthnAuthenticator};
+    use webauthn_rs::prelude::{PublicKeyCredential, Url, Webauthn};
+
+    use super::{finish_login, start_login, PendingAuthentication};
+    use crate::{
+        core::{config::WebauthnConfig, errors::AppError, webauthn::init_webauthn},
+        entities::{
+            passkey::Model as PasskeyModel,
+            user::{Model as UserModel, UserRole},
+        },
+    };
+
+    const ORIGIN: &str = "https://game-sync.example.com";
+
+    fn webauthn() -> Arc<Webauthn> {
+        let config = WebauthnConfig {
+            rp_id: "game-sync.example.com".to_owned(),
+            rp_origin: ORIGIN.to_owned(),
+            rp_name: "Game Sync".to_owned(),
+        };
+
+        init_webauthn(&config).unwrap()
+    }
+
+    fn session() -> Session {
+        TestRequest::default().to_http_request().get_session()
+    }
+
+    fn user(id: i32, email_verified: bool) -> UserModel {
+        let now = OffsetDateTime::now_utc();
+
+        UserModel {
+            id,
+            email: format!("admin{}@example.com", id),
+            password: "hash".to_owned(),
+            role: UserRole::Admin,
+            totp_secret: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            sessions_valid_after: None,
+            oidc_subject: None,
+            email_verified_at: email_verified.then_some(now),
+            disabled_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Register a passkey of the authenticator, as stored in the database
+    fn register(
+        webauthn: &Webauthn,
+        authenticator: &mut WebauthnAuthenticator<SoftPasskey>,
+        user: &UserModel,
+    ) -> PasskeyModel {
+        let (challenge, registration) = webauthn
+            .start_passkey_registration(super::user_handle(user), &user.email, &user.email, None)
+            .unwrap();
+
+        let credential = authenticator
+            .do_registration(Url::parse(ORIGIN).unwrap(), challenge)
+            .unwrap();
+        let passkey = webauthn
+            .finish_passkey_registration(&credential, &registration)
+            .unwrap();
+
+        PasskeyModel {
+            id: user.id,
+            user_id: user.id,
+            name: "Security key".to_owned(),
+            credential: serde_json::to_value(&passkey).unwrap(),
+            last_used_at: None,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Start the login of the user, and answer the challenge with the authenticator
+    async fn authenticate(
+        webauthn: &Webauthn,
+        session: &Session,
+        authenticator: &mut WebauthnAuthenticator<SoftPasskey>,
+        user: &UserModel,
+        passkey: &PasskeyModel,
+    ) -> PublicKeyCredential {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![user.clone()]])
+            .append_query_results([vec![passkey.clone()]])
+            .into_connection();
+
+        let challenge = start_login(webauthn, &db, session, &user.email)
+            .await
+            .unwrap();
+
+        authenticator
+            .do_authentication(Url::parse(ORIGIN).unwrap(), challenge)
+            .unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn login_challenge_is_stored_in_the_session() {
+        let webauthn = webauthn();
+        let user = user(1, true);
+        let passkey = register(
+            &webauthn,
+            &mut WebauthnAuthenticator::new(SoftPasskey::new()),
+            &user,
+        );
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![user.clone()]])
+            .append_query_results([vec![passkey]])
+            .into_connection();
+        let session = session();
+
+        let challenge = start_login(&webauthn, &db, &session, &user.email)
+            .await
+            .unwrap();
+
+        let challenge = serde_json::to_value(challenge).unwrap();
+        assert_eq!(
+            challenge["publicKey"]["allowCredentials"]
+                .as_array()
+                .map(Vec::len),
+            Some(1)
+        );
+
+        let pending = session
+            .get::<PendingAuthentication>("passkey_authentication")
+            .unwrap()
+            .flatten();
+        assert_eq!(pending.map(|(user_id, _)| user_id), Some(user.id));
+    }
+
+    #[actix_rt::test]
+    async fn unknown_emails_get_a_challenge() {
+        let webauthn = webauthn();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<UserModel>::new()])
+            .into_connection();
+        let session = session();
+
+        let challenge = start_login(&webauthn, &db, &session, &"unknown@example.com".to_owned())
+            .await
+            .unwrap();
+
+        let challenge = serde_json::to_value(challenge).unwrap();
+        assert!(challenge["publicKey"]["challenge"].is_string());
+        assert!(challenge["publicKey"]["allowCredentials"]
+            .as_array()
+            .map_or(true, Vec::is_empty));
+
+        // A login is pending, but nothing can finish it
+        let pending = session
+            .get::<PendingAuthentication>("passkey_authentication")
+            .unwrap();
+        assert!(matches!(pending, Some(None)));
+    }
+
+    #[actix_rt::test]
+    async fn login_updates_the_passkey() {
+        let webauthn = webauthn();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new());
+        let user = user(1, true);
+        let passkey = register(&webauthn, &mut authenticator, &user);
+        let session = session();
+
+        let credential =
+            authenticate(&webauthn, &session, &mut authenticator, &user, &passkey).await;
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![user.clone()]])
+            .append_query_results([vec![passkey.clone()]])
+            .append_query_results([vec![passkey]])
+            .into_connection();
+
+        let logged_in = finish_login(&webauthn, &db, &session, &credential)
+            .await
+            .unwrap();
+        assert_eq!(logged_in, user);
+
+        // The credential and its usage are stored, and the challenge can't be used twice
+        let log = db.into_transaction_log();
+        let update = &log[2].statements()[0];
+        assert!(update.sql.starts_with(r#"UPDATE "passkey""#));
+        assert!(update.sql.contains(r#""credential""#));
+        assert!(update.sql.contains(r#""last_used_at""#));
+
+        assert!(session
+            .get::<PendingAuthentication>("passkey_authentication")
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn login_rejects_a_foreign_credential() {
+        let webauthn = webauthn();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new());
+        let mut other_authenticator = WebauthnAuthenticator::new(SoftPasskey::new());
+        let other = user(2, true);
+        let user = user(1, true);
+        let passkey = register(&webauthn, &mut authenticator, &user);
+        let other_passkey = register(&webauthn, &mut other_authenticator, &other);
+
+        // The user starts a login, another account answers with its own passkey
+        let other_session = session();
+        let session = session();
+        authenticate(&webauthn, &session, &mut authenticator, &user, &passkey).await;
+        let credential = authenticate(
+            &webauthn,
+            &other_session,
+            &mut other_authenticator,
+            &other,
+            &other_passkey,
+        )
+        .await;
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let result = finish_login(&webauthn, &db, &session, &credential).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+        assert!(db.into_transaction_log().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn login_requires_a_verified_email() {
+        let webauthn = webauthn();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new());
+        let user = user(1, false);
+        let passkey = register(&webauthn, &mut authenticator, &user);
+        let session = session();
+
+        let credential =
+            authenticate(&webauthn, &session, &mut authenticator, &user, &passkey).await;
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![user]])
+            .into_connection();
+
+        let result = finish_login(&webauthn, &db, &session, &credential).await;
+        assert!(matches!(result, Err(AppError::EmailNotVerified)));
+    }
+}
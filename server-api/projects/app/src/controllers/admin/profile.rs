@@ -0,0 +1,102 @@
+use actix_multi_session::Session;
+use actix_web::{
+    web::{Data, ReqData},
+    HttpResponse, Responder,
+};
+
+use crate::{
+    controllers::admin::account,
+    core::{
+        audit::AuditContext,
+        errors::{AppError, AppResult},
+        types::ValidatedJson,
+    },
+    data::AppData,
+    entities::{user::Model as UserModel, user_token::UserTokenKind},
+    helpers::{hashing, sessions},
+    models::user::{ProfilePasswordInput, ProfileUpdateInput},
+    repositories,
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/profile",
+    tag = "Profile",
+    responses(
+        (status = 200, description = "Current user", body = UserModel),
+        (status = 401, description = "Not logged in", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/profile", skip(user))]
+pub async fn get_profile(user: ReqData<UserModel>) -> impl Responder {
+    HttpResponse::Ok().json(user.into_inner())
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/profile",
+    tag = "Profile",
+    request_body = ProfileUpdateInput,
+    responses(
+        (status = 200, description = "Updated user, a new email address must be verified", body = UserModel),
+        (status = 409, description = "Email address already used", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("PUT /admin/profile", skip(input, user, app_data, ctx))]
+pub async fn update_profile(
+    input: ValidatedJson<ProfileUpdateInput>,
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let user =
+        repositories::user::update_email(&app_data.db, &ctx, user.into_inner(), &input.email)
+            .await?;
+
+    // The new address must be confirmed before the next login
+    if user.email_verified_at.is_none() {
+        account::send_token_mail(app_data, user.clone(), UserTokenKind::EmailVerification).await?;
+    }
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/profile/password",
+    tag = "Profile",
+    request_body = ProfilePasswordInput,
+    responses(
+        (status = 200, description = "Password changed, the other sessions are revoked", body = UserModel),
+        (status = 400, description = "Invalid current password", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument(
+    "PUT /admin/profile/password",
+    skip(input, user, app_data, session, ctx)
+)]
+pub async fn change_password(
+    input: ValidatedJson<ProfilePasswordInput>,
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    session: Session,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    if !hashing::verify_password(&user.password, &input.current_password)? {
+        return Err(AppError::BadRequest("Invalid password".to_string()));
+    }
+
+    let user =
+        repositories::user::change_password(&app_data.db, &ctx, user.into_inner(), &input.password)
+            .await?;
+
+    // The other sessions are revoked, this one stays logged in
+    sessions::log_in(&session, &user)?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
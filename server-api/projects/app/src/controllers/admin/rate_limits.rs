@@ -0,0 +1,18 @@
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::data::AppData;
+
+#[utoipa::path(
+    get,
+    path = "/admin/rate-limits",
+    tag = "Maintenance",
+    responses(
+        (status = 200, description = "Rate limiting counters", body = [RateLimitCounter]),
+        (status = 403, description = "Not an admin, or two-factor authentication required", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/rate-limits", skip(app_data))]
+pub async fn get_counters(app_data: Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(app_data.rate_limiter.counters())
+}
@@ -0,0 +1,51 @@
+use actix_multi_session::Session;
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::{
+    core::{
+        errors::{AppError, AppResult},
+        types::ValidatedJson,
+    },
+    data::AppData,
+    helpers::{hashing, sessions},
+    models::setup::SetupInput,
+    repositories,
+};
+
+/// Create the first admin of the application, and log it in
+#[utoipa::path(
+    post,
+    path = "/admin/setup",
+    tag = "Setup",
+    request_body = SetupInput,
+    responses(
+        (status = 201, description = "First admin created and logged in", body = UserModel),
+        (status = 400, description = "Invalid token, or the server is already configured", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument("POST /admin/setup", skip(input, app_data, session))]
+pub async fn setup(
+    input: ValidatedJson<SetupInput>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let setup_token = app_data
+        .setup_token
+        .as_ref()
+        .ok_or(AppError::AlreadyExists(
+            "The application is already configured".to_string(),
+        ))?;
+
+    // Compare the hashes so the comparison time doesn't depend on the token
+    if hashing::hash_token(setup_token) != hashing::hash_token(&input.token) {
+        return Err(AppError::Unauthorized);
+    }
+
+    let user =
+        repositories::app::create_first_admin(&app_data.db, &input.email, &input.password).await?;
+
+    sessions::log_in(&session, &user)?;
+
+    Ok(HttpResponse::Created().json(user))
+}
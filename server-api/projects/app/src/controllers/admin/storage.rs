@@ -0,0 +1,50 @@
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::{
+    core::errors::{AppError, AppResult},
+    data::AppData,
+    models::admin::JobStarted,
+    repositories, tasks,
+};
+
+/// Dry-run of the garbage collector: list the files which would be deleted
+#[utoipa::path(
+    get,
+    path = "/admin/storage/gc",
+    tag = "Maintenance",
+    responses(
+        (status = 200, description = "Dry-run of the garbage collector", body = GcReport),
+        (status = 403, description = "Not an admin, or two-factor authentication required", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/storage/gc", skip(app_data))]
+pub async fn get_gc_report(app_data: Data<AppData>) -> AppResult<impl Responder> {
+    let report = repositories::storage::find_orphans(
+        &app_data.db,
+        &app_data.s3,
+        app_data.config.gc.grace_period_hours,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/storage/gc",
+    tag = "Maintenance",
+    responses(
+        (status = 202, description = "Garbage collector job started", body = JobStarted),
+        (status = 403, description = "Not an admin, or two-factor authentication required", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("POST /admin/storage/gc", skip(app_data))]
+pub async fn run_gc(app_data: Data<AppData>) -> AppResult<impl Responder> {
+    let job_id = tasks::storage_gc::start(app_data.get_ref().clone()).ok_or(
+        AppError::AlreadyExists("The garbage collector is already running".to_string()),
+    )?;
+
+    Ok(HttpResponse::Accepted().json(JobStarted { job_id }))
+}
@@ -0,0 +1,216 @@
+use actix_multi_session::Session;
+use actix_web::{
+    web::{Data, ReqData},
+    HttpResponse, Responder,
+};
+
+use crate::{
+    core::{
+        errors::{AppError, AppResult},
+        types::ValidatedJson,
+    },
+    data::AppData,
+    entities::user::Model as UserModel,
+    helpers::{sessions, two_factor},
+    models::two_factor::{RecoveryCodesResponse, TwoFactorCodeInput, TwoFactorEnrollment},
+    repositories,
+};
+
+/// Second step of the login, the session only holds the id of the user who validated its password
+#[utoipa::path(
+    post,
+    path = "/admin/auth/two-factor",
+    tag = "Two-factor",
+    request_body = TwoFactorCodeInput,
+    responses(
+        (status = 200, description = "Logged in", body = UserModel),
+        (status = 401, description = "Invalid code or no pending login", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument("POST /admin/auth/two-factor", skip(input, app_data, session))]
+pub async fn verify(
+    input: ValidatedJson<TwoFactorCodeInput>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let user_id = session
+        .get::<i32>("two_factor_user_id")?
+        .ok_or(AppError::Unauthorized)?;
+
+    let user = repositories::user::get_user_from_id(&app_data.db, user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let is_valid = repositories::two_factor::verify_code(
+        &app_data.db,
+        &app_data.config.two_factor,
+        &app_data.secret_key,
+        &user,
+        &input.code,
+    )
+    .await?;
+
+    if !is_valid {
+        app_data.metrics.login_failed("two_factor");
+        return Err(AppError::Unauthorized);
+    }
+
+    // Create a session for the user
+    session.remove("two_factor_user_id");
+    sessions::log_in(&session, &user)?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Generate a new secret, which is only saved once a first code is validated
+#[utoipa::path(
+    post,
+    path = "/admin/auth/two-factor/enroll",
+    tag = "Two-factor",
+    responses(
+        (status = 200, description = "Secret to add to an authenticator app", body = TwoFactorEnrollment),
+        (status = 401, description = "Not logged in", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("POST /admin/auth/two-factor/enroll", skip(user, app_data, session))]
+pub async fn enroll(
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    if user.has_two_factor() {
+        return Err(AppError::AlreadyExists(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let secret = two_factor::generate_secret();
+    let totp = two_factor::build_totp(&secret, &app_data.config.two_factor.issuer, &user.email)?;
+
+    session.insert("two_factor_secret", &secret)?;
+
+    Ok(HttpResponse::Ok().json(TwoFactorEnrollment {
+        provisioning_uri: totp.get_url(),
+        qr_code: totp.get_qr_base64().map_err(AppError::TotpError)?,
+        secret,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/two-factor/enable",
+    tag = "Two-factor",
+    request_body = TwoFactorCodeInput,
+    responses(
+        (status = 200, description = "Recovery codes, only returned once", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument(
+    "POST /admin/auth/two-factor/enable",
+    skip(input, user, app_data, session)
+)]
+pub async fn enable(
+    input: ValidatedJson<TwoFactorCodeInput>,
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let secret = session
+        .get::<String>("two_factor_secret")?
+        .ok_or(AppError::BadRequest(
+            "Two-factor enrollment has not been started".to_string(),
+        ))?;
+
+    let totp = two_factor::build_totp(&secret, &app_data.config.two_factor.issuer, &user.email)?;
+
+    let Some(step) = two_factor::verify_code(&totp, &input.code)? else {
+        return Err(AppError::BadRequest("Invalid code".to_string()));
+    };
+
+    let recovery_codes =
+        repositories::two_factor::enable(&app_data.db, &app_data.secret_key, &user, &secret, step)
+            .await?;
+
+    session.remove("two_factor_secret");
+
+    Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/auth/two-factor",
+    tag = "Two-factor",
+    request_body = TwoFactorCodeInput,
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Invalid code", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("DELETE /admin/auth/two-factor", skip(input, user, app_data))]
+pub async fn disable(
+    input: ValidatedJson<TwoFactorCodeInput>,
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    if app_data.config.two_factor.enforce && user.role.requires_two_factor() {
+        return Err(AppError::BadRequest(
+            "Two-factor authentication is required for your role".to_string(),
+        ));
+    }
+
+    check_code(&app_data, &user, &input.code).await?;
+
+    repositories::two_factor::disable(&app_data.db, &user).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/auth/two-factor/recovery-codes",
+    tag = "Two-factor",
+    request_body = TwoFactorCodeInput,
+    responses(
+        (status = 200, description = "New recovery codes, the previous ones are revoked", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument(
+    "POST /admin/auth/two-factor/recovery-codes",
+    skip(input, user, app_data)
+)]
+pub async fn regenerate_recovery_codes(
+    input: ValidatedJson<TwoFactorCodeInput>,
+    user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    check_code(&app_data, &user, &input.code).await?;
+
+    let recovery_codes =
+        repositories::two_factor::regenerate_recovery_codes(&app_data.db, &user).await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Sensitive changes require a valid code, even if the user is already logged in
+async fn check_code(app_data: &AppData, user: &UserModel, code: &str) -> AppResult<()> {
+    let is_valid = repositories::two_factor::verify_code(
+        &app_data.db,
+        &app_data.config.two_factor,
+        &app_data.secret_key,
+        user,
+        code,
+    )
+    .await?;
+
+    if !is_valid {
+        return Err(AppError::BadRequest("Invalid code".to_string()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use actix_web::{
+    web::{Data, ReqData},
+    HttpResponse, Responder,
+};
+
+use crate::{
+    core::{
+        audit::AuditContext,
+        errors::{AppError, AppResult},
+        types::{ValidatedJson, ValidatedPath, ValidatedQuery},
+    },
+    data::AppData,
+    entities::user::{Model as UserModel, UserRole},
+    models::{
+        pagination::Pagination,
+        search::Search,
+        user::{
+            UserAdminCreateInput, UserFilter, UserPasswordInput, UserUpdateInput, UserViewPath,
+        },
+    },
+    repositories,
+};
+
+async fn find_user(app_data: &AppData, id: i32) -> AppResult<UserModel> {
+    repositories::user::get_user_from_id(&app_data.db, id)
+        .await?
+        .ok_or(AppError::NotFoundError)
+}
+
+/// Prevent the admins from locking themselves out of the instance
+fn ensure_not_self(current_user: &UserModel, user: &UserModel) -> AppResult<()> {
+    if current_user.id == user.id {
+        return Err(AppError::BadRequest(
+            "You can't perform this action on your own account".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    tag = "Users",
+    params(Pagination, Search, UserFilter),
+    responses(
+        (status = 200, description = "Users matching the search", body = PaginatedUsers),
+        (status = 403, description = "Not an admin, or two-factor authentication required", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/users", skip(app_data))]
+pub async fn get_users(
+    pagination_query: ValidatedQuery<Pagination>,
+    search_query: ValidatedQuery<Search>,
+    filter: ValidatedQuery<UserFilter>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    let users =
+        repositories::user::paginate_users(&app_data.db, &pagination_query, &search_query, &filter)
+            .await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users",
+    tag = "Users",
+    request_body = UserAdminCreateInput,
+    responses(
+        (status = 201, description = "Created user", body = UserModel),
+        (status = 409, description = "Email address already used", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("POST /admin/users", skip(input, app_data, ctx))]
+pub async fn create_user(
+    input: ValidatedJson<UserAdminCreateInput>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let user = repositories::user::create_user_as_admin(&app_data.db, &ctx, &input).await?;
+
+    Ok(HttpResponse::Created().json(user))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users/{id}",
+    tag = "Users",
+    params(UserViewPath),
+    responses(
+        (status = 200, description = "User", body = UserModel),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("GET /admin/users/{id}", skip(app_data))]
+pub async fn get_user(
+    path: ValidatedPath<UserViewPath>,
+    app_data: Data<AppData>,
+) -> AppResult<impl Responder> {
+    let user = find_user(&app_data, path.id).await?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/users/{id}",
+    tag = "Users",
+    params(UserViewPath),
+    request_body = UserUpdateInput,
+    responses(
+        (status = 200, description = "Updated user", body = UserModel),
+        (status = 400, description = "Admins can't demote themselves", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("PUT /admin/users/{id}", skip(input, current_user, app_data, ctx))]
+pub async fn update_user(
+    path: ValidatedPath<UserViewPath>,
+    input: ValidatedJson<UserUpdateInput>,
+    current_user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let user = find_user(&app_data, path.id).await?;
+
+    if matches!(&input.role, Some(role) if *role != UserRole::Admin) {
+        ensure_not_self(&current_user, &user)?;
+    }
+
+    let user = repositories::user::update_user(&app_data.db, &ctx, user, &input).await?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{id}",
+    tag = "Users",
+    params(UserViewPath),
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 400, description = "Admins can't delete themselves", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("DELETE /admin/users/{id}", skip(current_user, app_data, ctx))]
+pub async fn delete_user(
+    path: ValidatedPath<UserViewPath>,
+    current_user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let user = find_user(&app_data, path.id).await?;
+    ensure_not_self(&current_user, &user)?;
+
+    repositories::user::delete_user(&app_data.db, &ctx, user).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/disable",
+    tag = "Users",
+    params(UserViewPath),
+    responses(
+        (status = 200, description = "Disabled user, their sessions are revoked", body = UserModel),
+        (status = 400, description = "Admins can't disable themselves", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("POST /admin/users/{id}/disable", skip(current_user, app_data, ctx))]
+pub async fn disable_user(
+    path: ValidatedPath<UserViewPath>,
+    current_user: ReqData<UserModel>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let user = find_user(&app_data, path.id).await?;
+    ensure_not_self(&current_user, &user)?;
+
+    let user = repositories::user::set_disabled(&app_data.db, &ctx, user, true).await?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{id}/disable",
+    tag = "Users",
+    params(UserViewPath),
+    responses(
+        (status = 200, description = "Enabled user", body = UserModel),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("DELETE /admin/users/{id}/disable", skip(app_data, ctx))]
+pub async fn enable_user(
+    path: ValidatedPath<UserViewPath>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let user = find_user(&app_data, path.id).await?;
+
+    let user = repositories::user::set_disabled(&app_data.db, &ctx, user, false).await?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/users/{id}/password",
+    tag = "Users",
+    params(UserViewPath),
+    request_body = UserPasswordInput,
+    responses(
+        (status = 204, description = "Password changed, the sessions of the user are revoked"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_cookie" = []))
+)]
+#[tracing::instrument("PUT /admin/users/{id}/password", skip(input, app_data, ctx))]
+pub async fn set_password(
+    path: ValidatedPath<UserViewPath>,
+    input: ValidatedJson<UserPasswordInput>,
+    app_data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let user = find_user(&app_data, path.id).await?;
+
+    repositories::user::change_password(&app_data.db, &ctx, user, &input.password).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -5,30 +5,125 @@ use actix_web::{
 };
 
 use crate::{
-    core::{errors::AppResult, types::ValidatedJson},
+    core::{
+        errors::{AppError, AppResult},
+        types::ValidatedJson,
+    },
     data::AppData,
     entities::user::Model as UserModel,
-    models::user::UserLoginRequest,
+    helpers::sessions,
+    models::{
+        two_factor::{TwoFactorChallenge, TwoFactorCodeInput},
+        user::UserLoginRequest,
+    },
     repositories,
 };
 
+#[utoipa::path(
+    post,
+    path = "/api/auth",
+    tag = "Auth",
+    request_body = UserLoginRequest,
+    responses(
+        (status = 200, description = "Logged in, the session token is in the `WWW-Authenticate` header", body = UserModel),
+        (status = 202, description = "The code of the second factor is required", body = TwoFactorChallenge),
+        (status = 401, description = "Invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 pub async fn login(
     input: ValidatedJson<UserLoginRequest>,
     data: Data<AppData>,
     session: Session,
 ) -> AppResult<impl Responder> {
-    let user = repositories::user::login(&data.db, &input).await?;
+    if !data.config.enable_password_login {
+        return Err(AppError::PasswordLoginDisabled);
+    }
 
-    session.insert("user_id", user.id)?;
+    let user = data.metrics.track_login(
+        "password",
+        repositories::user::login(&data.db, &input).await,
+    )?;
+
+    // The user will only be logged in once the second factor is validated
+    if user.has_two_factor() {
+        session.insert("two_factor_user_id", user.id)?;
+
+        return Ok(HttpResponse::Accepted().json(TwoFactorChallenge {
+            two_factor_required: true,
+        }));
+    }
+
+    sessions::log_in(&session, &user)?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/two-factor",
+    tag = "Auth",
+    request_body = TwoFactorCodeInput,
+    responses(
+        (status = 200, description = "Logged in", body = UserModel),
+        (status = 401, description = "Invalid code or no pending login", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn verify_two_factor(
+    input: ValidatedJson<TwoFactorCodeInput>,
+    data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let user_id = session
+        .get::<i32>("two_factor_user_id")?
+        .ok_or(AppError::Unauthorized)?;
+
+    let user = repositories::user::get_user_from_id(&data.db, user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let is_valid = repositories::two_factor::verify_code(
+        &data.db,
+        &data.config.two_factor,
+        &data.secret_key,
+        &user,
+        &input.code,
+    )
+    .await?;
+
+    if !is_valid {
+        data.metrics.login_failed("two_factor");
+        return Err(AppError::Unauthorized);
+    }
+
+    session.remove("two_factor_user_id");
+    sessions::log_in(&session, &user)?;
 
     Ok(HttpResponse::Ok().json(user))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/auth",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "Current user", body = UserModel),
+        (status = 401, description = "Not logged in", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
 pub async fn me(user: ReqData<UserModel>) -> impl Responder {
     // Return the user data
     HttpResponse::Ok().json(user.into_inner())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/auth",
+    tag = "Auth",
+    responses((status = 200, description = "Logged out")),
+    security(("session_token" = []))
+)]
 pub async fn logout(session: Session) -> impl Responder {
     // Remove the user_id from the session
     session.purge();
@@ -1,48 +1,98 @@
 use actix_multipart::{form::MultipartForm, Multipart};
-use actix_web::{web::Data, HttpResponse, Responder};
+use actix_web::{
+    web::{Data, ReqData},
+    HttpResponse, Responder,
+};
 
 use crate::{
     core::{
+        audit::AuditContext,
         errors::{AppError, AppResult},
         types::{ValidatedJson, ValidatedPath, ValidatedQuery},
     },
     data::AppData,
+    entities::user::Model as UserModel,
     models::{
-        games::{GameBannerUpload, GameCreateInput, GameViewPath},
+        games::{
+            media_response, GameBannerUpload, GameInput, GameMediaReorderInput, GameMediaUpload,
+            GameMediaViewPath, GameTrailerInput, GameViewPath,
+        },
         pagination::Pagination,
         search::Search,
     },
     repositories,
 };
 
-#[tracing::instrument(name = "GET /api/games", skip(data))]
+#[utoipa::path(
+    get,
+    path = "/api/games",
+    tag = "Games",
+    params(Pagination, Search),
+    responses(
+        (status = 200, description = "Games matching the search, with the facets", body = GameSearchResponse),
+        (status = 400, description = "Invalid cursor", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "GET /api/games", skip(data, user))]
 pub async fn get_games(
     pagination_query: ValidatedQuery<Pagination>,
     search_query: ValidatedQuery<Search>,
     data: Data<AppData>,
+    user: ReqData<UserModel>,
 ) -> AppResult<impl Responder> {
     let games =
-        repositories::games::paginate_games(&data.db, &pagination_query, &search_query).await?;
+        repositories::games::paginate_games(&data.db, &user, &pagination_query, &search_query)
+            .await?;
 
     Ok(HttpResponse::Ok().json(games))
 }
 
-#[tracing::instrument(name = "POST /api/games", skip(data))]
+#[utoipa::path(
+    post,
+    path = "/api/games",
+    tag = "Games",
+    request_body = GameInput,
+    responses(
+        (status = 200, description = "Created game", body = GameModel),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "A game with this name already exists", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "POST /api/games", skip(data, ctx))]
 pub async fn create_game(
-    input: ValidatedJson<GameCreateInput>,
+    input: ValidatedJson<GameInput>,
     data: Data<AppData>,
+    ctx: AuditContext,
 ) -> AppResult<impl Responder> {
-    let game = repositories::games::create_games(&data.db, &input).await?;
+    let game = repositories::games::create_games(&data.db, &ctx, &input).await?;
 
     Ok(HttpResponse::Ok().json(game))
 }
 
-#[tracing::instrument(name = "GET /api/games/{id}", skip(data))]
+#[utoipa::path(
+    get,
+    path = "/api/games/{id}",
+    tag = "Games",
+    params(GameViewPath),
+    responses(
+        (status = 200, description = "Game with its metadata and media", body = GameDetails),
+        (status = 404, description = "Game not found, or not granted to the user", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "GET /api/games/{id}", skip(data, user))]
 pub async fn get_game(
     path: ValidatedPath<GameViewPath>,
     data: Data<AppData>,
+    user: ReqData<UserModel>,
 ) -> AppResult<impl Responder> {
-    let game = repositories::games::get_game(&data.db, path.into_inner().id).await?;
+    let id = path.into_inner().id;
+    repositories::games::ensure_game_access(&data.db, &user, id).await?;
+
+    let game = repositories::games::get_game(&data.db, &data.s3, id).await?;
 
     if let Some(game) = game {
         return Ok(HttpResponse::Ok().json(game));
@@ -51,37 +101,291 @@ pub async fn get_game(
     Err(AppError::NotFoundError)
 }
 
-#[tracing::instrument(name = "PUT /api/games/{id}", skip(data))]
+#[utoipa::path(
+    put,
+    path = "/api/games/{id}",
+    tag = "Games",
+    params(GameViewPath),
+    request_body = GameInput,
+    responses(
+        (status = 200, description = "Updated game", body = GameModel),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "PUT /api/games/{id}", skip(data, ctx, user))]
 pub async fn update_game(
     path: ValidatedPath<GameViewPath>,
-    input: ValidatedJson<GameCreateInput>,
+    input: ValidatedJson<GameInput>,
     data: Data<AppData>,
+    ctx: AuditContext,
+    user: ReqData<UserModel>,
 ) -> AppResult<impl Responder> {
-    let game = repositories::games::update_games(&data.db, path.into_inner().id, &input).await?;
+    let id = path.into_inner().id;
+    repositories::games::ensure_game_access(&data.db, &user, id).await?;
+
+    let game = repositories::games::update_games(&data.db, &ctx, id, &input).await?;
 
     Ok(HttpResponse::Ok().json(game))
 }
 
 // Upload game banner
-#[tracing::instrument(name = "POST /api/games/{id}/banner", skip(data, form))]
+#[utoipa::path(
+    post,
+    path = "/api/games/{id}/banner",
+    tag = "Games",
+    params(GameViewPath),
+    request_body(
+        content = GameBannerImageUpload,
+        content_type = "multipart/form-data",
+        description = "Either an image in `value`, or a `color` (see `GameBannerColorUpload`)"
+    ),
+    responses(
+        (status = 204, description = "Banner updated"),
+        (status = 400, description = "Invalid image or color", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "POST /api/games/{id}/banner", skip(data, form, ctx, user))]
 pub async fn upload_game_banner(
     path: ValidatedPath<GameViewPath>,
     data: Data<AppData>,
     form: GameBannerUpload,
+    ctx: AuditContext,
+    user: ReqData<UserModel>,
 ) -> AppResult<impl Responder> {
-    repositories::games::update_game_banner(&data.db, &data.s3, path.into_inner().id, &form)
-        .await?;
+    let id = path.into_inner().id;
+    repositories::games::ensure_game_access(&data.db, &user, id).await?;
+
+    let _upload = data.metrics.track_upload();
+
+    repositories::games::update_game_banner(&data.db, &data.s3, &ctx, id, &form).await?;
 
     Ok(HttpResponse::NoContent().finish())
 }
 
 // Delete game banner
-#[tracing::instrument(name = "DELETE /api/games/{id}/banner", skip(data))]
+#[utoipa::path(
+    delete,
+    path = "/api/games/{id}/banner",
+    tag = "Games",
+    params(GameViewPath),
+    responses(
+        (status = 204, description = "Banner reset to a color"),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "DELETE /api/games/{id}/banner", skip(data, ctx, user))]
 pub async fn delete_game_banner(
     path: ValidatedPath<GameViewPath>,
     data: Data<AppData>,
+    ctx: AuditContext,
+    user: ReqData<UserModel>,
 ) -> AppResult<impl Responder> {
-    repositories::games::delete_game_banner(&data.db, path.into_inner().id).await?;
+    let id = path.into_inner().id;
+    repositories::games::ensure_game_access(&data.db, &user, id).await?;
+
+    repositories::games::delete_game_banner(&data.db, &ctx, id).await?;
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+// Upload an image media: icon, logo, hero or screenshot
+#[utoipa::path(
+    post,
+    path = "/api/games/{id}/media",
+    tag = "Games",
+    params(GameViewPath),
+    request_body(content = GameMediaUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "Uploaded media", body = GameMedia),
+        (status = 400, description = "Invalid image, or too many screenshots", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "POST /api/games/{id}/media", skip(data, form, ctx))]
+pub async fn upload_game_media(
+    path: ValidatedPath<GameViewPath>,
+    data: Data<AppData>,
+    form: MultipartForm<GameMediaUpload>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let _upload = data.metrics.track_upload();
+
+    let media = repositories::media::upload_media(
+        &data.db,
+        &data.s3,
+        &ctx,
+        path.into_inner().id,
+        *form.kind,
+        form.file.file.path(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Created().json(media_response(media, &data.s3)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/games/{id}/media/trailer",
+    tag = "Games",
+    params(GameViewPath),
+    request_body = GameTrailerInput,
+    responses(
+        (status = 200, description = "Trailer of the game", body = GameMedia),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "PUT /api/games/{id}/media/trailer", skip(data, ctx))]
+pub async fn set_game_trailer(
+    path: ValidatedPath<GameViewPath>,
+    input: ValidatedJson<GameTrailerInput>,
+    data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let input = input.into_inner();
+    let media =
+        repositories::media::set_trailer(&data.db, &ctx, path.into_inner().id, input.url).await?;
+
+    Ok(HttpResponse::Ok().json(media_response(media, &data.s3)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/games/{id}/media/order",
+    tag = "Games",
+    params(GameViewPath),
+    request_body = GameMediaReorderInput,
+    responses(
+        (status = 200, description = "Screenshots in their new order", body = [GameMedia]),
+        (status = 400, description = "The IDs don't match the screenshots of the game", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "PUT /api/games/{id}/media/order", skip(data, ctx))]
+pub async fn reorder_game_screenshots(
+    path: ValidatedPath<GameViewPath>,
+    input: ValidatedJson<GameMediaReorderInput>,
+    data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let screenshots =
+        repositories::media::reorder_screenshots(&data.db, &ctx, path.into_inner().id, &input.ids)
+            .await?;
+
+    let screenshots: Vec<_> = screenshots
+        .into_iter()
+        .map(|media| media_response(media, &data.s3))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(screenshots))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/games/{id}/media/{media_id}",
+    tag = "Games",
+    params(GameMediaViewPath),
+    responses(
+        (status = 204, description = "Media deleted"),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Media not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "DELETE /api/games/{id}/media/{media_id}", skip(data, ctx))]
+pub async fn delete_game_media(
+    path: ValidatedPath<GameMediaViewPath>,
+    data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let path = path.into_inner();
+    repositories::media::delete_media(&data.db, &ctx, path.id, path.media_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/games/{id}",
+    tag = "Games",
+    params(GameViewPath),
+    responses(
+        (status = 200, description = "Deleted game, it can be restored until it is purged", body = GameModel),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "DELETE /api/games/{id}", skip(data, ctx))]
+pub async fn delete_game(
+    path: ValidatedPath<GameViewPath>,
+    data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let game = repositories::games::delete_game(&data.db, &ctx, path.into_inner().id).await?;
+
+    Ok(HttpResponse::Ok().json(game))
+}
+
+// List the deleted games which can still be restored
+#[utoipa::path(
+    get,
+    path = "/api/games/trash",
+    tag = "Games",
+    responses(
+        (status = 200, description = "Deleted games which can still be restored", body = [GameModel]),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "GET /api/games/trash", skip(data))]
+pub async fn get_deleted_games(data: Data<AppData>) -> AppResult<impl Responder> {
+    let games =
+        repositories::games::get_deleted_games(&data.db, data.config.games.restore_window_days)
+            .await?;
+
+    Ok(HttpResponse::Ok().json(games))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/games/{id}/restore",
+    tag = "Games",
+    params(GameViewPath),
+    responses(
+        (status = 200, description = "Restored game", body = GameModel),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found, or purged", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument(name = "POST /api/games/{id}/restore", skip(data, ctx))]
+pub async fn restore_game(
+    path: ValidatedPath<GameViewPath>,
+    data: Data<AppData>,
+    ctx: AuditContext,
+) -> AppResult<impl Responder> {
+    let game = repositories::games::restore_game(
+        &data.db,
+        &ctx,
+        data.config.games.restore_window_days,
+        path.into_inner().id,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(game))
+}
@@ -1,3 +1,5 @@
 pub mod auth;
 pub mod games;
+pub mod oidc;
+pub mod saves;
 pub mod versions;
@@ -0,0 +1,114 @@
+use actix_multi_session::Session;
+use actix_web::{web::Data, HttpResponse, Responder};
+use openidconnect::RedirectUrl;
+
+use crate::{
+    core::{
+        errors::{AppError, AppResult},
+        oidc::OidcState,
+        types::ValidatedJson,
+    },
+    data::AppData,
+    helpers::sessions,
+    models::{
+        oidc::{OidcAuthorization, OidcAuthorizeInput, OidcCallbackInput},
+        two_factor::TwoFactorChallenge,
+    },
+    repositories,
+};
+
+/// Only the loopback interface is allowed as a redirection for the desktop client,
+/// otherwise the authorization code could be sent to any website
+fn is_loopback(redirect_url: &RedirectUrl) -> bool {
+    let url = redirect_url.url();
+
+    url.scheme() == "http"
+        && matches!(
+            url.host_str(),
+            Some("127.0.0.1") | Some("[::1]") | Some("localhost")
+        )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/oidc",
+    tag = "Auth",
+    operation_id = "oidc_authorize",
+    request_body = OidcAuthorizeInput,
+    responses(
+        (status = 200, description = "URL of the login page of the identity provider", body = OidcAuthorization),
+        (status = 422, description = "Invalid input", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn authorize(
+    input: ValidatedJson<OidcAuthorizeInput>,
+    data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let oidc = data.oidc.as_ref().ok_or(AppError::NotFoundError)?;
+
+    let redirect_url = RedirectUrl::new(input.redirect_uri.clone())
+        .map_err(|_| AppError::BadRequest("Invalid redirect URI".to_string()))?;
+
+    if !is_loopback(&redirect_url) {
+        return Err(AppError::BadRequest(
+            "The redirect URI must be a loopback address".to_string(),
+        ));
+    }
+
+    let (authorization_url, state) = oidc.authorize(Some(redirect_url));
+
+    session.insert("oidc_state", state)?;
+
+    Ok(HttpResponse::Ok().json(OidcAuthorization { authorization_url }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/oidc/callback",
+    tag = "Auth",
+    operation_id = "oidc_callback",
+    request_body = OidcCallbackInput,
+    responses(
+        (status = 200, description = "Logged in", body = UserModel),
+        (status = 202, description = "The code of the second factor is required", body = TwoFactorChallenge),
+        (status = 400, description = "Invalid state or code", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Email address not verified by the identity provider, or account disabled", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn callback(
+    input: ValidatedJson<OidcCallbackInput>,
+    data: Data<AppData>,
+    session: Session,
+) -> AppResult<impl Responder> {
+    let (Some(oidc), Some(oidc_config)) = (&data.oidc, &data.config.oidc) else {
+        return Err(AppError::NotFoundError);
+    };
+
+    let state = session
+        .remove_as::<OidcState>("oidc_state")
+        .and_then(Result::ok)
+        .ok_or(AppError::Unauthorized)?;
+
+    let identity = oidc
+        .exchange(input.code.clone(), &input.state, state)
+        .await?;
+
+    let user = data.metrics.track_login(
+        "oidc",
+        repositories::user::login_oidc(&data.db, oidc_config, &identity).await,
+    )?;
+
+    // The user will only be logged in once the second factor is validated
+    if user.has_two_factor() {
+        session.insert("two_factor_user_id", user.id)?;
+
+        return Ok(HttpResponse::Accepted().json(TwoFactorChallenge {
+            two_factor_required: true,
+        }));
+    }
+
+    sessions::log_in(&session, &user)?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
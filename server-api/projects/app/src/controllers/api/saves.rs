@@ -0,0 +1,94 @@
+use crate::{
+    core::{
+        errors::{AppError, AppResult},
+        types::{ValidatedPath, ValidatedQuery},
+    },
+    entities::user::Model as UserModel,
+    models::games::GameViewPath,
+    repositories,
+};
+
+use actix_web::{
+    web::{Data, ReqData},
+    HttpResponse, Responder,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::data::AppData;
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SaveUploadRequest {
+    file_size: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/game/{id}/save",
+    tag = "Games",
+    params(GameViewPath, SaveUploadRequest),
+    responses(
+        (status = 200, description = "Presigned URL to upload the save of the user to", body = PresignedUrl),
+        (status = 404, description = "Game not found, or not granted to the user", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument("POST /api/games/{id}/save", skip(data, path, user), fields(id = %path.id))]
+pub async fn upload_save(
+    query_data: ValidatedQuery<SaveUploadRequest>,
+    path: ValidatedPath<GameViewPath>,
+    data: Data<AppData>,
+    user: ReqData<UserModel>,
+) -> AppResult<impl Responder> {
+    repositories::games::ensure_game_access(&data.db, &user, path.id).await?;
+
+    // A single save per user, the versioned bucket keeps the previous ones
+    let prefix = repositories::games::save_key_prefix(path.id);
+    let filename = user.id.to_string();
+
+    let presigned_url = data
+        .s3
+        .create_presigned_url(&filename, query_data.file_size, &prefix)
+        .await?;
+
+    repositories::storage::record_pending_upload(&data.db, &format!("{}{}", prefix, filename))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(presigned_url))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/game/{id}/save",
+    tag = "Games",
+    params(GameViewPath),
+    responses(
+        (status = 200, description = "Presigned URL to download the save of the user from", body = DownloadUrl),
+        (status = 404, description = "Game or save not found, or game not granted to the user", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument("GET /api/games/{id}/save", skip(data, path, user), fields(id = %path.id))]
+pub async fn download_save(
+    path: ValidatedPath<GameViewPath>,
+    data: Data<AppData>,
+    user: ReqData<UserModel>,
+) -> AppResult<impl Responder> {
+    repositories::games::ensure_game_access(&data.db, &user, path.id).await?;
+
+    let key = format!(
+        "{}{}",
+        repositories::games::save_key_prefix(path.id),
+        user.id
+    );
+
+    if !data.s3.file_exists(&key).await? {
+        return Err(AppError::NotFoundError);
+    }
+
+    let download_url = data.s3.create_presigned_download(&key)?;
+
+    Ok(HttpResponse::Ok().json(download_url))
+}
@@ -1,44 +1,125 @@
 use crate::{
     core::{
-        errors::AppResult,
+        audit::AuditContext,
+        errors::{AppError, AppResult},
         types::{ValidatedJson, ValidatedPath, ValidatedQuery},
     },
+    entities::user::Model as UserModel,
+    helpers::validation::filename::validate_filename,
     models::games::GameViewPath,
+    repositories,
 };
 
 use actix_web::{
-    web::{Data, Redirect},
+    web::{Data, Redirect, ReqData},
     HttpResponse, Responder,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
 use validator::Validate;
 
 use crate::data::AppData;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct UploadRequest {
     file_size: usize,
+    #[validate(length(min = 1, max = 255), custom = "validate_filename")]
     filename: String,
 }
 
-#[tracing::instrument("POST /api/games/{id}/versions", skip(data, path), fields(id = %path.id))]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadRequest {
+    #[validate(length(min = 1, max = 255), custom = "validate_filename")]
+    filename: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/game/{id}/version",
+    tag = "Games",
+    params(GameViewPath, UploadRequest),
+    responses(
+        (status = 200, description = "Presigned URL to upload the file to", body = PresignedUrl),
+        (status = 403, description = "Not a publisher", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Game not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument("POST /api/games/{id}/versions", skip(data, path, ctx, user), fields(id = %path.id))]
 pub async fn upload_file(
     query_data: ValidatedQuery<UploadRequest>,
     path: ValidatedPath<GameViewPath>,
     data: Data<AppData>,
+    ctx: AuditContext,
+    user: ReqData<UserModel>,
 ) -> AppResult<impl Responder> {
+    repositories::games::ensure_game_access(&data.db, &user, path.id).await?;
+
     let s3client = data.s3.clone();
 
-    let prefix = format!("uploads/images/");
+    let prefix = repositories::games::version_key_prefix(path.id);
 
     let presigned_url = s3client
         .create_presigned_url(&query_data.filename, query_data.file_size, &prefix)
         .await?;
 
+    // Tracked as soon as the URL is issued, the garbage collector checks that the upload finished
+    repositories::storage::record_pending_upload(
+        &data.db,
+        &format!("{}{}", prefix, query_data.filename),
+    )
+    .await?;
+
+    repositories::audit::record(
+        &data.db,
+        &ctx,
+        "version.upload",
+        "game",
+        Some(path.id.to_string()),
+        None,
+        Some(&serde_json::json!({
+            "filename": query_data.filename,
+            "file_size": query_data.file_size,
+        })),
+    )
+    .await?;
+
     return Ok(HttpResponse::Ok().json(presigned_url));
 }
 
-#[tracing::instrument("GET /api/games/{id}/versions")]
-pub async fn get_versions(path: ValidatedPath<GameViewPath>) -> AppResult<impl Responder> {
-    Ok(HttpResponse::Ok().json(()))
+#[utoipa::path(
+    get,
+    path = "/api/game/{id}/version",
+    tag = "Games",
+    params(GameViewPath, DownloadRequest),
+    responses(
+        (status = 200, description = "Presigned URL to download the file from", body = DownloadUrl),
+        (status = 404, description = "Game or file not found, or game not granted to the user", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("session_token" = []))
+)]
+#[tracing::instrument("GET /api/games/{id}/versions", skip(data, path, user), fields(id = %path.id))]
+pub async fn download_file(
+    query_data: ValidatedQuery<DownloadRequest>,
+    path: ValidatedPath<GameViewPath>,
+    data: Data<AppData>,
+    user: ReqData<UserModel>,
+) -> AppResult<impl Responder> {
+    repositories::games::ensure_game_access(&data.db, &user, path.id).await?;
+
+    let key = format!(
+        "{}{}",
+        repositories::games::version_key_prefix(path.id),
+        query_data.filename
+    );
+
+    if !data.s3.file_exists(&key).await? {
+        return Err(AppError::NotFoundError);
+    }
+
+    let download_url = data.s3.create_presigned_download(&key)?;
+
+    Ok(HttpResponse::Ok().json(download_url))
 }
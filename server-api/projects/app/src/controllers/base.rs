@@ -1,15 +1,38 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, http::header::AUTHORIZATION, web, HttpRequest, HttpResponse, Responder};
 
-use crate::{data::AppData, repositories::app};
+use crate::{
+    core::{
+        config::RegistrationMode,
+        database,
+        errors::{AppError, AppResult},
+    },
+    data::AppData,
+    helpers::hashing,
+    repositories::app,
+};
 
-#[derive(serde::Serialize)]
-struct Status {
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct Status {
     name: &'static str,
     version: &'static str,
     status: &'static str,
     configured: bool,
+    password_login: bool,
+    registration: RegistrationMode,
+    oidc: bool,
+    /// Last migration applied to the database
+    schema_version: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "Status",
+    responses(
+        (status = 200, description = "Server status", body = Status),
+        (status = 503, description = "The database is unreachable", body = Status),
+    )
+)]
 #[get("/")]
 pub async fn status(data: web::Data<AppData>) -> impl Responder {
     let is_conn_ok = data.db.ping().await.is_ok();
@@ -19,6 +42,10 @@ pub async fn status(data: web::Data<AppData>) -> impl Responder {
         version: env!("CARGO_PKG_VERSION"),
         status: "UP",
         configured: false,
+        password_login: data.config.enable_password_login,
+        registration: data.config.registration_mode(),
+        oidc: data.oidc.is_some(),
+        schema_version: None,
     };
 
     if !is_conn_ok {
@@ -28,6 +55,31 @@ pub async fn status(data: web::Data<AppData>) -> impl Responder {
     let is_configured = app::is_configured(&data.db).await.unwrap_or(false);
 
     status.configured = is_configured;
+    status.schema_version = database::schema_version(&data.db).await.ok().flatten();
 
     return HttpResponse::Ok().json(status);
 }
+
+/// Metrics in the Prometheus format, with the `metrics_token` as bearer token when it is set
+#[tracing::instrument("GET /metrics", skip(req, data))]
+pub async fn metrics(req: HttpRequest, data: web::Data<AppData>) -> AppResult<HttpResponse> {
+    if let Some(token) = &data.config.server.metrics_token {
+        let given = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AppError::Unauthorized)?;
+
+        // Compare the hashes to not leak the token through the comparison time
+        if hashing::hash_token(given) != hashing::hash_token(token) {
+            return Err(AppError::Unauthorized);
+        }
+    }
+
+    let body = data.metrics.render(&data.db)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(body))
+}
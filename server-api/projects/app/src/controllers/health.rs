@@ -0,0 +1,124 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use actix_web::{get, web, HttpResponse, Responder};
+
+use crate::data::AppData;
+
+/// Longest time given to a dependency to answer
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(serde::Serialize, utoipa::ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct DependencyHealth {
+    status: HealthStatus,
+    /// Time taken by the check, the timeout when it didn't answer
+    latency_ms: u64,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct Health {
+    status: HealthStatus,
+    /// Checks of the dependencies, by name. Empty for the liveness probe.
+    checks: BTreeMap<&'static str, DependencyHealth>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "Status",
+    responses(
+        (status = 200, description = "The server is running", body = Health),
+    )
+)]
+#[get("/health/live")]
+pub async fn live() -> impl Responder {
+    HttpResponse::Ok().json(Health {
+        status: HealthStatus::Up,
+        checks: BTreeMap::new(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "Status",
+    responses(
+        (status = 200, description = "All the dependencies are reachable", body = Health),
+        (status = 503, description = "A dependency is unreachable", body = Health),
+    )
+)]
+#[get("/health/ready")]
+#[tracing::instrument("GET /health/ready", skip(data))]
+pub async fn ready(data: web::Data<AppData>) -> impl Responder {
+    let (database, redis, storage) = futures::join!(
+        check("database", async {
+            data.db.ping().await.map_err(|e| e.to_string())
+        }),
+        check("redis", async {
+            data.session_store
+                .inner()
+                .ping()
+                .await
+                .map_err(|e| e.to_string())
+        }),
+        check("storage", async {
+            data.s3.check_bucket().await.map_err(|e| e.to_string())
+        }),
+    );
+
+    let checks = BTreeMap::from([
+        ("database", database),
+        ("redis", redis),
+        ("storage", storage),
+    ]);
+
+    let status = if checks
+        .values()
+        .all(|check| check.status == HealthStatus::Up)
+    {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    };
+    let health = Health { status, checks };
+
+    match status {
+        HealthStatus::Up => HttpResponse::Ok().json(health),
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(health),
+    }
+}
+
+/// Run a check with a timeout. The errors are only logged, they could leak internal details.
+async fn check(name: &str, future: impl Future<Output = Result<(), String>>) -> DependencyHealth {
+    let started_at = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, future).await;
+    let latency_ms = started_at.elapsed().as_millis() as u64;
+
+    let status = match result {
+        Ok(Ok(())) => HealthStatus::Up,
+        Ok(Err(e)) => {
+            tracing::warn!("Health check of {} failed: {}", name, e);
+            HealthStatus::Down
+        }
+        Err(_) => {
+            tracing::warn!(
+                "Health check of {} timed out after {:?}",
+                name,
+                CHECK_TIMEOUT
+            );
+            HealthStatus::Down
+        }
+    };
+
+    DependencyHealth { status, latency_ms }
+}
@@ -0,0 +1,52 @@
+use actix_web::{
+    dev::Payload, http::header::USER_AGENT, web, FromRequest, HttpMessage, HttpRequest,
+};
+use std::future::{ready, Ready};
+
+use crate::{data::AppData, entities::user::Model as UserModel, helpers::client_ip::client_ip};
+
+use super::errors::AppError;
+
+/// Who performs an action and from where, recorded in the audit log.
+/// The actor is the user provided by the `Auth` middleware, if any.
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor_id: Option<i32>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl AuditContext {
+    /// Context of the actions performed by the application itself, e.g. background tasks
+    pub fn system() -> Self {
+        Self::default()
+    }
+}
+
+impl FromRequest for AuditContext {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let actor_id = req.extensions().get::<UserModel>().map(|user| user.id);
+
+        // Same source as the rate limiter, the forwarded address is only read from the proxies
+        let trusted_proxies = req
+            .app_data::<web::Data<AppData>>()
+            .map(|data| data.config.server.trusted_proxies.as_slice())
+            .unwrap_or_default();
+        let ip = client_ip(req, trusted_proxies).map(|ip| ip.to_string());
+
+        let user_agent = req
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+
+        ready(Ok(AuditContext {
+            actor_id,
+            ip,
+            user_agent,
+        }))
+    }
+}
@@ -1,11 +1,18 @@
-use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use std::collections::HashSet;
 
-use crate::{models::user::UserCreateInput, repositories};
+use migration::{MigrationName, Migrator, MigratorTrait};
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseConnection, Statement, TransactionTrait,
+};
 
 use super::errors::{AppError, AppResult};
 
 pub type DbPool = DatabaseConnection;
 
+/// Key of the advisory lock taken while checking the migrations, for the replicas starting at
+/// the same time to not apply them concurrently
+const MIGRATION_LOCK_KEY: i64 = 0x6761_6d65_7379_6e63;
+
 #[tracing::instrument("initialize database", skip(database_url))]
 pub async fn init_pool(database_url: &str) -> AppResult<DbPool> {
     let opt = ConnectOptions::new(database_url);
@@ -15,20 +22,69 @@ pub async fn init_pool(database_url: &str) -> AppResult<DbPool> {
         .map_err(AppError::DatabaseError)
 }
 
-#[tracing::instrument("seeding database", skip(pool))]
-pub async fn seed_database(pool: &DbPool) -> AppResult<()> {
-    let user_count = repositories::user::count_users(pool).await?;
+/// Compare the schema with the migrations of this server, refusing to start if the database has
+/// migrations unknown to it. The pending ones are applied when `auto_migrate` is enabled.
+#[tracing::instrument("migrate database", skip(db))]
+pub async fn migrate(db: &DbPool, auto_migrate: bool) -> AppResult<()> {
+    // The lock is released with the transaction, the migrations are applied in it too
+    let txn = db.begin().await?;
+    txn.execute(Statement::from_string(
+        txn.get_database_backend(),
+        format!("SELECT pg_advisory_xact_lock({})", MIGRATION_LOCK_KEY),
+    ))
+    .await?;
+
+    let known: HashSet<String> = Migrator::migrations()
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect();
+    let applied: HashSet<String> = Migrator::get_migration_models(&txn)
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
 
-    if user_count > 0 {
+    let mut unknown: Vec<&String> = applied.difference(&known).collect();
+    if !unknown.is_empty() {
+        unknown.sort();
+        return Err(AppError::Other(anyhow::anyhow!(
+            "The database schema is newer than this server, unknown migrations: {}",
+            unknown
+                .iter()
+                .map(|version| version.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let pending = known.difference(&applied).count();
+    if pending == 0 {
         return Ok(());
     }
 
-    let user = UserCreateInput {
-        email: "admin@admin.com".to_owned(),
-        password: "password".to_owned(),
-    };
+    if !auto_migrate {
+        tracing::warn!(
+            "{} migrations are pending, apply them or enable `database.auto_migrate`",
+            pending
+        );
+        return Ok(());
+    }
 
-    repositories::user::create_user(pool, &user).await?;
+    tracing::info!("Applying {} pending migrations", pending);
+    Migrator::up(&txn, None).await?;
+    txn.commit().await?;
 
     Ok(())
 }
+
+/// Last migration applied to the database
+pub async fn schema_version(db: &DbPool) -> AppResult<Option<String>> {
+    let row = db
+        .query_one(Statement::from_string(
+            db.get_database_backend(),
+            "SELECT version FROM seaql_migrations ORDER BY version DESC LIMIT 1".to_owned(),
+        ))
+        .await?;
+
+    Ok(row.map(|row| row.try_get("", "version")).transpose()?)
+}
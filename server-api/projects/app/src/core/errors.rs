@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use actix_web::{
     error::{JsonPayloadError, ResponseError},
-    http::StatusCode,
-    HttpRequest, HttpResponse,
+    http::{header::RETRY_AFTER, StatusCode},
+    HttpRequest, HttpResponse, HttpResponseBuilder,
 };
 use s3::error::S3Error;
-use serde::Serialize;
+use uuid::Uuid;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::middlewares::request_id::current_request_id;
+
+pub use game_sync_common::errors::{ErrorCode, FieldError, ProblemDetails, PROBLEM_JSON};
 
 pub type AppResult<T> = Result<T, AppError>;
 
@@ -66,6 +71,48 @@ pub enum AppError {
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("TOTP Error: {0}")]
+    TotpError(String),
+
+    #[error("Mail Error: {0}")]
+    MailError(String),
+
+    #[error("Email address not verified")]
+    EmailNotVerified,
+
+    #[error("Account disabled")]
+    AccountDisabled,
+
+    #[error("Registration is disabled")]
+    RegistrationDisabled,
+
+    #[error("Invalid or expired invitation")]
+    InvalidInvitation,
+
+    #[error("Forbidden")]
+    Forbidden,
+
+    #[error("Invalid or expired token")]
+    InvalidToken,
+
+    #[error("OIDC Error: {0}")]
+    OidcError(String),
+
+    #[error("Password login is disabled")]
+    PasswordLoginDisabled,
+
+    #[error("WebAuthn Error: {0}")]
+    WebauthnError(#[from] webauthn_rs::prelude::WebauthnError),
+
+    #[error("Two-factor authentication enrollment required")]
+    TwoFactorRequired,
+
+    #[error("Metrics Error: {0}")]
+    MetricsError(#[from] prometheus::Error),
+
+    #[error("Too Many Requests, retry after {0}s")]
+    TooManyRequests(u64),
+
     #[error("Unknown Error")]
     UnknownError,
 
@@ -73,15 +120,43 @@ pub enum AppError {
     Other(#[from] anyhow::Error),
 }
 
-#[derive(Serialize)]
-pub struct AppErrorResponse {
-    pub error: String,
-}
+impl AppError {
+    /// Stable code of the error, sent to the clients
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::NotFoundError => ErrorCode::NotFound,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::Unauthorized => ErrorCode::Unauthorized,
+            AppError::TwoFactorRequired => ErrorCode::TwoFactorRequired,
+            AppError::PasswordLoginDisabled => ErrorCode::PasswordLoginDisabled,
+            AppError::EmailNotVerified => ErrorCode::EmailNotVerified,
+            AppError::AccountDisabled => ErrorCode::AccountDisabled,
+            AppError::RegistrationDisabled => ErrorCode::RegistrationDisabled,
+            AppError::InvalidInvitation => ErrorCode::InvalidInvitation,
+            AppError::Forbidden => ErrorCode::Forbidden,
+            AppError::InvalidToken => ErrorCode::InvalidToken,
+            AppError::AlreadyExists(_) => ErrorCode::AlreadyExists,
+            AppError::MultipartError(_) => ErrorCode::InvalidMultipart,
+            AppError::WebauthnError(_) => ErrorCode::InvalidPasskey,
+            AppError::TooManyRequests(_) => ErrorCode::RateLimited,
+            AppError::OidcError(_) => ErrorCode::IdentityProviderError,
+            AppError::S3Error(S3Error::HttpFailWithBody(404, _)) => ErrorCode::NotFound,
+            AppError::S3Error(_) | AppError::S3CredentialsError(_) => ErrorCode::StorageError,
+            _ => ErrorCode::InternalError,
+        }
+    }
 
-#[derive(Serialize)]
-pub struct AppS3ErrorResponse {
-    pub error: String,
-    pub message: String,
+    /// Message of the error for the clients, without the details of the internal errors
+    fn detail(&self) -> String {
+        match self {
+            AppError::BadRequest(message) | AppError::AlreadyExists(message) => message.clone(),
+            AppError::MultipartError(error) => error.to_string(),
+            AppError::WebauthnError(_) => "Invalid passkey".to_string(),
+            AppError::S3Error(S3Error::HttpFailWithBody(404, _)) => "Not Found".to_string(),
+            _ if self.status_code().is_server_error() => "An internal error occurred".to_string(),
+            _ => self.to_string(),
+        }
+    }
 }
 
 impl ResponseError for AppError {
@@ -89,56 +164,131 @@ impl ResponseError for AppError {
         match self {
             AppError::NotFoundError => actix_web::http::StatusCode::NOT_FOUND,
             AppError::Unauthorized => actix_web::http::StatusCode::UNAUTHORIZED,
+            AppError::TwoFactorRequired => actix_web::http::StatusCode::FORBIDDEN,
+            AppError::PasswordLoginDisabled => actix_web::http::StatusCode::FORBIDDEN,
+            AppError::EmailNotVerified => actix_web::http::StatusCode::FORBIDDEN,
+            AppError::AccountDisabled => actix_web::http::StatusCode::FORBIDDEN,
+            AppError::RegistrationDisabled => actix_web::http::StatusCode::FORBIDDEN,
+            AppError::InvalidInvitation => actix_web::http::StatusCode::BAD_REQUEST,
+            AppError::Forbidden => actix_web::http::StatusCode::FORBIDDEN,
+            AppError::InvalidToken => actix_web::http::StatusCode::BAD_REQUEST,
             AppError::AlreadyExists(_) => actix_web::http::StatusCode::CONFLICT,
             AppError::BadRequest(_) => actix_web::http::StatusCode::BAD_REQUEST,
             AppError::MultipartError(_) => actix_web::http::StatusCode::BAD_REQUEST,
+            AppError::WebauthnError(_) => actix_web::http::StatusCode::BAD_REQUEST,
+            AppError::TooManyRequests(_) => actix_web::http::StatusCode::TOO_MANY_REQUESTS,
+            AppError::S3Error(S3Error::HttpFailWithBody(404, _)) => {
+                actix_web::http::StatusCode::NOT_FOUND
+            }
+            AppError::S3Error(_) => actix_web::http::StatusCode::BAD_GATEWAY,
             _ => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> actix_web::HttpResponse<actix_web::body::BoxBody> {
-        if let AppError::S3Error(error) = self {
-            if let S3Error::HttpFailWithBody(s3_status, message) = error {
-                let s3_status =
-                    StatusCode::from_u16(*s3_status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-
-                return HttpResponse::build(s3_status).json(AppS3ErrorResponse {
-                    error: "S3 Error".to_string(),
-                    message: message.to_string(),
-                });
-            }
+        let status = self.status_code();
+        let mut problem = build_problem(status, self.code(), self.detail());
+
+        // The details of the internal errors are only logged, the clients get the ID of the
+        // request to report
+        if status.is_server_error() {
+            let correlation_id = current_request_id().unwrap_or_else(|| Uuid::new_v4().to_string());
+            tracing::error!(correlation_id = %correlation_id, error = ?self, "{}", self);
+            problem.correlation_id = Some(correlation_id);
         }
-        HttpResponse::build(self.status_code()).json(AppErrorResponse {
-            error: self.to_string(),
-        })
+
+        let mut response = HttpResponse::build(status);
+        if let AppError::TooManyRequests(retry_after) = self {
+            response.insert_header((RETRY_AFTER, retry_after.to_string()));
+        }
+
+        problem_response(response, &problem)
     }
 }
 
-#[derive(Serialize)]
-struct ErrorJson {
-    message: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    fields: Option<HashMap<String, Vec<String>>>,
+/// Send a problem with the `application/problem+json` media type
+fn problem_response(mut response: HttpResponseBuilder, problem: &ProblemDetails) -> HttpResponse {
+    response
+        .content_type(PROBLEM_JSON)
+        .body(serde_json::to_string(problem).unwrap_or_default())
 }
 
-#[tracing::instrument("json_error_handler", skip(err, _req))]
-pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
-    let details = ErrorJson {
-        message: err.to_string(),
-        fields: None,
-    };
+fn build_problem(status: StatusCode, code: ErrorCode, detail: impl Into<String>) -> ProblemDetails {
+    ProblemDetails::new(
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default(),
+        code,
+        detail,
+    )
+}
 
-    let resp = match &err {
-        JsonPayloadError::ContentType => HttpResponse::UnsupportedMediaType().json(details),
-        JsonPayloadError::Deserialize(json_err) if json_err.is_data() => {
-            HttpResponse::UnprocessableEntity().json(details)
+/// Problem of a JSON body which couldn't be read
+fn json_payload_problem(err: &JsonPayloadError) -> ProblemDetails {
+    match err {
+        JsonPayloadError::ContentType => build_problem(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::UnsupportedMediaType,
+            err.to_string(),
+        ),
+        JsonPayloadError::Deserialize(json_err) if json_err.is_eof() => build_problem(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::InvalidJson,
+            "Unexpected end of json",
+        ),
+        JsonPayloadError::Deserialize(json_err) if json_err.is_data() => build_problem(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::InvalidJson,
+            json_err.to_string(),
+        ),
+        _ => build_problem(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidJson,
+            err.to_string(),
+        ),
+    }
+}
+
+/// Errors of each invalid field, the nested structures and lists are flattened with dotted paths
+fn field_errors(
+    errors: &ValidationErrors,
+    prefix: Option<String>,
+    fields: &mut BTreeMap<String, Vec<FieldError>>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = match &prefix {
+            Some(prefix) => format!("{}.{}", prefix, field),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields
+                    .entry(path)
+                    .or_default()
+                    .extend(errors.iter().map(|error| FieldError {
+                        code: error.code.to_string(),
+                        message: error.to_string(),
+                    }))
+            }
+            ValidationErrorsKind::Struct(errors) => field_errors(errors, Some(path), fields),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    field_errors(errors, Some(format!("{}.{}", path, index)), fields);
+                }
+            }
         }
-        _ => HttpResponse::BadRequest().json(details),
-    };
+    }
+}
+
+#[tracing::instrument("json_error_handler", skip(err, _req))]
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let problem = json_payload_problem(&err);
+    let resp = problem_response(HttpResponse::build(problem_status(&problem)), &problem);
 
     actix_web::error::InternalError::from_response(err, resp).into()
 }
 
+/// Error handler of the validated JSON bodies, queries and paths
 #[tracing::instrument("validated_json_error_handler", skip(err, _req))]
 pub fn validated_json_error_handler(
     err: actix_web_validator::Error,
@@ -146,68 +296,41 @@ pub fn validated_json_error_handler(
 ) -> actix_web::Error {
     use actix_web_validator::Error;
 
-    let details = ErrorJson {
-        message: err.to_string(),
-        fields: None,
-    };
-
-    let resp = match &err {
-        Error::Deserialize(json_err) => {
-            tracing::error!("Deserialize error: {}", json_err);
-            HttpResponse::UnprocessableEntity().json(details)
-        }
-        actix_web_validator::Error::JsonPayloadError(JsonPayloadError::ContentType) => {
-            HttpResponse::UnsupportedMediaType().json(details)
+    let problem = match &err {
+        Error::JsonPayloadError(json_payload_error) => {
+            tracing::debug!("JsonPayloadError: {}", json_payload_error);
+            json_payload_problem(json_payload_error)
         }
-        actix_web_validator::Error::JsonPayloadError(json_payload_error) => {
-            tracing::error!("JsonPayloadError: {}", json_payload_error);
-
-            match json_payload_error {
-                JsonPayloadError::Deserialize(json_err) => {
-                    if json_err.is_eof() {
-                        HttpResponse::UnprocessableEntity().json(ErrorJson {
-                            message: "Unexpected end of json".to_string(),
-                            fields: None,
-                        })
-                    } else if json_err.is_data() {
-                        HttpResponse::BadRequest().json(ErrorJson {
-                            message: json_err.to_string(),
-                            fields: None,
-                        })
-                    } else {
-                        HttpResponse::UnprocessableEntity().json(ErrorJson {
-                            message: json_err.to_string(),
-                            fields: None,
-                        })
-                    }
-                }
-                _ => HttpResponse::BadRequest().json(details),
-            }
+        Error::Deserialize(deserialize_error) => {
+            tracing::debug!("Deserialize error: {}", deserialize_error);
+            build_problem(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::InvalidJson,
+                deserialize_error.to_string(),
+            )
         }
         Error::Validate(validation_error) => {
-            tracing::error!("Validation error: {}", validation_error);
-
-            HttpResponse::UnprocessableEntity().json(ErrorJson {
-                message: "Validation error".to_owned(),
-                fields: Some(
-                    validation_error
-                        .field_errors()
-                        .iter()
-                        .map(|(&fields, &errors)| {
-                            (
-                                fields.to_string(),
-                                errors
-                                    .iter()
-                                    .map(|e| e.to_string())
-                                    .collect::<Vec<String>>(),
-                            )
-                        })
-                        .collect::<HashMap<String, Vec<String>>>(),
-                ),
-            })
+            tracing::debug!("Validation error: {}", validation_error);
+
+            let mut problem = build_problem(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::ValidationFailed,
+                "Validation error",
+            );
+            field_errors(validation_error, None, &mut problem.fields);
+            problem
         }
-        _ => HttpResponse::BadRequest().json(details),
+        _ => build_problem(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+            err.to_string(),
+        ),
     };
+    let resp = problem_response(HttpResponse::build(problem_status(&problem)), &problem);
 
     actix_web::error::InternalError::from_response(err, resp).into()
 }
+
+fn problem_status(problem: &ProblemDetails) -> StatusCode {
+    StatusCode::from_u16(problem.status).unwrap_or(StatusCode::BAD_REQUEST)
+}
@@ -0,0 +1,124 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// Finished jobs are kept this long so their result can still be checked
+const FINISHED_JOB_RETENTION: time::Duration = time::Duration::hours(24);
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case", tag = "status", content = "error")]
+pub enum JobStatus {
+    Running,
+    Done,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobProgress {
+    pub id: String,
+    #[serde(flatten)]
+    pub status: JobStatus,
+    pub total: u64,
+    pub processed: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub finished_at: Option<OffsetDateTime>,
+}
+
+/// In-memory progress of the background jobs, only meant to be displayed to the admins
+#[derive(Clone, Default)]
+pub struct JobTracker {
+    jobs: Arc<Mutex<HashMap<String, JobProgress>>>,
+}
+
+impl JobTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a job, failing if a job with the same id is already running
+    pub fn start(&self, id: &str) -> Option<JobHandle> {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        let now = OffsetDateTime::now_utc();
+        jobs.retain(|_, job| {
+            job.finished_at.map_or(true, |finished_at| {
+                now - finished_at < FINISHED_JOB_RETENTION
+            })
+        });
+
+        if matches!(jobs.get(id), Some(job) if job.status == JobStatus::Running) {
+            return None;
+        }
+
+        jobs.insert(
+            id.to_owned(),
+            JobProgress {
+                id: id.to_owned(),
+                status: JobStatus::Running,
+                total: 0,
+                processed: 0,
+                started_at: now,
+                finished_at: None,
+            },
+        );
+
+        Some(JobHandle {
+            id: id.to_owned(),
+            tracker: self.clone(),
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobProgress> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<JobProgress> {
+        let mut jobs: Vec<JobProgress> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+
+        jobs
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut JobProgress)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            f(job);
+        }
+    }
+}
+
+/// Handle given to the running job to report its progress
+pub struct JobHandle {
+    id: String,
+    tracker: JobTracker,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn set_total(&self, total: u64) {
+        self.tracker.update(&self.id, |job| job.total = total);
+    }
+
+    pub fn advance(&self) {
+        self.tracker.update(&self.id, |job| job.processed += 1);
+    }
+
+    pub fn finish(self, result: Result<(), String>) {
+        self.tracker.update(&self.id, |job| {
+            job.status = match result {
+                Ok(()) => JobStatus::Done,
+                Err(e) => JobStatus::Failed(e),
+            };
+            job.finished_at = Some(OffsetDateTime::now_utc());
+        });
+    }
+}
@@ -0,0 +1,108 @@
+use lettre::{
+    address::AddressError,
+    message::{header::ContentType, Mailbox},
+    AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use tera::{Context, Tera};
+
+use super::{
+    config::{MailConfig, MailTransport},
+    errors::{AppError, AppResult},
+};
+
+#[derive(Clone)]
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    File(AsyncFileTransport<Tokio1Executor>),
+    Log,
+}
+
+#[derive(Clone)]
+pub struct Mailer {
+    transport: Transport,
+    from: String,
+}
+
+#[tracing::instrument("initialize mailer", skip(config))]
+pub fn init_mailer(config: &MailConfig) -> AppResult<Mailer> {
+    let transport = match config.transport {
+        MailTransport::Smtp => {
+            let url = config.smtp_url.as_ref().ok_or(AppError::MailError(
+                "`mail.smtp_url` is required by the SMTP transport".to_string(),
+            ))?;
+
+            let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url)
+                .map_err(|e| AppError::MailError(e.to_string()))?
+                .build();
+
+            Transport::Smtp(transport)
+        }
+        MailTransport::File => {
+            let directory = config.file_directory.as_ref().ok_or(AppError::MailError(
+                "`mail.file_directory` is required by the file transport".to_string(),
+            ))?;
+
+            Transport::File(AsyncFileTransport::<Tokio1Executor>::new(directory))
+        }
+        MailTransport::Log => Transport::Log,
+    };
+
+    Ok(Mailer {
+        transport,
+        from: config.from.clone(),
+    })
+}
+
+impl Mailer {
+    /// Render the `mail/{template}.html` template and send it to `to`
+    #[tracing::instrument("send mail", skip(self, tera, context))]
+    pub async fn send_template(
+        &self,
+        tera: &Tera,
+        to: &str,
+        subject: &str,
+        template: &str,
+        context: &Context,
+    ) -> AppResult<()> {
+        let body = tera.render(&format!("mail/{}.html", template), context)?;
+
+        if let Transport::Log = self.transport {
+            tracing::info!("Mail to {}: {}\n{}", to, subject, body);
+            return Ok(());
+        }
+
+        let from: Mailbox = self
+            .from
+            .parse()
+            .map_err(|e: AddressError| AppError::MailError(e.to_string()))?;
+        let to: Mailbox = to
+            .parse()
+            .map_err(|e: AddressError| AppError::MailError(e.to_string()))?;
+
+        let message = Message::builder()
+            .from(from)
+            .to(to)
+            .subject(subject)
+            .header(ContentType::TEXT_HTML)
+            .body(body)
+            .map_err(|e| AppError::MailError(e.to_string()))?;
+
+        match &self.transport {
+            Transport::Smtp(transport) => {
+                transport
+                    .send(message)
+                    .await
+                    .map_err(|e| AppError::MailError(e.to_string()))?;
+            }
+            Transport::File(transport) => {
+                transport
+                    .send(message)
+                    .await
+                    .map_err(|e| AppError::MailError(e.to_string()))?;
+            }
+            Transport::Log => {}
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use std::{collections::HashMap, future::Future, time::Instant};
+
+use actix_multi_session::storage::{LoadError, SaveError, SessionKey, SessionStore, UpdateError};
+use actix_web::cookie::time::Duration;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use super::{
+    database::DbPool,
+    errors::{AppError, AppResult},
+};
+
+/// Metrics of the server, exposed in the Prometheus format. Cloning it is cheap, the clones
+/// update the same metrics.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    session_store_duration: HistogramVec,
+    session_store_errors: IntCounterVec,
+    db_connections: IntGaugeVec,
+    s3_operations: IntCounterVec,
+    s3_bytes: IntCounterVec,
+    active_uploads: IntGauge,
+    login_failures: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> AppResult<Self> {
+        let registry = Registry::new_custom(Some("game_sync".to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests handled"),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time spent handling the HTTP requests",
+            ),
+            &["method", "route"],
+        )?;
+        let session_store_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "session_store_duration_seconds",
+                "Time spent by the session store operations",
+            )
+            .buckets(vec![
+                0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+            ]),
+            &["operation"],
+        )?;
+        let session_store_errors = IntCounterVec::new(
+            Opts::new(
+                "session_store_errors_total",
+                "Number of failed session store operations",
+            ),
+            &["operation"],
+        )?;
+        let db_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_connections",
+                "Number of connections of the database pool",
+            ),
+            &["state"],
+        )?;
+        let s3_operations = IntCounterVec::new(
+            Opts::new("s3_operations_total", "Number of storage operations"),
+            &["operation", "outcome"],
+        )?;
+        let s3_bytes = IntCounterVec::new(
+            Opts::new(
+                "s3_bytes_total",
+                "Bytes sent to and received from the storage",
+            ),
+            &["direction"],
+        )?;
+        let active_uploads = IntGauge::new(
+            "active_uploads",
+            "Number of uploaded files being processed and stored",
+        )?;
+        let login_failures = IntCounterVec::new(
+            Opts::new("login_failures_total", "Number of failed logins"),
+            &["method"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(session_store_duration.clone()))?;
+        registry.register(Box::new(session_store_errors.clone()))?;
+        registry.register(Box::new(db_connections.clone()))?;
+        registry.register(Box::new(s3_operations.clone()))?;
+        registry.register(Box::new(s3_bytes.clone()))?;
+        registry.register(Box::new(active_uploads.clone()))?;
+        registry.register(Box::new(login_failures.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            session_store_duration,
+            session_store_errors,
+            db_connections,
+            s3_operations,
+            s3_bytes,
+            active_uploads,
+            login_failures,
+        })
+    }
+
+    /// `route` is the pattern of the matched route, e.g. `/api/games/{id}`, to keep the number of
+    /// series bounded
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, started_at: Instant) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(started_at.elapsed().as_secs_f64());
+    }
+
+    /// Count a storage operation by its outcome
+    pub fn observe_s3<T>(&self, operation: &str, result: &AppResult<T>) {
+        let outcome = if result.is_ok() { "success" } else { "error" };
+        self.s3_operations
+            .with_label_values(&[operation, outcome])
+            .inc();
+    }
+
+    pub fn add_s3_bytes(&self, direction: &str, bytes: usize) {
+        self.s3_bytes
+            .with_label_values(&[direction])
+            .inc_by(bytes as u64);
+    }
+
+    /// Count the upload as active until the returned guard is dropped
+    pub fn track_upload(&self) -> UploadGuard {
+        self.active_uploads.inc();
+        UploadGuard {
+            gauge: self.active_uploads.clone(),
+        }
+    }
+
+    /// `method` is the way the user tried to log in, e.g. `password` or `passkey`
+    pub fn login_failed(&self, method: &str) {
+        self.login_failures.with_label_values(&[method]).inc();
+    }
+
+    /// Count the failed logins in the result of a login
+    pub fn track_login<T>(&self, method: &str, result: AppResult<T>) -> AppResult<T> {
+        if let Err(AppError::Unauthorized | AppError::AccountDisabled) = &result {
+            self.login_failed(method);
+        }
+
+        result
+    }
+
+    /// Text exposition of the metrics, the state of the database pool is read at this time
+    pub fn render(&self, db: &DbPool) -> AppResult<String> {
+        if let DbPool::SqlxPostgresPoolConnection(_) = db {
+            let pool = db.get_postgres_connection_pool();
+            let idle = pool.num_idle() as i64;
+            let size = pool.size() as i64;
+
+            self.db_connections.with_label_values(&["idle"]).set(idle);
+            self.db_connections
+                .with_label_values(&["active"])
+                .set(size - idle);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    async fn time_session<T, E>(
+        &self,
+        operation: &str,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let started_at = Instant::now();
+        let result = future.await;
+
+        self.session_store_duration
+            .with_label_values(&[operation])
+            .observe(started_at.elapsed().as_secs_f64());
+        if result.is_err() {
+            self.session_store_errors
+                .with_label_values(&[operation])
+                .inc();
+        }
+
+        result
+    }
+}
+
+pub struct UploadGuard {
+    gauge: IntGauge,
+}
+
+impl Drop for UploadGuard {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
+/// Session store measuring the latency and the errors of another one
+#[derive(Clone)]
+pub struct MeteredSessionStore<S> {
+    inner: S,
+    metrics: Metrics,
+}
+
+impl<S> MeteredSessionStore<S> {
+    pub fn new(inner: S, metrics: Metrics) -> Self {
+        Self { inner, metrics }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<S: SessionStore> SessionStore for MeteredSessionStore<S> {
+    async fn load(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<Option<HashMap<String, String>>, LoadError> {
+        self.metrics
+            .time_session("load", self.inner.load(session_key))
+            .await
+    }
+
+    async fn save(
+        &self,
+        session_state: HashMap<String, String>,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        self.metrics
+            .time_session("save", self.inner.save(session_state, ttl))
+            .await
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: HashMap<String, String>,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        self.metrics
+            .time_session("update", self.inner.update(session_key, session_state, ttl))
+            .await
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> Result<(), anyhow::Error> {
+        self.metrics
+            .time_session("update_ttl", self.inner.update_ttl(session_key, ttl))
+            .await
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        self.metrics
+            .time_session("delete", self.inner.delete(session_key))
+            .await
+    }
+}
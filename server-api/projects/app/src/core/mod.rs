@@ -1,6 +1,15 @@
+pub mod audit;
 pub mod config;
 pub mod database;
 pub mod errors;
+pub mod jobs;
+pub mod mail;
+pub mod metrics;
+pub mod oidc;
+pub mod openapi;
+pub mod rate_limit;
 pub mod s3;
 pub mod setup;
+pub mod telemetry;
 pub mod types;
+pub mod webauthn;
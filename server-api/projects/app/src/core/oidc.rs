@@ -0,0 +1,404 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use openidconnect::{
+    core::{CoreAuthenticationFlow, CoreClient, CoreProviderMetadata},
+    reqwest::async_http_client,
+    AuthorizationCode, ClientId, ClientSecret, CsrfToken, IssuerUrl, Nonce, PkceCodeChallenge,
+    PkceCodeVerifier, RedirectUrl, Scope, TokenResponse,
+};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+use super::{
+    config::OidcConfig,
+    errors::{AppError, AppResult},
+};
+
+#[derive(Clone)]
+pub struct OidcClient {
+    client: CoreClient,
+    scopes: Vec<String>,
+    groups_claim: String,
+}
+
+/// Pending authorization request, kept in the session until the provider redirects back
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcState {
+    csrf_token: String,
+    nonce: String,
+    pkce_verifier: String,
+    redirect_url: Option<String>,
+}
+
+/// Identity of the user, extracted from the verified ID token
+#[derive(Debug)]
+pub struct OidcIdentity {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub groups: Vec<String>,
+}
+
+#[tracing::instrument("initialize oidc client", skip(config))]
+pub async fn init_client(config: &OidcConfig) -> AppResult<OidcClient> {
+    let issuer_url = IssuerUrl::new(config.issuer_url.clone()).map_err(anyhow::Error::from)?;
+    let redirect_url =
+        RedirectUrl::new(config.redirect_url.clone()).map_err(anyhow::Error::from)?;
+
+    let provider_metadata = CoreProviderMetadata::discover_async(issuer_url, async_http_client)
+        .await
+        .map_err(|e| AppError::OidcError(e.to_string()))?;
+
+    let client = CoreClient::from_provider_metadata(
+        provider_metadata,
+        ClientId::new(config.client_id.clone()),
+        config.client_secret.clone().map(ClientSecret::new),
+    )
+    .set_redirect_uri(redirect_url);
+
+    Ok(OidcClient {
+        client,
+        scopes: config.scopes.clone(),
+        groups_claim: config.groups_claim.clone(),
+    })
+}
+
+impl OidcClient {
+    /// Build the URL of the provider login page, using PKCE.
+    /// `redirect_url` overrides the configured callback, e.g. for the loopback of the desktop client.
+    pub fn authorize(&self, redirect_url: Option<RedirectUrl>) -> (String, OidcState) {
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+        let mut request = self
+            .client
+            .authorize_url(
+                CoreAuthenticationFlow::AuthorizationCode,
+                CsrfToken::new_random,
+                Nonce::new_random,
+            )
+            .set_pkce_challenge(pkce_challenge);
+
+        for scope in &self.scopes {
+            request = request.add_scope(Scope::new(scope.clone()));
+        }
+
+        if let Some(redirect_url) = &redirect_url {
+            request = request.set_redirect_uri(Cow::Borrowed(redirect_url));
+        }
+
+        let (url, csrf_token, nonce) = request.url();
+
+        let state = OidcState {
+            csrf_token: csrf_token.secret().clone(),
+            nonce: nonce.secret().clone(),
+            pkce_verifier: pkce_verifier.secret().clone(),
+            redirect_url: redirect_url.map(|url| url.to_string()),
+        };
+
+        (url.to_string(), state)
+    }
+
+    /// Exchange the authorization code and verify the returned ID token
+    #[tracing::instrument("exchange oidc code", skip(self, code, state, oidc_state))]
+    pub async fn exchange(
+        &self,
+        code: String,
+        state: &str,
+        oidc_state: OidcState,
+    ) -> AppResult<OidcIdentity> {
+        if state != oidc_state.csrf_token {
+            return Err(AppError::Unauthorized);
+        }
+
+        let mut request = self
+            .client
+            .exchange_code(AuthorizationCode::new(code))
+            .set_pkce_verifier(PkceCodeVerifier::new(oidc_state.pkce_verifier));
+
+        let redirect_url = oidc_state
+            .redirect_url
+            .map(RedirectUrl::new)
+            .transpose()
+            .map_err(anyhow::Error::from)?;
+
+        if let Some(redirect_url) = &redirect_url {
+            request = request.set_redirect_uri(Cow::Borrowed(redirect_url));
+        }
+
+        let token_response = request
+            .request_async(async_http_client)
+            .await
+            .map_err(|e| AppError::OidcError(e.to_string()))?;
+
+        let id_token = token_response
+            .id_token()
+            .ok_or(AppError::OidcError("No ID token returned".to_string()))?;
+
+        let claims = id_token
+            .claims(
+                &self.client.id_token_verifier(),
+                &Nonce::new(oidc_state.nonce),
+            )
+            .map_err(|e| AppError::OidcError(e.to_string()))?;
+
+        Ok(OidcIdentity {
+            subject: claims.subject().as_str().to_owned(),
+            email: claims.email().map(|email| email.as_str().to_owned()),
+            email_verified: claims.email_verified().unwrap_or(false),
+            groups: self.extract_groups(&id_token.to_string()),
+        })
+    }
+
+    /// The groups claim is not standard, so it is read from the raw payload of the ID token.
+    /// The token signature has already been verified at this point.
+    fn extract_groups(&self, id_token: &str) -> Vec<String> {
+        let payload = id_token
+            .split('.')
+            .nth(1)
+            .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+            .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok());
+
+        let Some(payload) = payload else {
+            return vec![];
+        };
+
+        match payload.get(&self.groups_claim) {
+            Some(serde_json::Value::Array(groups)) => groups
+                .iter()
+                .filter_map(|group| group.as_str().map(str::to_owned))
+                .collect(),
+            Some(serde_json::Value::String(group)) => vec![group.clone()],
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use openidconnect::{
+        core::{
+            CoreClient, CoreGenderClaim, CoreJsonWebKeySet, CoreJsonWebKeyType,
+            CoreJweContentEncryptionAlgorithm, CoreJwsSigningAlgorithm, CoreRsaPrivateSigningKey,
+        },
+        AdditionalClaims, Audience, AuthUrl, ClientId, EndUserEmail, IdToken, IdTokenClaims,
+        IssuerUrl, JsonWebKeyId, Nonce, PrivateSigningKey, RedirectUrl, StandardClaims,
+        SubjectIdentifier, TokenUrl,
+    };
+    use sha2::{Digest, Sha256};
+
+    use super::{OidcClient, OidcState};
+    use crate::core::errors::AppError;
+
+    const ISSUER: &str = "https://sso.example.com";
+    const CLIENT_ID: &str = "game-sync";
+    const CODE: &str = "authorization-code";
+    const SIGNING_KEY: &str = include_str!("../../tests/fixtures/oidc_signing_key.pem");
+
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+    struct GroupsClaim {
+        groups: Vec<String>,
+    }
+
+    impl AdditionalClaims for GroupsClaim {}
+
+    fn signing_key() -> CoreRsaPrivateSigningKey {
+        CoreRsaPrivateSigningKey::from_pem(SIGNING_KEY, Some(JsonWebKeyId::new("test".to_owned())))
+            .unwrap()
+    }
+
+    /// Client of the mock provider, which only serves the token endpoint
+    fn client(token_url: String) -> OidcClient {
+        let client = CoreClient::new(
+            ClientId::new(CLIENT_ID.to_owned()),
+            None,
+            IssuerUrl::new(ISSUER.to_owned()).unwrap(),
+            AuthUrl::new(format!("{}/authorize", ISSUER)).unwrap(),
+            Some(TokenUrl::new(token_url).unwrap()),
+            None,
+            CoreJsonWebKeySet::new(vec![signing_key().as_verification_key()]),
+        )
+        .set_redirect_uri(
+            RedirectUrl::new("https://game-sync.example.com/admin/auth/oidc/callback".to_owned())
+                .unwrap(),
+        );
+
+        OidcClient {
+            client,
+            scopes: vec!["email".to_owned()],
+            groups_claim: "groups".to_owned(),
+        }
+    }
+
+    fn query_param(url: &str, name: &str) -> String {
+        url::Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    }
+
+    fn id_token(nonce: &str) -> String {
+        let now = chrono::Utc::now();
+        let claims = IdTokenClaims::new(
+            IssuerUrl::new(ISSUER.to_owned()).unwrap(),
+            vec![Audience::new(CLIENT_ID.to_owned())],
+            now + chrono::Duration::minutes(5),
+            now,
+            StandardClaims::new(SubjectIdentifier::new("subject".to_owned()))
+                .set_email(Some(EndUserEmail::new("player@example.com".to_owned())))
+                .set_email_verified(Some(true)),
+            GroupsClaim {
+                groups: vec!["publishers".to_owned()],
+            },
+        )
+        .set_nonce(Some(Nonce::new(nonce.to_owned())));
+
+        IdToken::<
+            GroupsClaim,
+            CoreGenderClaim,
+            CoreJweContentEncryptionAlgorithm,
+            CoreJwsSigningAlgorithm,
+            CoreJsonWebKeyType,
+        >::new(
+            claims,
+            &signing_key(),
+            CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256,
+            None,
+            None,
+        )
+        .unwrap()
+        .to_string()
+    }
+
+    /// Token endpoint answering a single request. The ID token is only issued for the expected
+    /// code and a PKCE verifier matching the challenge of the authorization request.
+    fn serve_token(listener: TcpListener, code_challenge: String, nonce: String) {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+
+                match line.trim_end().split_once(':') {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        content_length = value.trim().parse().unwrap()
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let param = |name: &str| {
+                url::form_urlencoded::parse(&body)
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap_or_default()
+            };
+            let verifier_hash = URL_SAFE_NO_PAD.encode(Sha256::digest(param("code_verifier")));
+
+            let (status, response) = if param("code") == CODE && verifier_hash == code_challenge {
+                let token = id_token(&nonce);
+                (
+                    "200 OK",
+                    serde_json::json!({
+                        "access_token": "access-token",
+                        "token_type": "bearer",
+                        "id_token": token,
+                    }),
+                )
+            } else {
+                (
+                    "400 Bad Request",
+                    serde_json::json!({ "error": "invalid_grant" }),
+                )
+            };
+
+            let response = response.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            )
+            .unwrap();
+        });
+    }
+
+    /// Start an authorization request against a new mock provider. The state goes through the
+    /// session between the redirect and the callback.
+    fn authorize() -> (OidcClient, TcpListener, String, OidcState) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = client(format!("http://{}/token", listener.local_addr().unwrap()));
+
+        let (url, state) = client.authorize(None);
+        let state: OidcState =
+            serde_json::from_value(serde_json::to_value(&state).unwrap()).unwrap();
+
+        (client, listener, url, state)
+    }
+
+    #[actix_rt::test]
+    async fn oidc_authorization_round_trip() {
+        let (client, listener, url, state) = authorize();
+
+        assert_eq!(query_param(&url, "code_challenge_method"), "S256");
+        assert_eq!(query_param(&url, "state"), state.csrf_token);
+        assert_eq!(query_param(&url, "nonce"), state.nonce);
+        serve_token(
+            listener,
+            query_param(&url, "code_challenge"),
+            state.nonce.clone(),
+        );
+
+        let csrf_token = state.csrf_token.clone();
+        let identity = client
+            .exchange(CODE.to_owned(), &csrf_token, state)
+            .await
+            .unwrap();
+
+        assert_eq!(identity.subject, "subject");
+        assert_eq!(identity.email.as_deref(), Some("player@example.com"));
+        assert!(identity.email_verified);
+        assert_eq!(identity.groups, vec!["publishers".to_owned()]);
+    }
+
+    #[actix_rt::test]
+    async fn oidc_exchange_rejects_another_state() {
+        let (client, _listener, _, state) = authorize();
+
+        let result = client.exchange(CODE.to_owned(), "forged", state).await;
+
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[actix_rt::test]
+    async fn oidc_exchange_rejects_another_verifier() {
+        let (client, listener, url, mut state) = authorize();
+        serve_token(
+            listener,
+            query_param(&url, "code_challenge"),
+            state.nonce.clone(),
+        );
+
+        // Verifier of another authorization request
+        state.pkce_verifier = client.authorize(None).1.pkce_verifier;
+        let csrf_token = state.csrf_token.clone();
+        let result = client.exchange(CODE.to_owned(), &csrf_token, state).await;
+
+        assert!(matches!(result, Err(AppError::OidcError(_))));
+    }
+}
@@ -0,0 +1,393 @@
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+        ArrayBuilder, ObjectBuilder, Ref, RefOr, Schema,
+    },
+    Modify, OpenApi,
+};
+
+use crate::{
+    controllers::{admin, api, base, health},
+    core::{
+        config::RegistrationMode,
+        errors::{ErrorCode, FieldError, ProblemDetails},
+        jobs::{JobProgress, JobStatus},
+        rate_limit::RateLimitCounter,
+        s3::{DownloadUrl, PresignedUrl, StoredObject, UploadMode},
+    },
+    entities::{
+        audit_log::Model as AuditLogModel,
+        game::Model as GameModel,
+        game_banner::BannerType,
+        game_media::MediaKind,
+        invitation::Model as InvitationModel,
+        passkey::Model as PasskeyModel,
+        user::{Model as UserModel, UserRole},
+    },
+    helpers::images::ImageFormat,
+    models::{
+        account::{EmailInput, ResetPasswordInput, VerifyEmailInput},
+        admin::JobStarted,
+        games::{
+            FacetCount, GameBanner, GameBannerColorUpload, GameBannerImageUpload, GameBannerInput,
+            GameDetails, GameFacets, GameInput, GameMedia, GameMediaReorderInput, GameMediaSet,
+            GameMediaUpload, GameSearchResponse, GameTags, GameTrailerInput, ImageVariant,
+            Platform, SystemRequirements,
+        },
+        invitation::{InvitationCreateInput, InvitationCreated},
+        oidc::{OidcAuthorization, OidcAuthorizeInput, OidcCallbackInput},
+        pagination::PaginationMeta,
+        passkey::{PasskeyLoginStartInput, PasskeyRegisterInput},
+        search::{SortBy, SortOrder},
+        setup::SetupInput,
+        storage::{GcReport, PendingUpload},
+        two_factor::{
+            RecoveryCodesResponse, TwoFactorChallenge, TwoFactorCodeInput, TwoFactorEnrollment,
+        },
+        user::{
+            ProfilePasswordInput, ProfileUpdateInput, UserAdminCreateInput, UserCreateInput,
+            UserLoginRequest, UserPasswordInput, UserUpdateInput,
+        },
+    },
+};
+
+/// OpenAPI document of the routes, served at `/api/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Game Sync API"),
+    paths(
+        base::status,
+        health::live,
+        health::ready,
+        // Desktop client API
+        api::auth::login,
+        api::auth::verify_two_factor,
+        api::auth::me,
+        api::auth::logout,
+        api::oidc::authorize,
+        api::oidc::callback,
+        api::games::get_games,
+        api::games::create_game,
+        api::games::get_deleted_games,
+        api::games::get_game,
+        api::games::update_game,
+        api::games::delete_game,
+        api::games::restore_game,
+        api::games::upload_game_banner,
+        api::games::delete_game_banner,
+        api::games::upload_game_media,
+        api::games::set_game_trailer,
+        api::games::reorder_game_screenshots,
+        api::games::delete_game_media,
+        api::versions::upload_file,
+        api::versions::download_file,
+        api::saves::upload_save,
+        api::saves::download_save,
+        // Admin panel API
+        admin::setup::setup,
+        admin::auth::register,
+        admin::auth::login,
+        admin::auth::me,
+        admin::auth::logout,
+        admin::account::verify_email,
+        admin::account::resend_verification,
+        admin::account::forgot_password,
+        admin::account::reset_password,
+        admin::two_factor::verify,
+        admin::two_factor::enroll,
+        admin::two_factor::enable,
+        admin::two_factor::disable,
+        admin::two_factor::regenerate_recovery_codes,
+        admin::oidc::authorize,
+        admin::oidc::callback,
+        admin::passkeys::get_passkeys,
+        admin::passkeys::start_registration,
+        admin::passkeys::finish_registration,
+        admin::passkeys::delete_passkey,
+        admin::passkeys::start_authentication,
+        admin::passkeys::finish_authentication,
+        admin::profile::get_profile,
+        admin::profile::update_profile,
+        admin::profile::change_password,
+        admin::users::get_users,
+        admin::users::create_user,
+        admin::users::get_user,
+        admin::users::update_user,
+        admin::users::delete_user,
+        admin::users::disable_user,
+        admin::users::enable_user,
+        admin::users::set_password,
+        admin::invitations::get_invitations,
+        admin::invitations::create_invitation,
+        admin::invitations::delete_invitation,
+        admin::games::purge_game,
+        admin::storage::get_gc_report,
+        admin::storage::run_gc,
+        admin::jobs::get_jobs,
+        admin::audit::get_audit_logs,
+        admin::rate_limits::get_counters,
+    ),
+    components(schemas(
+        base::Status,
+        health::Health,
+        health::HealthStatus,
+        health::DependencyHealth,
+        RegistrationMode,
+        ProblemDetails,
+        ErrorCode,
+        FieldError,
+        JobStarted,
+        JobProgress,
+        JobStatus,
+        RateLimitCounter,
+        PresignedUrl,
+        DownloadUrl,
+        UploadMode,
+        StoredObject,
+        GcReport,
+        PendingUpload,
+        AuditLogModel,
+        GameModel,
+        BannerType,
+        MediaKind,
+        InvitationModel,
+        PasskeyModel,
+        UserModel,
+        UserRole,
+        ImageFormat,
+        EmailInput,
+        ResetPasswordInput,
+        VerifyEmailInput,
+        FacetCount,
+        GameBannerColorUpload,
+        GameBannerInput,
+        GameBannerImageUpload,
+        GameBanner,
+        GameInput,
+        GameFacets,
+        GameDetails,
+        GameMediaReorderInput,
+        GameMedia,
+        GameMediaSet,
+        GameMediaUpload,
+        GameSearchResponse,
+        GameTags,
+        GameTrailerInput,
+        Platform,
+        SystemRequirements,
+        ImageVariant,
+        InvitationCreateInput,
+        InvitationCreated,
+        OidcAuthorization,
+        OidcAuthorizeInput,
+        OidcCallbackInput,
+        PaginationMeta,
+        PasskeyLoginStartInput,
+        PasskeyRegisterInput,
+        SortBy,
+        SortOrder,
+        SetupInput,
+        RecoveryCodesResponse,
+        TwoFactorChallenge,
+        TwoFactorCodeInput,
+        TwoFactorEnrollment,
+        ProfilePasswordInput,
+        ProfileUpdateInput,
+        UserAdminCreateInput,
+        UserCreateInput,
+        UserLoginRequest,
+        UserPasswordInput,
+        UserUpdateInput,
+    )),
+    modifiers(&SecuritySchemes, &PaginatedSchemas)
+)]
+pub struct ApiDoc;
+
+/// The desktop client sends its session token as a bearer token, the admin panel uses a cookie
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "session_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "session_cookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("game-sync-session"))),
+        );
+    }
+}
+
+/// `Paginated` is generic and shared with the clients, its listings are documented by name
+struct PaginatedSchemas;
+
+impl Modify for PaginatedSchemas {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        for (name, item) in [
+            ("PaginatedGames", "GameModel"),
+            ("PaginatedUsers", "UserModel"),
+            ("PaginatedAuditLogs", "AuditLogModel"),
+        ] {
+            let data = ArrayBuilder::new()
+                .items(Ref::from_schema_name(item))
+                .build();
+            let schema = ObjectBuilder::new()
+                .property("data", RefOr::T(Schema::Array(data)))
+                .required("data")
+                .property("meta", Ref::from_schema_name("PaginationMeta"))
+                .required("meta")
+                .build();
+
+            components
+                .schemas
+                .insert(name.to_string(), RefOr::T(Schema::Object(schema)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeSet, HashMap},
+        future::ready,
+    };
+
+    use actix_multi_session::storage::{
+        LoadError, SaveError, SessionKey, SessionStore, UpdateError,
+    };
+    use actix_web::{cookie::time::Duration, test, App, HttpResponse};
+    use utoipa::OpenApi;
+
+    use super::ApiDoc;
+    use crate::{core::config::SecretKey, routes};
+
+    /// The requests are answered before reaching the session middleware
+    #[derive(Clone)]
+    struct UnusedSessionStore;
+
+    #[async_trait::async_trait(?Send)]
+    impl SessionStore for UnusedSessionStore {
+        async fn load(&self, _: &SessionKey) -> Result<Option<HashMap<String, String>>, LoadError> {
+            unreachable!()
+        }
+
+        async fn save(
+            &self,
+            _: HashMap<String, String>,
+            _: &Duration,
+        ) -> Result<SessionKey, SaveError> {
+            unreachable!()
+        }
+
+        async fn update(
+            &self,
+            _: SessionKey,
+            _: HashMap<String, String>,
+            _: &Duration,
+        ) -> Result<SessionKey, UpdateError> {
+            unreachable!()
+        }
+
+        async fn update_ttl(&self, _: &SessionKey, _: &Duration) -> Result<(), anyhow::Error> {
+            unreachable!()
+        }
+
+        async fn delete(&self, _: &SessionKey) -> Result<(), anyhow::Error> {
+            unreachable!()
+        }
+    }
+
+    /// Replaces the parameters of a path by a value, any value matches them
+    fn uri(path: &str) -> String {
+        path.split('/')
+            .map(|segment| {
+                if segment.starts_with('{') {
+                    "1"
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Every documented operation must be registered with the same method and path, and the
+    /// other way around. The requests are answered with the matched pattern before the
+    /// middlewares, which need the app data, to check the recorded paths are the ones of the
+    /// resources.
+    #[actix_rt::test]
+    async fn spec_matches_routes() {
+        let secret_key = SecretKey("a".repeat(64));
+        let mut routes = None;
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(|req, _| {
+                    let pattern = req.match_pattern().unwrap_or_default();
+                    ready(Ok(req.into_response(HttpResponse::Ok().body(pattern))))
+                })
+                .configure(|cfg| {
+                    routes = Some(routes::register_documented_routes(
+                        cfg,
+                        UnusedSessionStore,
+                        &secret_key,
+                    ));
+                }),
+        )
+        .await;
+        let routes = routes.unwrap();
+
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let documented = spec["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .flat_map(|(path, item)| {
+                item.as_object()
+                    .unwrap()
+                    .keys()
+                    .map(move |method| (method.to_uppercase(), path.clone()))
+            })
+            .collect::<BTreeSet<_>>();
+        let registered = routes
+            .documented
+            .iter()
+            .map(|(method, path)| (method.to_string(), path.clone()))
+            .collect::<BTreeSet<_>>();
+
+        let missing = documented.difference(&registered).collect::<Vec<_>>();
+        assert!(
+            missing.is_empty(),
+            "Documented but not registered: {:?}",
+            missing
+        );
+
+        let undocumented = registered.difference(&documented).collect::<Vec<_>>();
+        assert!(
+            undocumented.is_empty(),
+            "Registered but not documented: {:?}",
+            undocumented
+        );
+
+        let mut mismatches = Vec::new();
+
+        for (_, path) in routes.documented.iter().chain(&routes.undocumented) {
+            let request = test::TestRequest::get().uri(&uri(path)).to_request();
+            let pattern = test::call_and_read_body(&app, request).await;
+
+            if pattern != path.as_bytes() {
+                mismatches.push((path.clone(), String::from_utf8_lossy(&pattern).into_owned()));
+            }
+        }
+
+        assert!(
+            mismatches.is_empty(),
+            "Recorded paths matched by another route, or none: {:?}",
+            mismatches
+        );
+    }
+}
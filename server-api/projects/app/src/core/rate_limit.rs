@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use serde::Serialize;
+use time::Duration;
+use utoipa::ToSchema;
+
+/// Above this number of tracked buckets, the idle ones are dropped on the next insertion. When
+/// there are still too many, the least recently used ones are evicted down to
+/// `EVICTION_TARGET`, so the memory stays bounded whatever the number of keys.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+const EVICTION_TARGET: usize = MAX_TRACKED_BUCKETS * 9 / 10;
+
+#[derive(Debug, Clone, Copy)]
+pub enum RateLimitKey {
+    /// Key by the authenticated user id, falling back to the client IP for guests
+    User,
+    /// Key by the bearer token of the authenticated session, falling back to the client IP
+    ApiKey,
+    /// Key by the client IP
+    Ip,
+}
+
+/// A token bucket policy: `capacity` requests are allowed per `period`, refilled continuously
+#[derive(Debug, Clone)]
+pub struct RateLimitPolicy {
+    pub name: &'static str,
+    pub capacity: u32,
+    pub period: Duration,
+    pub key: RateLimitKey,
+}
+
+impl RateLimitPolicy {
+    pub fn new(name: &'static str, capacity: u32, period: Duration, key: RateLimitKey) -> Self {
+        Self {
+            name,
+            capacity,
+            period,
+            key,
+        }
+    }
+
+    /// Number of tokens regained per second
+    fn refill_rate(&self) -> f64 {
+        self.capacity as f64 / self.period.as_seconds_f64()
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    capacity: u32,
+    refill_rate: f64,
+    updated_at: Instant,
+    last_used: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity as f64);
+        self.updated_at = now;
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity as f64
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RateLimitCounter {
+    pub policy: String,
+    pub key: String,
+    pub capacity: u32,
+    pub remaining: u32,
+}
+
+/// In-memory store of the token buckets, shared by all the rate limiting middlewares
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<HashMap<(&'static str, String), TokenBucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a token from the bucket of `key` for the given policy.
+    /// Return the number of seconds to wait before retrying if the bucket is empty.
+    pub fn check(&self, policy: &RateLimitPolicy, key: String) -> Result<(), u64> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            Self::prune(&mut buckets);
+            Self::evict_least_recently_used(&mut buckets, EVICTION_TARGET);
+        }
+
+        let bucket = buckets
+            .entry((policy.name, key))
+            .or_insert_with(|| TokenBucket {
+                tokens: policy.capacity as f64,
+                capacity: policy.capacity,
+                refill_rate: policy.refill_rate(),
+                updated_at: Instant::now(),
+                last_used: Instant::now(),
+            });
+
+        bucket.refill();
+        bucket.last_used = Instant::now();
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let retry_after = ((1.0 - bucket.tokens) / bucket.refill_rate).ceil() as u64;
+        Err(retry_after.max(1))
+    }
+
+    /// List the buckets currently being consumed
+    pub fn counters(&self) -> Vec<RateLimitCounter> {
+        let mut buckets = self.buckets.lock().unwrap();
+        Self::prune(&mut buckets);
+
+        buckets
+            .iter()
+            .map(|((policy, key), bucket)| RateLimitCounter {
+                policy: policy.to_string(),
+                key: key.clone(),
+                capacity: bucket.capacity,
+                remaining: bucket.tokens.floor() as u32,
+            })
+            .collect()
+    }
+
+    /// Drop the buckets which are back to full capacity, they are equivalent to untracked ones
+    fn prune(buckets: &mut HashMap<(&'static str, String), TokenBucket>) {
+        buckets.retain(|_, bucket| {
+            bucket.refill();
+            !bucket.is_full()
+        });
+    }
+
+    fn evict_least_recently_used(
+        buckets: &mut HashMap<(&'static str, String), TokenBucket>,
+        target: usize,
+    ) {
+        if buckets.len() <= target {
+            return;
+        }
+
+        let excess = buckets.len() - target;
+        let mut by_use: Vec<_> = buckets
+            .iter()
+            .map(|(key, bucket)| (bucket.last_used, key.clone()))
+            .collect();
+        by_use.sort_unstable_by_key(|(last_used, _)| *last_used);
+
+        for (_, key) in by_use.into_iter().take(excess) {
+            buckets.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration as StdDuration, Instant};
+
+    use actix_web::{http::header::RETRY_AFTER, ResponseError};
+    use time::Duration;
+
+    use super::{RateLimitKey, RateLimitPolicy, RateLimiter, EVICTION_TARGET, MAX_TRACKED_BUCKETS};
+    use crate::core::errors::AppError;
+
+    fn policy() -> RateLimitPolicy {
+        RateLimitPolicy::new("test", 5, Duration::seconds(10), RateLimitKey::Ip)
+    }
+
+    /// Move the clock of a bucket back, as if `elapsed` had passed since its last request
+    fn rewind(limiter: &RateLimiter, key: &str, elapsed: StdDuration) {
+        let mut buckets = limiter.buckets.lock().unwrap();
+        let bucket = buckets.get_mut(&("test", key.to_owned())).unwrap();
+        bucket.updated_at -= elapsed;
+    }
+
+    #[test]
+    fn bursts_are_limited_to_the_capacity() {
+        let limiter = RateLimiter::new();
+        let policy = policy();
+
+        for _ in 0..5 {
+            assert!(limiter.check(&policy, "ip:1".to_owned()).is_ok());
+        }
+
+        // A token comes back every 2 seconds
+        let retry_after = limiter.check(&policy, "ip:1".to_owned()).unwrap_err();
+        assert_eq!(retry_after, 2);
+
+        let response = AppError::TooManyRequests(retry_after).error_response();
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "2");
+
+        // The other keys have their own bucket
+        assert!(limiter.check(&policy, "ip:2".to_owned()).is_ok());
+    }
+
+    #[test]
+    fn buckets_are_refilled_over_time() {
+        let limiter = RateLimiter::new();
+        let policy = policy();
+
+        for _ in 0..5 {
+            limiter.check(&policy, "ip:1".to_owned()).unwrap();
+        }
+        assert!(limiter.check(&policy, "ip:1".to_owned()).is_err());
+
+        rewind(&limiter, "ip:1", StdDuration::from_secs(4));
+        assert!(limiter.check(&policy, "ip:1".to_owned()).is_ok());
+        assert!(limiter.check(&policy, "ip:1".to_owned()).is_ok());
+        assert!(limiter.check(&policy, "ip:1".to_owned()).is_err());
+
+        // Never above the capacity, however long the bucket was idle
+        rewind(&limiter, "ip:1", StdDuration::from_secs(60));
+        for _ in 0..5 {
+            assert!(limiter.check(&policy, "ip:1".to_owned()).is_ok());
+        }
+        assert!(limiter.check(&policy, "ip:1".to_owned()).is_err());
+    }
+
+    #[test]
+    fn least_recently_used_buckets_are_evicted() {
+        let limiter = RateLimiter::new();
+        let policy = policy();
+
+        for i in 0..MAX_TRACKED_BUCKETS {
+            limiter.check(&policy, i.to_string()).unwrap();
+        }
+
+        // The buckets are used in the order of their key, the first one last
+        let start = Instant::now() - StdDuration::from_secs(1);
+        for ((_, key), bucket) in limiter.buckets.lock().unwrap().iter_mut() {
+            let order = match key.parse::<u64>().unwrap() {
+                0 => MAX_TRACKED_BUCKETS as u64,
+                i => i,
+            };
+            bucket.last_used = start + StdDuration::from_micros(order);
+        }
+
+        limiter.check(&policy, "new".to_owned()).unwrap();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), EVICTION_TARGET + 1);
+
+        let evicted = MAX_TRACKED_BUCKETS - EVICTION_TARGET;
+        for (key, tracked) in [
+            ("0", true),
+            ("1", false),
+            (evicted.to_string().as_str(), false),
+            ((evicted + 1).to_string().as_str(), true),
+            ("new", true),
+        ] {
+            assert_eq!(
+                buckets.contains_key(&("test", key.to_owned())),
+                tracked,
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn full_buckets_are_pruned() {
+        let limiter = RateLimiter::new();
+        let policy = policy();
+
+        limiter.check(&policy, "ip:1".to_owned()).unwrap();
+        limiter.check(&policy, "ip:2".to_owned()).unwrap();
+        rewind(&limiter, "ip:1", StdDuration::from_secs(10));
+
+        let counters = limiter.counters();
+        assert_eq!(counters.len(), 1);
+        assert_eq!(counters[0].key, "ip:2");
+        assert_eq!(counters[0].remaining, 4);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, collections::HashMap, path::Path};
+use std::{borrow::Cow, path::Path};
 
 use s3::{
     creds::{Credentials, Rfc3339OffsetDateTime},
@@ -6,39 +6,71 @@ use s3::{
     Bucket, BucketConfiguration, PostPolicy, PostPolicyField, PostPolicyValue, Region,
 };
 use serde::Serialize;
-use time::{Duration, OffsetDateTime};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
 use tokio::{fs, io::AsyncReadExt};
+use utoipa::ToSchema;
 
-use super::{config::StorageConfig, errors::AppResult};
+use super::{
+    config::StorageConfig,
+    errors::{AppError, AppResult},
+    metrics::Metrics,
+};
+use crate::helpers::sigv4::{self, SigningKey, UnsignedRequest};
+
+pub use game_sync_common::uploads::{DownloadUrl, PresignedUrl, UploadMode};
 
 #[derive(Clone)]
 pub struct S3Client {
     bucket: Bucket,
     bucket_name: String,
     credentials: Credentials,
+    http: reqwest::Client,
+    metrics: Metrics,
 }
 
 /// Maximum file size for uploads using presigned post URLs, allow up to 500MB
 const MAX_FILE_SIZE: u32 = 1024 * 1024 * 500;
 
-#[derive(Debug, Serialize)]
-pub enum UploadMode {
-    /// Upload file using presigned POST URL
-    SingleUpload,
-    /// Upload file using presigned PUT URL
-    MultipartUpload,
+/// Validity of the presigned upload URLs, an upload can't finish after it
+pub const UPLOAD_URL_LIFETIME: Duration = Duration::hours(4);
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StoredObject {
+    pub key: String,
+    pub size: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub last_modified: OffsetDateTime,
+}
+
+/// Version of a stored file, or a delete marker, in the versioned bucket
+#[derive(Debug, Clone)]
+pub struct ObjectVersion {
+    pub key: String,
+    pub version_id: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct PresignedUrl {
-    upload_mode: UploadMode,
-    url: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    fields: Option<HashMap<String, String>>,
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListVersionsResult {
+    #[serde(default)]
+    is_truncated: bool,
+    next_key_marker: Option<String>,
+    next_version_id_marker: Option<String>,
+    #[serde(default)]
+    version: Vec<ListedVersion>,
+    #[serde(default)]
+    delete_marker: Vec<ListedVersion>,
 }
 
-#[tracing::instrument("initialize s3 client", skip(config))]
-pub async fn init_client(config: &StorageConfig) -> AppResult<S3Client> {
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListedVersion {
+    key: String,
+    version_id: String,
+}
+
+#[tracing::instrument("initialize s3 client", skip(config, metrics))]
+pub async fn init_client(config: &StorageConfig, metrics: Metrics) -> AppResult<S3Client> {
     let region = Region::Custom {
         region: "eu-central-1".to_owned(),
         endpoint: config.endpoint.clone(),
@@ -58,6 +90,8 @@ pub async fn init_client(config: &StorageConfig) -> AppResult<S3Client> {
         bucket,
         bucket_name: config.name.clone(),
         credentials,
+        http: reqwest::Client::new(),
+        metrics,
     })
 }
 
@@ -88,6 +122,18 @@ impl S3Client {
         Ok(())
     }
 
+    /// Check that the bucket is reachable, e.g. for health checks
+    pub async fn check_bucket(&self) -> AppResult<()> {
+        if !self.bucket.exists().await? {
+            return Err(AppError::Other(anyhow::anyhow!(
+                "Bucket {} doesn't exist",
+                self.bucket_name
+            )));
+        }
+
+        Ok(())
+    }
+
     // Create PUT presigned URL
     #[tracing::instrument("create put presigned url", skip(self, key_prefix))]
     pub async fn create_presigned_url(
@@ -96,7 +142,7 @@ impl S3Client {
         file_size: usize,
         key_prefix: &str,
     ) -> AppResult<PresignedUrl> {
-        let duration = Duration::hours(4);
+        let duration = UPLOAD_URL_LIFETIME;
         let path = format!("{key_prefix}{filename}");
 
         // Check if file size is within limits
@@ -141,6 +187,28 @@ impl S3Client {
         }
     }
 
+    /// Presigned GET URL to download a stored file
+    #[tracing::instrument("create get presigned url", skip(self))]
+    pub fn create_presigned_download(&self, key: &str) -> AppResult<DownloadUrl> {
+        let duration = Duration::hours(4);
+
+        let url = self
+            .bucket
+            .presign_get(key, duration.whole_seconds().abs() as u32, None)?;
+
+        Ok(DownloadUrl {
+            url,
+            expires_at: OffsetDateTime::now_utc() + duration,
+        })
+    }
+
+    /// Whether a file is stored under exactly this key
+    pub async fn file_exists(&self, key: &str) -> AppResult<bool> {
+        let objects = self.list_objects(key).await?;
+
+        Ok(objects.iter().any(|object| object.key == key))
+    }
+
     /// Upload file to S3 (only single small upload only)
     /// This will return the URL of the uploaded file
     /// WARNING: This is not recommended for large files !!! Be sure to check the file size before uploading
@@ -156,20 +224,249 @@ impl S3Client {
         let path = format!("{key_prefix}{filename}");
 
         let mut file = fs::File::open(file_path).await?;
+        let size = file.metadata().await?.len() as usize;
 
-        self.bucket.put_object_stream(&mut file, &path).await?;
+        let result = self
+            .bucket
+            .put_object_stream(&mut file, &path)
+            .await
+            .map_err(AppError::from);
+        self.metrics.observe_s3("upload", &result);
+        result?;
+        self.metrics.add_s3_bytes("sent", size);
 
         Ok(path)
     }
 
+    /// Upload an in-memory file to S3 under the given key
+    #[tracing::instrument("upload bytes", skip(self, content), fields(size = content.len()))]
+    pub async fn upload_bytes(
+        &self,
+        key: &str,
+        content: &[u8],
+        content_type: &str,
+    ) -> AppResult<()> {
+        let result = self
+            .bucket
+            .put_object_with_content_type(key, content, content_type)
+            .await
+            .map_err(AppError::from);
+        self.metrics.observe_s3("upload", &result);
+        result?;
+        self.metrics.add_s3_bytes("sent", content.len());
+
+        Ok(())
+    }
+
+    /// Public URL of a stored file
+    pub fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.bucket.url(), key)
+    }
+
+    /// List all the files stored under the given prefix, with their metadata
+    #[tracing::instrument("list objects", skip(self))]
+    pub async fn list_objects(&self, key_prefix: &str) -> AppResult<Vec<StoredObject>> {
+        let results = self
+            .bucket
+            .list(key_prefix.to_owned(), None)
+            .await
+            .map_err(AppError::from);
+        self.metrics.observe_s3("list", &results);
+        let results = results?;
+
+        let objects = results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .map(|object| StoredObject {
+                // Objects with an unreadable date are considered as just modified, to be kept
+                last_modified: OffsetDateTime::parse(&object.last_modified, &Rfc3339)
+                    .unwrap_or_else(|_| OffsetDateTime::now_utc()),
+                key: object.key,
+                size: object.size,
+            })
+            .collect();
+
+        Ok(objects)
+    }
+
     pub async fn delete_file(&self, key: &str) -> AppResult<()> {
-        self.bucket.delete_object(key).await?;
+        let result = self.bucket.delete_object(key).await.map_err(AppError::from);
+        self.metrics.observe_s3("delete", &result);
+        result?;
 
         Ok(())
     }
 
+    /// List every version and delete marker stored under the given prefix
+    #[tracing::instrument("list object versions", skip(self))]
+    pub async fn list_object_versions(&self, key_prefix: &str) -> AppResult<Vec<ObjectVersion>> {
+        let result = self.list_all_object_versions(key_prefix).await;
+        self.metrics.observe_s3("list", &result);
+
+        result
+    }
+
+    async fn list_all_object_versions(&self, key_prefix: &str) -> AppResult<Vec<ObjectVersion>> {
+        let mut versions = Vec::new();
+        let mut markers: Option<(String, String)> = None;
+
+        loop {
+            let mut query = vec![
+                ("versions", String::new()),
+                ("prefix", key_prefix.to_owned()),
+            ];
+            if let Some((key_marker, version_id_marker)) = markers.take() {
+                query.push(("key-marker", key_marker));
+                query.push(("version-id-marker", version_id_marker));
+            }
+
+            let body = self.send_signed("GET", None, &query).await?;
+            let page: ListVersionsResult = quick_xml::de::from_str(&body)
+                .map_err(|e| AppError::Other(anyhow::anyhow!("Invalid version listing: {}", e)))?;
+
+            versions.extend(
+                page.version
+                    .into_iter()
+                    .chain(page.delete_marker)
+                    .map(|version| ObjectVersion {
+                        key: version.key,
+                        version_id: version.version_id,
+                    }),
+            );
+
+            match (
+                page.is_truncated,
+                page.next_key_marker,
+                page.next_version_id_marker,
+            ) {
+                (true, Some(key_marker), Some(version_id_marker)) => {
+                    markers = Some((key_marker, version_id_marker))
+                }
+                _ => break,
+            }
+        }
+
+        Ok(versions)
+    }
+
+    /// Permanently delete a version of a file, unlike `delete_file` which only adds a delete marker
+    pub async fn delete_object_version(&self, version: &ObjectVersion) -> AppResult<()> {
+        let result = self
+            .send_signed(
+                "DELETE",
+                Some(&version.key),
+                &[("versionId", version.version_id.clone())],
+            )
+            .await;
+        self.metrics.observe_s3("delete", &result);
+        result?;
+
+        Ok(())
+    }
+
+    /// Send a request signed with the bucket credentials and return the body of the response
+    async fn send_signed(
+        &self,
+        method: &str,
+        key: Option<&str>,
+        query: &[(&str, String)],
+    ) -> AppResult<String> {
+        let base = url::Url::parse(&self.bucket.url()).map_err(|e| AppError::Other(e.into()))?;
+        let host = match (base.host_str(), base.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => {
+                return Err(AppError::Other(anyhow::anyhow!(
+                    "The storage endpoint has no host"
+                )))
+            }
+        };
+
+        let bucket_path = base.path().trim_end_matches('/');
+        let path = match key {
+            Some(key) => format!("{}/{}", bucket_path, sigv4::uri_encode(key, false)),
+            None => bucket_path.to_owned(),
+        };
+        let query = sigv4::canonical_query(query);
+
+        let (Some(access_key), Some(secret_key)) = (
+            self.credentials.access_key.as_deref(),
+            self.credentials.secret_key.as_deref(),
+        ) else {
+            return Err(AppError::Other(anyhow::anyhow!(
+                "The storage credentials are missing"
+            )));
+        };
+        let region = self.bucket.region.to_string();
+
+        // Temporary credentials, e.g. from an instance profile, come with a token
+        let session_token = self
+            .credentials
+            .security_token
+            .as_deref()
+            .or(self.credentials.session_token.as_deref());
+
+        let signed = sigv4::sign(
+            &UnsignedRequest {
+                method,
+                host: &host,
+                path: &path,
+                query: &query,
+                headers: &[("x-amz-content-sha256", sigv4::EMPTY_PAYLOAD_HASH)],
+                payload_hash: sigv4::EMPTY_PAYLOAD_HASH,
+            },
+            &SigningKey {
+                access_key,
+                secret_key,
+                session_token,
+                region: &region,
+                service: "s3",
+            },
+            OffsetDateTime::now_utc(),
+        );
+
+        let method = reqwest::Method::from_bytes(method.as_bytes())
+            .map_err(|e| AppError::Other(e.into()))?;
+        let mut request = self
+            .http
+            .request(
+                method,
+                format!("{}://{}{}?{}", base.scheme(), host, path, query),
+            )
+            .header("x-amz-content-sha256", sigv4::EMPTY_PAYLOAD_HASH)
+            .header("x-amz-date", signed.amz_date)
+            .header("authorization", signed.authorization);
+        if let Some(session_token) = session_token {
+            request = request.header("x-amz-security-token", session_token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::Other(e.into()))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::Other(e.into()))?;
+
+        if !status.is_success() {
+            return Err(AppError::S3Error(s3::error::S3Error::HttpFailWithBody(
+                status.as_u16(),
+                body,
+            )));
+        }
+
+        Ok(body)
+    }
+
     pub async fn fetch_file(&self, key: &str) -> AppResult<Vec<u8>> {
-        let file = self.bucket.get_object(key).await?;
-        Ok(file.bytes().to_vec())
+        let result = self.bucket.get_object(key).await.map_err(AppError::from);
+        self.metrics.observe_s3("fetch", &result);
+        let bytes = result?.bytes().to_vec();
+        self.metrics.add_s3_bytes("received", bytes.len());
+
+        Ok(bytes)
     }
 }
@@ -20,6 +20,10 @@ pub fn server_setup(cfg: &mut ServiceConfig, app_data: AppData) {
         .error_handler(errors::validated_json_error_handler);
     cfg.app_data(validated_query_config);
 
+    let validated_path_config = actix_web_validator::PathConfig::default()
+        .error_handler(errors::validated_json_error_handler);
+    cfg.app_data(validated_path_config);
+
     // Register routes
     cfg.configure(|cfg| routes::setup_routes(cfg, &app_data));
 }
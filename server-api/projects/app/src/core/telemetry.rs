@@ -0,0 +1,82 @@
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::core::{
+    config::{LogFormat, TelemetryConfig},
+    errors::AppError,
+};
+
+/// Flush the traces not exported yet when dropped
+pub struct TelemetryGuard {
+    otlp: bool,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if self.otlp {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Install the logger, and the export of the traces when a collector is configured
+pub fn init(config: &TelemetryConfig) -> Result<TelemetryGuard, AppError> {
+    let filter =
+        EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&config.log_level));
+    let filter = filter.map_err(|e| AppError::Other(e.into()))?;
+
+    let (pretty, json) = match config.log_format {
+        LogFormat::Pretty => (Some(fmt::layer()), None),
+        LogFormat::Json => (
+            None,
+            Some(
+                fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false),
+            ),
+        ),
+    };
+
+    let otlp = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            let tracer = init_tracer(config, endpoint)?;
+            Some(tracing_opentelemetry::layer().with_tracer(tracer))
+        }
+        None => None,
+    };
+    let guard = TelemetryGuard {
+        otlp: otlp.is_some(),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(pretty)
+        .with(json)
+        .with(otlp)
+        .try_init()
+        .map_err(|e| AppError::Other(e.into()))?;
+
+    Ok(guard)
+}
+
+fn init_tracer(config: &TelemetryConfig, endpoint: &str) -> Result<trace::Tracer, AppError> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .tonic()
+        .with_endpoint(endpoint);
+    let resource = Resource::new(vec![
+        KeyValue::new("service.name", config.service_name.clone()),
+        KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+    ]);
+
+    // The main runtime of actix is single threaded, the batches are sent from another thread
+    // to not block it when flushing on shutdown
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace::config().with_resource(resource))
+        .install_batch(runtime::TokioCurrentThread)
+        .map_err(|e| AppError::Other(e.into()))
+}
@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use webauthn_rs::prelude::{Url, Webauthn, WebauthnBuilder};
+
+use super::{config::WebauthnConfig, errors::AppResult};
+
+#[tracing::instrument("initialize webauthn", skip(config))]
+pub fn init_webauthn(config: &WebauthnConfig) -> AppResult<Arc<Webauthn>> {
+    let rp_origin = Url::parse(&config.rp_origin).map_err(anyhow::Error::from)?;
+
+    let webauthn = WebauthnBuilder::new(&config.rp_id, &rp_origin)?
+        .rp_name(&config.rp_name)
+        .build()?;
+
+    Ok(Arc::new(webauthn))
+}
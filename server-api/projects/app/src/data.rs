@@ -1,12 +1,18 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 use actix_multi_session::storage::RedisSessionStore;
 use tera::Tera;
+use webauthn_rs::prelude::Webauthn;
 
 use crate::core::{
     config::{AppConfig, SecretKey},
     database::DbPool,
     errors::AppResult,
+    jobs::JobTracker,
+    mail::Mailer,
+    metrics::{MeteredSessionStore, Metrics},
+    oidc::OidcClient,
+    rate_limit::RateLimiter,
     s3::S3Client,
 };
 
@@ -14,15 +20,27 @@ use crate::core::{
 pub struct AppData {
     pub tera: Tera,
     pub db: DbPool,
-    pub session_store: RedisSessionStore,
+    pub session_store: MeteredSessionStore<RedisSessionStore>,
     pub config: AppConfig,
     pub secret_key: SecretKey,
     pub s3: S3Client,
+    pub mailer: Mailer,
+    pub rate_limiter: RateLimiter,
+    pub jobs: JobTracker,
+    pub metrics: Metrics,
+    pub webauthn: Option<Arc<Webauthn>>,
+    pub oidc: Option<OidcClient>,
+    /// Only set while the app isn't configured
+    pub setup_token: Option<String>,
 }
 
 impl AppData {
     pub fn get_server_address(&self) -> AppResult<SocketAddr> {
-        let ip_addr = self.config.server.host.parse()?;
-        Ok(SocketAddr::new(ip_addr, self.config.server.port))
+        Ok(self.config.server.socket_address()?)
+    }
+
+    /// Separate address of the metrics endpoint, if configured
+    pub fn get_metrics_address(&self) -> AppResult<Option<SocketAddr>> {
+        Ok(self.config.server.metrics_socket_address()?)
     }
 }
@@ -1,11 +1,13 @@
-use crate::helpers;
-
 use super::game_banner::{self, Entity as GameBanner};
 use sea_orm::{entity::prelude::*, Set};
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+use crate::models::games::SystemRequirements;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, ToSchema)]
+#[schema(as = GameModel)]
 #[sea_orm(table_name = "game")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -14,16 +16,34 @@ pub struct Model {
     #[sea_orm(unique)]
     pub name: String,
     pub description: Option<String>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    #[serde(default, with = "crate::helpers::dates::option")]
+    #[schema(value_type = Option<String>, format = Date)]
+    pub release_date: Option<TimeDate>,
+    /// Minimum system requirements, as `SystemRequirements`
+    #[schema(value_type = Option<SystemRequirements>)]
+    pub system_requirements: Option<Json>,
     #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String, format = DateTime)]
     pub created_at: TimeDateTimeWithTimeZone,
     #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String, format = DateTime)]
     pub updated_at: TimeDateTimeWithTimeZone,
+    /// Deleted games can be restored until they are purged by the cleanup job
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub deleted_at: Option<TimeDateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_one = "super::game_banner::Entity")]
     GameBanner,
+    #[sea_orm(has_many = "super::game_media::Entity")]
+    GameMedia,
+    #[sea_orm(has_many = "super::game_tag::Entity")]
+    GameTag,
 }
 
 impl Related<super::game_banner::Entity> for Entity {
@@ -32,6 +52,18 @@ impl Related<super::game_banner::Entity> for Entity {
     }
 }
 
+impl Related<super::game_media::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GameMedia.def()
+    }
+}
+
+impl Related<super::game_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GameTag.def()
+    }
+}
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
     /// Create a new ActiveModel with default values. Also used by `Default::default()`.
@@ -70,14 +102,7 @@ impl ActiveModelBehavior for ActiveModel {
 
             if banner.is_none() {
                 // If the game doesn't have a banner, create one
-                let color = helpers::colors::Color::from_text(game.name.clone());
-
-                let banner = game_banner::ActiveModel {
-                    game_id: Set(game.id),
-                    banner_type: Set(game_banner::BannerType::Color),
-                    value: Set(color.to_string()),
-                    ..Default::default()
-                };
+                let banner = game_banner::ActiveModel::from_game(&game);
 
                 banner.insert(db).await?;
 
@@ -88,3 +113,49 @@ impl ActiveModelBehavior for ActiveModel {
         Ok(game)
     }
 }
+
+impl Model {
+    pub fn get_system_requirements(&self) -> Option<SystemRequirements> {
+        self.system_requirements
+            .clone()
+            .and_then(|requirements| serde_json::from_value(requirements).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::date, OffsetDateTime};
+
+    use super::Model;
+
+    /// The clients read the serialized entity as the shared `Game`
+    #[test]
+    fn game_matches_the_shared_type() {
+        let now = OffsetDateTime::now_utc();
+        let game = Model {
+            id: 1,
+            name: "Celeste".to_owned(),
+            description: None,
+            developer: Some("Maddy Makes Games".to_owned()),
+            publisher: None,
+            release_date: Some(date!(2018 - 01 - 25)),
+            system_requirements: Some(serde_json::json!({ "memory": 2048 })),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        };
+
+        let shared: game_sync_common::games::Game =
+            serde_json::from_value(serde_json::to_value(&game).unwrap()).unwrap();
+
+        assert_eq!(shared.id, game.id);
+        assert_eq!(shared.developer, game.developer);
+        assert_eq!(shared.release_date, game.release_date);
+        assert_eq!(
+            shared
+                .system_requirements
+                .and_then(|requirements| requirements.memory),
+            Some(2048)
+        );
+    }
+}
@@ -2,16 +2,9 @@ use sea_orm::{entity::prelude::*, Set};
 use serde::{Deserialize, Serialize};
 
 use crate::helpers::colors;
+use crate::models::games::StoredVariant;
 
-#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
-#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "banner_type")]
-#[serde(rename_all = "snake_case")]
-pub enum BannerType {
-    #[sea_orm(string_value = "Color")]
-    Color,
-    #[sea_orm(string_value = "Image")]
-    Image,
-}
+pub use game_sync_common::games::BannerType;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "banner")]
@@ -22,6 +15,10 @@ pub struct Model {
     pub banner_type: BannerType,
     pub value: String,
     pub game_id: i32,
+    /// Dominant color of the image, or the color itself
+    pub accent_color: Option<String>,
+    /// Keys of the resized images, as a list of `StoredVariant`
+    pub variants: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -50,14 +47,32 @@ impl ActiveModelBehavior for ActiveModel {
     }
 }
 
+impl Model {
+    /// Resized versions of the banner image, empty for color banners
+    pub fn get_variants(&self) -> Vec<StoredVariant> {
+        self.variants
+            .clone()
+            .and_then(|variants| serde_json::from_value(variants).ok())
+            .unwrap_or_default()
+    }
+}
+
 impl ActiveModel {
     /// Default from game
     pub fn from_game(game: &super::game::Model) -> Self {
-        Self {
+        let mut banner = Self {
             game_id: Set(game.id.clone()),
-            banner_type: Set(BannerType::Color),
-            value: Set(colors::Color::from_text(game.name.clone()).to_string()),
             ..Self::new()
-        }
+        };
+        banner.set_color(colors::Color::from_text(game.name.clone()).to_string());
+        banner
+    }
+
+    /// Turn the banner into a plain color one
+    pub fn set_color(&mut self, color: String) {
+        self.banner_type = Set(BannerType::Color);
+        self.value = Set(color.clone());
+        self.accent_color = Set(Some(color));
+        self.variants = Set(None);
     }
 }
@@ -0,0 +1,64 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::images::{self, ImageSize};
+use crate::models::games::StoredVariant;
+
+pub use game_sync_common::games::MediaKind;
+
+/// Sizes of the generated variants, `None` for media which aren't uploaded images
+pub fn image_sizes(kind: MediaKind) -> Option<&'static [ImageSize]> {
+    match kind {
+        MediaKind::Icon => Some(&images::ICON_SIZES),
+        MediaKind::Logo => Some(&images::LOGO_SIZES),
+        MediaKind::Hero => Some(&images::BANNER_SIZES),
+        MediaKind::Screenshot => Some(&images::SCREENSHOT_SIZES),
+        MediaKind::Trailer => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "game_media")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub game_id: i32,
+    pub kind: MediaKind,
+    /// Order of the media in the gallery, always 0 for single slot kinds
+    pub position: i32,
+    /// Storage key of the reference image, or the URL of a trailer
+    pub value: String,
+    pub accent_color: Option<String>,
+    /// Keys of the resized images, as a list of `StoredVariant`
+    pub variants: Option<Json>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: TimeDateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::game::Entity",
+        from = "Column::GameId",
+        to = "super::game::Column::Id"
+    )]
+    Game,
+}
+
+impl Related<super::game::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Game.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn get_variants(&self) -> Vec<StoredVariant> {
+        self.variants
+            .clone()
+            .and_then(|variants| serde_json::from_value(variants).ok())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,57 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::UserRole;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, ToSchema)]
+#[schema(as = InvitationModel)]
+#[sea_orm(table_name = "invitation")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    /// SHA-256 of the invitation code, the code is only shown once at creation
+    #[sea_orm(unique)]
+    #[serde(skip)]
+    pub code_hash: String,
+    /// Role given to the users registering with this invitation
+    pub role: UserRole,
+    /// Games the registered users will be granted access to
+    #[schema(value_type = Vec<i32>)]
+    pub game_ids: Json,
+    /// Unlimited when not set
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub expires_at: Option<TimeDateTimeWithTimeZone>,
+    pub created_by: Option<i32>,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: TimeDateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::CreatedBy",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn get_game_ids(&self) -> Vec<i32> {
+        serde_json::from_value(self.game_ids.clone()).unwrap_or_default()
+    }
+}
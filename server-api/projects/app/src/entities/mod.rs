@@ -2,6 +2,15 @@
 
 pub mod prelude;
 
+pub mod audit_log;
+pub mod file;
 pub mod game;
 pub mod game_banner;
+pub mod game_grant;
+pub mod game_media;
+pub mod game_tag;
+pub mod invitation;
+pub mod passkey;
+pub mod recovery_code;
 pub mod user;
+pub mod user_token;
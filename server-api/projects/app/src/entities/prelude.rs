@@ -1,5 +1,14 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.2
 
+pub use super::audit_log::Entity as AuditLog;
+pub use super::file::Entity as File;
 pub use super::game::Entity as Game;
 pub use super::game_banner::Entity as GameBanner;
+pub use super::game_grant::Entity as GameGrant;
+pub use super::game_media::Entity as GameMedia;
+pub use super::game_tag::Entity as GameTag;
+pub use super::invitation::Entity as Invitation;
+pub use super::passkey::Entity as Passkey;
+pub use super::recovery_code::Entity as RecoveryCode;
 pub use super::user::Entity as User;
+pub use super::user_token::Entity as UserToken;
@@ -1,10 +1,14 @@
 use sea_orm::{entity::prelude::*, Set};
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
+use utoipa::ToSchema;
 
 use crate::helpers::hashing;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+pub use game_sync_common::users::UserRole;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, ToSchema)]
+#[schema(as = UserModel)]
 #[sea_orm(table_name = "user")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -15,14 +19,52 @@ pub struct Model {
     #[sea_orm(column_name = "_password")]
     #[serde(skip)]
     pub password: String,
+    pub role: UserRole,
+    #[serde(skip)]
+    pub totp_secret: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub totp_enabled_at: Option<TimeDateTimeWithTimeZone>,
+    /// Time step of the last accepted TOTP code, a code can't be used twice
+    #[serde(skip)]
+    pub totp_last_step: Option<i64>,
+    /// Subject of the user at the identity provider, when logged in with single sign-on
+    #[sea_orm(unique)]
+    #[serde(skip)]
+    pub oidc_subject: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub email_verified_at: Option<TimeDateTimeWithTimeZone>,
+    /// Disabled users are kept for history but can't log in anymore
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub disabled_at: Option<TimeDateTimeWithTimeZone>,
+    /// Sessions opened before this time are revoked, e.g. after a password change
+    #[serde(skip)]
+    pub sessions_valid_after: Option<TimeDateTimeWithTimeZone>,
     #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String, format = DateTime)]
     pub created_at: TimeDateTimeWithTimeZone,
     #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String, format = DateTime)]
     pub updated_at: TimeDateTimeWithTimeZone,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::recovery_code::Entity")]
+    RecoveryCode,
+    #[sea_orm(has_many = "super::passkey::Entity")]
+    Passkey,
+    #[sea_orm(has_many = "super::user_token::Entity")]
+    UserToken,
+}
+
+impl Related<super::recovery_code::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecoveryCode.def()
+    }
+}
 
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
@@ -44,7 +86,8 @@ impl ActiveModelBehavior for ActiveModel {
             this.email = Set(this.email.as_ref().to_lowercase());
         }
 
-        {
+        // Only hash the password when it has been changed, otherwise we would hash the hash
+        if this.password.is_set() {
             let hashed_password = hashing::hash(this.password.as_ref())
                 .map_err(|_| DbErr::Custom("[before_save] Failed to hash password".to_string()))?;
 
@@ -59,3 +102,61 @@ impl ActiveModelBehavior for ActiveModel {
         Ok(this)
     }
 }
+
+impl Related<super::passkey::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Passkey.def()
+    }
+}
+
+impl Related<super::user_token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserToken.def()
+    }
+}
+
+impl Model {
+    pub fn has_two_factor(&self) -> bool {
+        self.totp_enabled_at.is_some()
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::{Model, UserRole};
+
+    /// The clients read the serialized entity as the shared `User`
+    #[test]
+    fn user_matches_the_shared_type() {
+        let now = OffsetDateTime::now_utc();
+        let user = Model {
+            id: 1,
+            email: "admin@example.com".to_owned(),
+            password: "hash".to_owned(),
+            role: UserRole::Admin,
+            totp_secret: Some("secret".to_owned()),
+            totp_enabled_at: Some(now),
+            totp_last_step: Some(1),
+            sessions_valid_after: None,
+            oidc_subject: None,
+            email_verified_at: Some(now),
+            disabled_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let shared: game_sync_common::users::User =
+            serde_json::from_value(serde_json::to_value(&user).unwrap()).unwrap();
+
+        assert_eq!(shared.id, user.id);
+        assert_eq!(shared.email, user.email);
+        assert_eq!(shared.role, user.role);
+        assert!(shared.totp_enabled_at.is_some());
+    }
+}
@@ -0,0 +1,60 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_token_kind")]
+#[serde(rename_all = "snake_case")]
+pub enum UserTokenKind {
+    #[sea_orm(string_value = "EmailVerification")]
+    EmailVerification,
+    #[sea_orm(string_value = "PasswordReset")]
+    PasswordReset,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_token")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub user_id: i32,
+    pub kind: UserTokenKind,
+    /// SHA-256 of the token sent by mail, the token itself is never stored
+    #[sea_orm(unique)]
+    #[serde(skip)]
+    pub token_hash: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: TimeDateTimeWithTimeZone,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub used_at: Option<TimeDateTimeWithTimeZone>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: TimeDateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl UserTokenKind {
+    /// How long a token stays valid after being sent
+    pub fn lifetime(&self) -> time::Duration {
+        match self {
+            UserTokenKind::EmailVerification => time::Duration::days(1),
+            UserTokenKind::PasswordReset => time::Duration::hours(1),
+        }
+    }
+}
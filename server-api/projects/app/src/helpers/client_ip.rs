@@ -0,0 +1,35 @@
+use std::net::IpAddr;
+
+use actix_web::HttpRequest;
+
+/// IP address of the client. The `X-Forwarded-For` header is only read when the request comes
+/// from one of the trusted proxies, the clients could send any address otherwise. Its hops are
+/// read from the closest one, skipping the trusted proxies.
+pub fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let hops: Vec<&str> = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            break;
+        };
+
+        client = ip;
+        if !trusted_proxies.contains(&ip) {
+            break;
+        }
+    }
+
+    Some(client)
+}
@@ -0,0 +1,3 @@
+//! Serde helpers for calendar dates, formatted as `YYYY-MM-DD`
+
+pub use game_sync_common::dates::{deserialize, option, serialize};
@@ -2,6 +2,7 @@ use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
+use sha2::{Digest, Sha256};
 
 use crate::core::errors::{AppError, AppResult};
 
@@ -29,3 +30,8 @@ pub fn verify_password(hash: &str, password: &String) -> AppResult<bool> {
         },
     }
 }
+
+/// Tokens are random and long enough, a fast hash is sufficient to avoid storing them in clear
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,130 @@
+use std::io::Cursor;
+
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageOutputFormat};
+
+use super::colors::Color;
+use crate::core::errors::{AppError, AppResult};
+
+pub use game_sync_common::games::ImageFormat;
+
+/// Size of a generated variant, as `(name, maximum width)`
+pub type ImageSize = (&'static str, u32);
+
+/// Sizes generated for banners and hero images
+pub const BANNER_SIZES: [ImageSize; 3] = [("full", 1920), ("medium", 960), ("thumbnail", 320)];
+pub const ICON_SIZES: [ImageSize; 2] = [("full", 512), ("thumbnail", 128)];
+pub const LOGO_SIZES: [ImageSize; 2] = [("full", 1280), ("thumbnail", 320)];
+pub const SCREENSHOT_SIZES: [ImageSize; 2] = [("full", 1920), ("thumbnail", 480)];
+
+/// Formats generated for every image size
+pub const IMAGE_FORMATS: [ImageFormat; 2] = [ImageFormat::Webp, ImageFormat::Avif];
+
+fn output_format(format: ImageFormat) -> ImageOutputFormat {
+    match format {
+        ImageFormat::Webp => ImageOutputFormat::WebP,
+        ImageFormat::Avif => ImageOutputFormat::Avif,
+    }
+}
+
+/// An encoded variant of an image, ready to be uploaded
+pub struct ImageVariant {
+    pub size: &'static str,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: Vec<u8>,
+}
+
+pub struct ProcessedImage {
+    /// Dominant color of the image
+    pub accent_color: Color,
+    pub variants: Vec<ImageVariant>,
+}
+
+/// Decode an uploaded image and generate a variant for each size and format, the largest first.
+/// Images are re-encoded from their pixels, so their metadata (EXIF, location, ...) is never kept.
+/// This is CPU heavy and blocking, it must be run on a blocking thread.
+pub fn process_image(bytes: &[u8], sizes: &[ImageSize]) -> AppResult<ProcessedImage> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|_| AppError::BadRequest("The file is not a valid image".to_string()))?;
+
+    // The encoders only take 8-bit RGB(A) pixels, e.g. a 16-bit grayscale PNG would be refused
+    let image = match image {
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => image,
+        image => DynamicImage::ImageRgba8(image.to_rgba8()),
+    };
+
+    let accent_color = dominant_color(&image);
+
+    let mut variants = Vec::with_capacity(sizes.len() * IMAGE_FORMATS.len());
+    for &(size, max_width) in sizes {
+        // Never upscale a small image
+        let resized = if image.width() > max_width {
+            image.resize(max_width, u32::MAX, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+        let (width, height) = resized.dimensions();
+
+        for format in IMAGE_FORMATS {
+            let mut buffer = Cursor::new(Vec::new());
+            resized
+                .write_to(&mut buffer, output_format(format))
+                .map_err(|e| AppError::Other(e.into()))?;
+
+            variants.push(ImageVariant {
+                size,
+                format,
+                width,
+                height,
+                bytes: buffer.into_inner(),
+            });
+        }
+    }
+
+    Ok(ProcessedImage {
+        accent_color,
+        variants,
+    })
+}
+
+/// Most present color of the image
+fn dominant_color(image: &DynamicImage) -> Color {
+    // The palette doesn't need the full resolution, a thumbnail is way faster to analyse
+    let thumbnail = image.thumbnail(256, 256).to_rgb8();
+    let colors = dominant_color::get_colors(thumbnail.as_raw(), false);
+
+    // Colors are returned as a flat list of RGB values, most present first
+    match colors.as_slice() {
+        [r, g, b, ..] => Color {
+            r: *r,
+            g: *g,
+            b: *b,
+        },
+        _ => Color { r: 0, g: 0, b: 0 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageBuffer, ImageOutputFormat, Luma};
+
+    use super::{process_image, IMAGE_FORMATS};
+
+    #[test]
+    fn deep_grayscale_images_are_encoded() {
+        let image = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(16, 8, Luma([40_000u16])));
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageOutputFormat::Png).unwrap();
+
+        let processed = process_image(png.get_ref(), &[("full", 8)]).unwrap();
+
+        assert_eq!(processed.variants.len(), IMAGE_FORMATS.len());
+        for variant in processed.variants {
+            assert_eq!((variant.width, variant.height), (8, 4));
+            assert!(!variant.bytes.is_empty());
+        }
+    }
+}
@@ -1,3 +1,9 @@
+pub mod client_ip;
 pub mod colors;
+pub mod dates;
 pub mod hashing;
+pub mod images;
+pub mod sessions;
+pub mod sigv4;
+pub mod two_factor;
 pub mod validation;
@@ -0,0 +1,86 @@
+use actix_multi_session::Session;
+use time::OffsetDateTime;
+
+use crate::{core::errors::AppResult, entities::user::Model as UserModel};
+
+/// Log the user in on a new session ID. The time of the login is kept to revoke the session
+/// later, see [`is_revoked`].
+pub fn log_in(session: &Session, user: &UserModel) -> AppResult<()> {
+    session.renew();
+    session.insert("user_id", user.id)?;
+    session.insert("logged_in_at", micros(OffsetDateTime::now_utc()))?;
+
+    Ok(())
+}
+
+/// Whether the session was opened before the sessions of the user were revoked
+pub fn is_revoked(session: &Session, user: &UserModel) -> AppResult<bool> {
+    let Some(valid_after) = user.sessions_valid_after else {
+        return Ok(false);
+    };
+    let logged_in_at = session.get::<i64>("logged_in_at")?;
+
+    Ok(logged_in_at.map_or(true, |logged_in_at| logged_in_at < micros(valid_after)))
+}
+
+/// Microseconds since the epoch, the precision of the database timestamps
+fn micros(time: OffsetDateTime) -> i64 {
+    (time.unix_timestamp_nanos() / 1_000) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_multi_session::SessionExt;
+    use actix_web::test::TestRequest;
+    use time::{Duration, OffsetDateTime};
+
+    use super::{is_revoked, log_in};
+    use crate::entities::user::{Model as UserModel, UserRole};
+
+    fn user(sessions_valid_after: Option<OffsetDateTime>) -> UserModel {
+        let now = OffsetDateTime::now_utc();
+
+        UserModel {
+            id: 1,
+            email: "player@example.com".to_owned(),
+            password: "hash".to_owned(),
+            role: UserRole::User,
+            totp_secret: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            sessions_valid_after,
+            oidc_subject: None,
+            email_verified_at: Some(now),
+            disabled_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn sessions_opened_before_the_revocation_are_revoked() {
+        let session = TestRequest::default().to_http_request().get_session();
+        log_in(&session, &user(None)).unwrap();
+
+        assert!(!is_revoked(&session, &user(None)).unwrap());
+        assert!(!is_revoked(
+            &session,
+            &user(Some(OffsetDateTime::now_utc() - Duration::minutes(1)))
+        )
+        .unwrap());
+        assert!(is_revoked(
+            &session,
+            &user(Some(OffsetDateTime::now_utc() + Duration::seconds(1)))
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn sessions_without_login_time_are_revoked() {
+        let session = TestRequest::default().to_http_request().get_session();
+        session.insert("user_id", 1).unwrap();
+
+        assert!(!is_revoked(&session, &user(None)).unwrap());
+        assert!(is_revoked(&session, &user(Some(OffsetDateTime::now_utc()))).unwrap());
+    }
+}
@@ -0,0 +1,256 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// SHA-256 of an empty payload, the signed requests have no body
+pub const EMPTY_PAYLOAD_HASH: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Headers to add to a request signed with AWS Signature Version 4
+pub struct SignedHeaders {
+    pub authorization: String,
+    pub amz_date: String,
+}
+
+/// Request to sign. `path` and `query` must already be encoded with `uri_encode`, they are sent
+/// as is.
+pub struct UnsignedRequest<'a> {
+    pub method: &'a str,
+    /// `host` or `host:port`
+    pub host: &'a str,
+    pub path: &'a str,
+    pub query: &'a str,
+    /// Headers to sign besides `host` and `x-amz-date`, e.g. `x-amz-content-sha256`
+    pub headers: &'a [(&'a str, &'a str)],
+    /// Hex encoded SHA-256 of the body
+    pub payload_hash: &'a str,
+}
+
+pub struct SigningKey<'a> {
+    pub access_key: &'a str,
+    pub secret_key: &'a str,
+    /// Token of temporary credentials, it must be sent as `x-amz-security-token`
+    pub session_token: Option<&'a str>,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+/// Percent-encode everything but the unreserved characters, as required by the signature.
+/// The slashes of the object keys are kept.
+pub fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Query string of the parameters, sorted as required by the signature
+pub fn canonical_query(params: &[(&str, String)]) -> String {
+    let mut pairs: Vec<(String, String)> = params
+        .iter()
+        .map(|(key, value)| (uri_encode(key, true), uri_encode(value, true)))
+        .collect();
+    pairs.sort();
+
+    pairs
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+pub fn sign(request: &UnsignedRequest, key: &SigningKey, now: OffsetDateTime) -> SignedHeaders {
+    let date = format!(
+        "{:04}{:02}{:02}",
+        now.year(),
+        u8::from(now.month()),
+        now.day()
+    );
+    let amz_date = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        now.hour(),
+        now.minute(),
+        now.second()
+    );
+
+    // Signed in the order of their lowercase names, the session token included
+    let session_token = key
+        .session_token
+        .map(|token| ("x-amz-security-token", token));
+    let mut headers: Vec<(String, &str)> =
+        [("host", request.host), ("x-amz-date", amz_date.as_str())]
+            .into_iter()
+            .chain(request.headers.iter().copied())
+            .chain(session_token)
+            .map(|(name, value)| (name.to_lowercase(), value.trim()))
+            .collect();
+    headers.sort();
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        request.path,
+        request.query,
+        canonical_headers,
+        signed_headers,
+        request.payload_hash
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, key.region, key.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = [key.region, key.service, "aws4_request"].iter().fold(
+        hmac(
+            format!("AWS4{}", key.secret_key).as_bytes(),
+            date.as_bytes(),
+        ),
+        |signing_key, part| hmac(&signing_key, part.as_bytes()),
+    );
+    let signature = hex::encode(hmac(&signing_key, string_to_sign.as_bytes()));
+
+    SignedHeaders {
+        authorization: format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            key.access_key, scope, signed_headers, signature
+        ),
+        amz_date,
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{
+        canonical_query, sign, uri_encode, SigningKey, UnsignedRequest, EMPTY_PAYLOAD_HASH,
+    };
+
+    // Requests and signatures of the AWS Signature Version 4 test suite
+    const ACCESS_KEY: &str = "AKIDEXAMPLE";
+    const SECRET_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+    const SESSION_TOKEN: &str = "AQoDYXdzEPT//////////wEXAMPLEtc764bNrC9SAPBSM22wDOk4x4HIZ8j4FZTwdQWLWsKWHGBuFqwAeMicRXmxfpSPfIeoIYRqTflfKD8YUuwthAx7mSEI/qkPpKPi/kMcGdQrmGdeehM4IC1NtBmUpp2wUE8phUZampKsburEDy0KPkyQDYwT7WZ0wq5VSXDvp75YU9HFvlRd8Tx6q6fE8YQcHNVXAkiY9q6d+xo0rKwT38xVqr7ZD0u0iPPkUL64lIZbqBAz+scqKmlzm8FDrypNC9Yjc8fPOLn9FX9KSYvKTr4rvx3iSIlTJabIQwj2ICCR/oLxBA==";
+
+    fn authorization(method: &str, path: &str, query: &str, session_token: Option<&str>) -> String {
+        let signed = sign(
+            &UnsignedRequest {
+                method,
+                host: "example.amazonaws.com",
+                path,
+                query,
+                headers: &[],
+                payload_hash: EMPTY_PAYLOAD_HASH,
+            },
+            &SigningKey {
+                access_key: ACCESS_KEY,
+                secret_key: SECRET_KEY,
+                session_token,
+                region: "us-east-1",
+                service: "service",
+            },
+            datetime!(2015-08-30 12:36:00 UTC),
+        );
+
+        assert_eq!(signed.amz_date, "20150830T123600Z");
+        signed.authorization
+    }
+
+    fn expected(signed_headers: &str, signature: &str) -> String {
+        format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders={}, Signature={}",
+            signed_headers, signature
+        )
+    }
+
+    #[test]
+    fn get_vanilla() {
+        assert_eq!(
+            authorization("GET", "/", "", None),
+            expected(
+                "host;x-amz-date",
+                "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+            )
+        );
+    }
+
+    #[test]
+    fn get_vanilla_query_order_key_case() {
+        let query = canonical_query(&[
+            ("Param2", "value2".to_owned()),
+            ("Param1", "value1".to_owned()),
+        ]);
+
+        assert_eq!(
+            authorization("GET", "/", &query, None),
+            expected(
+                "host;x-amz-date",
+                "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+            )
+        );
+    }
+
+    #[test]
+    fn get_utf8() {
+        assert_eq!(
+            authorization("GET", &uri_encode("/ሴ", false), "", None),
+            expected(
+                "host;x-amz-date",
+                "8318018e0b0f223aa2bbf98705b62bb787dc9c0e678f255a891fd03141be5d85"
+            )
+        );
+    }
+
+    #[test]
+    fn post_vanilla() {
+        assert_eq!(
+            authorization("POST", "/", "", None),
+            expected(
+                "host;x-amz-date",
+                "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+            )
+        );
+    }
+
+    #[test]
+    fn post_sts_header_before() {
+        assert_eq!(
+            authorization("POST", "/", "", Some(SESSION_TOKEN)),
+            expected(
+                "host;x-amz-date;x-amz-security-token",
+                "85d96828115b5dc0cfc3bd16ad9e210dd772bbebba041836c64533a82be05ead"
+            )
+        );
+    }
+}
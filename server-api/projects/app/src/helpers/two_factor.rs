@@ -0,0 +1,191 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hkdf::Hkdf;
+use rand::Rng;
+use sha2::Sha256;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::core::{
+    config::SecretKey,
+    errors::{AppError, AppResult},
+};
+
+/// Number of recovery codes generated for each enrollment
+const RECOVERY_CODES_COUNT: usize = 10;
+
+/// Context of the key derived from the server secret key to encrypt the TOTP secrets
+const SECRET_ENCRYPTION_INFO: &[u8] = b"game-sync totp secret";
+
+/// Size of the AES-GCM nonce, stored before the ciphertext
+const NONCE_LENGTH: usize = 12;
+
+/// Generate a new random secret, encoded in base32
+pub fn generate_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+/// Build the TOTP generator of a user from its base32 encoded secret
+pub fn build_totp(secret: &str, issuer: &str, account_name: &str) -> AppResult<TOTP> {
+    let secret = Secret::Encoded(secret.to_owned())
+        .to_bytes()
+        .map_err(|e| AppError::TotpError(e.to_string()))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(issuer.to_owned()),
+        account_name.to_owned(),
+    )
+    .map_err(|e| AppError::TotpError(e.to_string()))
+}
+
+/// Check a code against the current time step and its neighbours, to allow some clock drift.
+/// Returns the time step of the code, which must be recorded to prevent it from being reused.
+#[tracing::instrument("Verifying TOTP code", skip(totp, code))]
+pub fn verify_code(totp: &TOTP, code: &str) -> AppResult<Option<i64>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| AppError::TotpError(e.to_string()))?
+        .as_secs();
+
+    Ok(find_step(totp, code, now).map(|step| step as i64))
+}
+
+/// Time step of the code, among the step of `now` and its neighbours
+fn find_step(totp: &TOTP, code: &str, now: u64) -> Option<u64> {
+    let current_step = now / totp.step;
+    let skew = u64::from(totp.skew);
+    let code = code.trim();
+
+    (current_step.saturating_sub(skew)..=current_step + skew)
+        .find(|step| constant_time_eq(totp.generate(step * totp.step).as_bytes(), code.as_bytes()))
+}
+
+/// Encrypt the secret of a user before storing it, with a key derived from the server secret key.
+/// The random nonce is stored before the ciphertext, both encoded in base64.
+pub fn encrypt_secret(secret_key: &SecretKey, secret: &str) -> AppResult<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(secret_key)
+        .encrypt(&nonce, secret.as_bytes())
+        .map_err(|e| AppError::TotpError(e.to_string()))?;
+
+    Ok(STANDARD.encode([nonce.as_slice(), &ciphertext].concat()))
+}
+
+pub fn decrypt_secret(secret_key: &SecretKey, encrypted: &str) -> AppResult<String> {
+    let encrypted = STANDARD
+        .decode(encrypted)
+        .map_err(|e| AppError::TotpError(e.to_string()))?;
+
+    if encrypted.len() < NONCE_LENGTH {
+        return Err(AppError::TotpError("Invalid encrypted secret".to_string()));
+    }
+
+    let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+    let secret = cipher(secret_key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| AppError::TotpError(e.to_string()))?;
+
+    String::from_utf8(secret).map_err(|e| AppError::TotpError(e.to_string()))
+}
+
+fn cipher(secret_key: &SecretKey) -> Aes256Gcm {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, secret_key.0.as_bytes())
+        .expand(SECRET_ENCRYPTION_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+
+    Aes256Gcm::new(&key.into())
+}
+
+/// Compare the codes without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Generate a set of single-use recovery codes, formatted as `xxxxx-xxxxx`
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+
+    (0..RECOVERY_CODES_COUNT)
+        .map(|_| {
+            let code = hex::encode(rng.gen::<[u8; 5]>());
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_totp, decrypt_secret, encrypt_secret, find_step, generate_recovery_codes,
+        generate_secret,
+    };
+    use crate::core::config::SecretKey;
+
+    /// 2023-12-14 10:15:30 UTC, the start of a time step
+    const NOW: u64 = 1_702_548_930;
+
+    #[test]
+    fn codes_are_accepted_within_one_step() {
+        let totp = build_totp(&generate_secret(), "Game Sync", "player@example.com").unwrap();
+        let step = NOW / 30;
+
+        for accepted in [step - 1, step, step + 1] {
+            let code = totp.generate(accepted * 30);
+            assert_eq!(find_step(&totp, &code, NOW + 29), Some(accepted));
+        }
+
+        for refused in [step - 2, step + 2] {
+            let code = totp.generate(refused * 30);
+            // The codes of distant steps may collide with the accepted ones
+            if [step - 1, step, step + 1]
+                .iter()
+                .all(|accepted| totp.generate(accepted * 30) != code)
+            {
+                assert_eq!(find_step(&totp, &code, NOW), None);
+            }
+        }
+
+        assert_eq!(find_step(&totp, "not a code", NOW), None);
+    }
+
+    #[test]
+    fn secrets_round_trip() {
+        let secret_key = SecretKey("a".repeat(64));
+        let secret = generate_secret();
+
+        let encrypted = encrypt_secret(&secret_key, &secret).unwrap();
+        assert!(!encrypted.contains(&secret));
+        assert_eq!(decrypt_secret(&secret_key, &encrypted).unwrap(), secret);
+
+        // Each encryption has its own nonce
+        assert_ne!(encrypt_secret(&secret_key, &secret).unwrap(), encrypted);
+    }
+
+    #[test]
+    fn secrets_are_bound_to_the_secret_key() {
+        let encrypted = encrypt_secret(&SecretKey("a".repeat(64)), "secret").unwrap();
+
+        assert!(decrypt_secret(&SecretKey("b".repeat(64)), &encrypted).is_err());
+        assert!(decrypt_secret(&SecretKey("a".repeat(64)), "dGFtcGVyZWQ=").is_err());
+    }
+
+    #[test]
+    fn recovery_codes_are_unique() {
+        let mut codes = generate_recovery_codes();
+        assert!(codes.iter().all(|code| code.len() == 11));
+
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), 10);
+    }
+}
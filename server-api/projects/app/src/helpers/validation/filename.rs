@@ -0,0 +1,10 @@
+use validator::ValidationError;
+
+/// The file name is appended to a key prefix, it can't point to another folder
+#[must_use]
+pub fn validate_filename(val: &String) -> Result<(), ValidationError> {
+    if val.contains('/') || val == "." || val == ".." {
+        return Err(ValidationError::new("invalid_filename"));
+    }
+    Ok(())
+}
@@ -1 +1,2 @@
+pub mod filename;
 pub mod required_str;
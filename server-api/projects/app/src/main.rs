@@ -1,6 +1,8 @@
 mod app;
 
 mod bootstrap;
+mod cli;
+mod commands;
 mod controllers;
 mod core;
 mod data;
@@ -10,17 +12,46 @@ mod middlewares;
 mod models;
 mod repositories;
 mod routes;
+mod tasks;
+
+use clap::Parser;
+
+use crate::{
+    cli::{Cli, Command},
+    core::{config::TelemetryConfig, telemetry},
+};
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     dotenvy::dotenv().ok();
 
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::TRACE)
-        .init();
+    let cli = Cli::parse();
+
+    // Errors of the configuration are reported once the logs are set up, when loading all of it
+    let telemetry_config = TelemetryConfig::from_env().unwrap_or_default();
+    let telemetry_guard = telemetry::init(&telemetry_config)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
-    if let Err(e) = app::run().await {
+    let result = match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => app::run().await,
+        Command::Migrate => commands::migrate().await,
+        Command::CreateAdmin { email, password } => commands::create_admin(&email, &password).await,
+        Command::ResetPassword { email, password } => {
+            commands::reset_password(&email, &password).await
+        }
+        Command::ListUsers => commands::list_users().await,
+        Command::GcStorage { dry_run } => commands::gc_storage(dry_run).await,
+        Command::Export { path } => commands::export(&path).await,
+        Command::Import { path } => commands::import(&path).await,
+        Command::CheckConfig => commands::check_config().await,
+    };
+
+    if let Err(e) = result {
         tracing::error!("{}", e);
+
+        // Flush the traces first, exiting skips the destructors
+        drop(telemetry_guard);
+        std::process::exit(1);
     }
 
     Ok(())
@@ -9,8 +9,8 @@ use std::{
     rc::Rc,
 };
 
-use crate::repositories;
 use crate::{core::errors::AppError, data::AppData};
+use crate::{helpers::sessions, repositories};
 
 pub struct Auth;
 
@@ -65,6 +65,18 @@ where
                     let user = repositories::user::get_user_from_id(&app_data.db, user_id).await?;
 
                     if let Some(user) = user {
+                        // The session outlives the account being disabled
+                        if user.is_disabled() {
+                            session.purge();
+                            return Err(AppError::AccountDisabled.into());
+                        }
+
+                        // e.g. after a password change
+                        if sessions::is_revoked(&session, &user)? {
+                            session.purge();
+                            return Err(AppError::Unauthorized.into());
+                        }
+
                         // add the user to the request extensions
                         req.extensions_mut().insert(user);
                         return Ok(svc.call(req).await?);
@@ -9,8 +9,8 @@ use std::{
     rc::Rc,
 };
 
-use crate::repositories;
 use crate::{core::errors::AppError, data::AppData};
+use crate::{helpers::sessions, repositories};
 
 pub struct Guest;
 
@@ -62,8 +62,11 @@ where
                     let app_data = req.app_data::<web::Data<AppData>>().unwrap();
                     let user = repositories::user::get_user_from_id(&app_data.db, user_id).await?;
 
-                    if user.is_some() {
-                        return Err(AppError::Unauthorized.into());
+                    // A revoked session can log in again
+                    if let Some(user) = user {
+                        if !sessions::is_revoked(&session, &user)? {
+                            return Err(AppError::Unauthorized.into());
+                        }
                     }
 
                     Ok(svc.call(req).await?)
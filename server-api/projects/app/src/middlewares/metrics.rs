@@ -0,0 +1,84 @@
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+    time::Instant,
+};
+
+use crate::core::metrics::Metrics;
+
+/// Count the requests and measure their duration, by route
+pub struct HttpMetrics {
+    metrics: Metrics,
+}
+
+impl HttpMetrics {
+    pub fn new(metrics: Metrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S: 'static, B> Transform<S, ServiceRequest> for HttpMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = HttpMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpMetricsMiddleware {
+            service: Rc::new(service),
+            metrics: self.metrics.clone(),
+        }))
+    }
+}
+
+pub struct HttpMetricsMiddleware<S> {
+    service: Rc<S>,
+    metrics: Metrics,
+}
+
+impl<S, B> Service<ServiceRequest> for HttpMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+        let metrics = self.metrics.clone();
+
+        let started_at = Instant::now();
+        let method = req.method().to_string();
+
+        Box::pin(async move {
+            let result = svc.call(req).await;
+
+            let (route, status) = match &result {
+                Ok(res) => (
+                    res.request().match_pattern(),
+                    res.response().status().as_u16(),
+                ),
+                Err(err) => (None, err.as_response_error().status_code().as_u16()),
+            };
+            // Unknown paths are grouped, they would create a series for each path otherwise
+            let route = route.unwrap_or_else(|| "unmatched".to_string());
+
+            metrics.observe_request(&method, &route, status, started_at);
+
+            result
+        })
+    }
+}
@@ -1,2 +1,7 @@
 pub mod auth;
 pub mod guest;
+pub mod metrics;
+pub mod rate_limit;
+pub mod request_id;
+pub mod role;
+pub mod two_factor;
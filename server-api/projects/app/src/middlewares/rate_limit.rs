@@ -0,0 +1,209 @@
+use actix_multi_session::SessionExt;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::AUTHORIZATION,
+    web,
+};
+use std::{
+    future::{ready, Future, Ready},
+    net::IpAddr,
+    pin::Pin,
+    rc::Rc,
+};
+
+use crate::core::rate_limit::{RateLimitKey, RateLimitPolicy};
+use crate::{
+    core::errors::AppError,
+    data::AppData,
+    helpers::{client_ip::client_ip, hashing},
+};
+
+pub struct RateLimit {
+    policy: Rc<RateLimitPolicy>,
+}
+
+impl RateLimit {
+    pub fn new(policy: RateLimitPolicy) -> Self {
+        Self {
+            policy: Rc::new(policy),
+        }
+    }
+}
+
+impl<S: 'static, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            policy: self.policy.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    policy: Rc<RateLimitPolicy>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    #[tracing::instrument("RateLimitMiddleware", skip(self, req), fields(policy = self.policy.name))]
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+        let policy = self.policy.clone();
+
+        Box::pin(async move {
+            // Unwrap because we know that we have the data, otherwise that something is wrong
+            let app_data = req.app_data::<web::Data<AppData>>().unwrap();
+
+            let key = request_key(&req, policy.key, &app_data.config.server.trusted_proxies)?;
+
+            if let Err(retry_after) = app_data.rate_limiter.check(&policy, key) {
+                tracing::debug!("Rate limit exceeded, retry after {}s", retry_after);
+                return Err(AppError::TooManyRequests(retry_after).into());
+            }
+
+            svc.call(req).await
+        })
+    }
+}
+
+/// Build the bucket key of the request, falling back to the client IP when the
+/// requested identity is not available
+fn request_key(
+    req: &ServiceRequest,
+    key: RateLimitKey,
+    trusted_proxies: &[IpAddr],
+) -> Result<String, actix_web::Error> {
+    match key {
+        RateLimitKey::User => {
+            if let Some(user_id) = req.get_session().get::<i32>("user_id")? {
+                return Ok(format!("user:{}", user_id));
+            }
+        }
+        RateLimitKey::ApiKey => {
+            // Only the tokens of a valid session are used, a random token would get a new bucket
+            // on each request otherwise
+            let is_authenticated = req.get_session().get::<i32>("user_id")?.is_some();
+            let token = req
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok());
+
+            if let (true, Some(token)) = (is_authenticated, token) {
+                // Never keep the raw token in memory, the counters are exposed to the admins
+                return Ok(format!("key:{}", hashing::hash_token(token)));
+            }
+        }
+        RateLimitKey::Ip => {}
+    }
+
+    let ip = client_ip(req.request(), trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_owned());
+
+    Ok(format!("ip:{}", ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+
+    use actix_multi_session::SessionExt;
+    use actix_web::{dev::ServiceRequest, http::header::AUTHORIZATION, test::TestRequest};
+
+    use super::request_key;
+    use crate::{core::rate_limit::RateLimitKey, helpers::hashing};
+
+    const PROXY: &str = "10.0.0.1";
+
+    fn request(peer: &str, user_id: Option<i32>) -> ServiceRequest {
+        let req = TestRequest::default()
+            .peer_addr(SocketAddr::new(peer.parse().unwrap(), 4000))
+            .insert_header((AUTHORIZATION, "Bearer token"))
+            .insert_header(("x-forwarded-for", "203.0.113.7"))
+            .to_srv_request();
+
+        if let Some(user_id) = user_id {
+            req.get_session().insert("user_id", user_id).unwrap();
+        }
+
+        req
+    }
+
+    fn trusted_proxies() -> Vec<IpAddr> {
+        vec![PROXY.parse().unwrap()]
+    }
+
+    #[test]
+    fn api_keys_of_valid_sessions_are_hashed() {
+        let key = request_key(&request(PROXY, Some(1)), RateLimitKey::ApiKey, &[]).unwrap();
+
+        assert_eq!(key, format!("key:{}", hashing::hash_token("Bearer token")));
+    }
+
+    #[test]
+    fn unauthenticated_api_keys_fall_back_to_the_ip() {
+        let key = request_key(
+            &request(PROXY, None),
+            RateLimitKey::ApiKey,
+            &trusted_proxies(),
+        )
+        .unwrap();
+
+        assert_eq!(key, "ip:203.0.113.7");
+    }
+
+    #[test]
+    fn users_are_keyed_by_id() {
+        let key = request_key(&request(PROXY, Some(1)), RateLimitKey::User, &[]).unwrap();
+        assert_eq!(key, "user:1");
+
+        let key = request_key(&request(PROXY, None), RateLimitKey::User, &[]).unwrap();
+        assert_eq!(key, format!("ip:{}", PROXY));
+    }
+
+    #[test]
+    fn forwarded_ips_are_read_from_trusted_proxies() {
+        let key = request_key(
+            &request(PROXY, Some(1)),
+            RateLimitKey::Ip,
+            &trusted_proxies(),
+        )
+        .unwrap();
+
+        assert_eq!(key, "ip:203.0.113.7");
+    }
+
+    #[test]
+    fn forwarded_ips_of_untrusted_peers_are_ignored() {
+        let key = request_key(
+            &request("198.51.100.2", None),
+            RateLimitKey::Ip,
+            &trusted_proxies(),
+        )
+        .unwrap();
+
+        assert_eq!(key, "ip:198.51.100.2");
+    }
+}
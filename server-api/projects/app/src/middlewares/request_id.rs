@@ -0,0 +1,150 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest request ID accepted from the clients
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    /// ID of the request being handled, set by the middleware
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// ID of the request being handled, to correlate the error responses with the logs
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Give an ID to each request, reusing the `X-Request-Id` header of the client or the proxy when
+/// there is one. The handling of the request runs in a span carrying the ID, and it is sent back
+/// in the `X-Request-Id` header of the response.
+pub struct RequestId;
+
+impl<S: 'static, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+
+        let request_id = req
+            .headers()
+            .get(&X_REQUEST_ID)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| is_valid_request_id(value))
+            .map(str::to_owned)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.path(),
+        );
+
+        Box::pin(
+            CURRENT_REQUEST_ID.scope(
+                request_id.clone(),
+                async move {
+                    let http_req = req.request().clone();
+
+                    // The errors of the inner middlewares are turned into responses here to carry
+                    // the request ID too
+                    let mut res = match svc.call(req).await {
+                        Ok(res) => res.map_into_left_body(),
+                        Err(err) => ServiceResponse::from_err(err, http_req).map_into_right_body(),
+                    };
+
+                    // Only valid header values are kept or generated
+                    if let Ok(value) = HeaderValue::from_str(&request_id) {
+                        res.headers_mut().insert(X_REQUEST_ID.clone(), value);
+                    }
+
+                    Ok(res)
+                }
+                .instrument(span),
+            ),
+        )
+    }
+}
+
+/// Only short IDs of visible ASCII characters are reused, to keep the logs readable
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App, HttpResponse};
+
+    use super::{RequestId, X_REQUEST_ID};
+    use crate::core::errors::{AppError, ProblemDetails};
+
+    #[actix_rt::test]
+    async fn server_errors_carry_the_request_id() {
+        let app = test::init_service(App::new().wrap(RequestId).route(
+            "/",
+            web::get().to(|| async {
+                Err::<HttpResponse, _>(AppError::Other(anyhow::anyhow!("unreachable database")))
+            }),
+        ))
+        .await;
+
+        let request = test::TestRequest::get()
+            .uri("/")
+            .insert_header((X_REQUEST_ID.clone(), "proxy-request-1"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(
+            response.headers().get(&X_REQUEST_ID).unwrap(),
+            "proxy-request-1"
+        );
+        let problem: ProblemDetails = test::read_body_json(response).await;
+        assert_eq!(problem.correlation_id.as_deref(), Some("proxy-request-1"));
+    }
+}
@@ -0,0 +1,81 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    HttpMessage,
+};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+
+use crate::core::errors::AppError;
+use crate::entities::user::{Model as UserModel, UserRole};
+
+/// Reject the users whose role doesn't include the required one.
+/// It must be wrapped inside the `Auth` middleware, which provides the user.
+pub struct RequireRole {
+    role: UserRole,
+}
+
+impl RequireRole {
+    pub fn new(role: UserRole) -> Self {
+        Self { role }
+    }
+}
+
+impl<S: 'static, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RequireRoleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleMiddleware {
+            service: Rc::new(service),
+            role: self.role.clone(),
+        }))
+    }
+}
+
+pub struct RequireRoleMiddleware<S> {
+    service: Rc<S>,
+    role: UserRole,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    #[tracing::instrument("RequireRoleMiddleware", skip(self, req), fields(role = ?self.role))]
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+
+        let is_allowed = req
+            .extensions()
+            .get::<UserModel>()
+            .map(|user| user.role.includes(&self.role))
+            .unwrap_or(false);
+
+        Box::pin(async move {
+            if !is_allowed {
+                return Err(AppError::Forbidden.into());
+            }
+
+            svc.call(req).await
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web, HttpMessage,
+};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+
+use crate::entities::user::Model as UserModel;
+use crate::{core::errors::AppError, data::AppData};
+
+/// Reject the users who must enroll in two-factor authentication but haven't yet.
+/// It must be wrapped inside the `Auth` middleware, which provides the user.
+pub struct TwoFactor;
+
+impl<S: 'static, B> Transform<S, ServiceRequest> for TwoFactor
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = TwoFactorMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TwoFactorMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct TwoFactorMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for TwoFactorMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    #[tracing::instrument("TwoFactorMiddleware", skip(self, req))]
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+
+        Box::pin(async move {
+            // Unwrap because we know that we have the data, otherwise that something is wrong
+            let app_data = req.app_data::<web::Data<AppData>>().unwrap();
+
+            if app_data.config.two_factor.enforce {
+                let is_missing = req
+                    .extensions()
+                    .get::<UserModel>()
+                    .map(|user| user.role.requires_two_factor() && !user.has_two_factor())
+                    .unwrap_or(false);
+
+                if is_missing {
+                    return Err(AppError::TwoFactorRequired.into());
+                }
+            }
+
+            svc.call(req).await
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::helpers::validation::required_str::validate_required_str;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct EmailInput {
+    #[validate(email)]
+    #[schema(format = "email")]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct VerifyEmailInput {
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ResetPasswordInput {
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub token: String,
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub password: String,
+}
@@ -1 +1,8 @@
+use serde::Serialize;
+use utoipa::ToSchema;
 
+/// Returned when a background job is started, its progress is listed by `GET /admin/jobs`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobStarted {
+    pub job_id: String,
+}
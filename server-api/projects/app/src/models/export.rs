@@ -0,0 +1,16 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Rows of every table of the instance, written by the `export` command. The files of the bucket
+/// and the sessions aren't part of it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceExport {
+    /// Last migration applied to the exported database, the import requires the same one
+    pub schema_version: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    /// Rows by table, as JSON objects keyed by column
+    pub tables: BTreeMap<String, Vec<serde_json::Value>>,
+}
@@ -4,50 +4,162 @@ use actix_multipart::form::MultipartForm;
 use actix_web::Either;
 use serde::Deserialize;
 use serde::Serialize;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
-use crate::entities::game_banner::BannerType;
+use crate::core::s3::S3Client;
+use crate::entities::game::Model as GameModel;
 use crate::entities::game_banner::Model as GameBannerModel;
+use crate::entities::game_media::{self, MediaKind, Model as GameMediaModel};
+use crate::helpers::images::ImageFormat;
+use crate::models::pagination::Paginated;
 
-#[derive(Debug, Deserialize, Validate)]
-pub struct GameCreateInput {
-    #[validate(length(min = 1, message = "Name is required"))]
-    pub name: String,
-    pub description: Option<String>,
-    pub banner_type: Option<GameBannerCreateInput>,
+pub use game_sync_common::games::{
+    FacetCount, GameBanner, GameBannerInput, GameDetails, GameFacets, GameInput, GameMedia,
+    GameMediaSet, GameTags, ImageVariant, Platform, SystemRequirements,
+};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GameSearchResponse {
+    #[serde(flatten)]
+    #[schema(value_type = PaginatedGames)]
+    pub results: Paginated<GameModel>,
+    pub facets: GameFacets,
 }
 
-#[derive(Debug, Serialize)]
-pub struct GameGetResponse {
-    pub id: i32,
-    pub name: String,
-    pub description: Option<String>,
-    pub banner: Option<GameBannerModel>,
+/// A resized version of a banner image, as stored in the `variants` column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredVariant {
+    pub size: String,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub key: String,
+}
+
+/// Public URLs of the stored variants
+pub fn variants_response(variants: Vec<StoredVariant>, s3: &S3Client) -> Vec<ImageVariant> {
+    variants
+        .into_iter()
+        .map(|variant| ImageVariant {
+            url: s3.public_url(&variant.key),
+            size: variant.size,
+            format: variant.format,
+            width: variant.width,
+            height: variant.height,
+        })
+        .collect()
+}
+
+pub fn banner_response(banner: GameBannerModel, s3: &S3Client) -> GameBanner {
+    let variants = variants_response(banner.get_variants(), s3);
+
+    GameBanner {
+        id: banner.id,
+        game_id: banner.game_id,
+        banner_type: banner.banner_type,
+        value: banner.value,
+        accent_color: banner.accent_color,
+        variants,
+    }
+}
+
+pub fn media_response(media: GameMediaModel, s3: &S3Client) -> GameMedia {
+    let url = if game_media::image_sizes(media.kind).is_some() {
+        s3.public_url(&media.value)
+    } else {
+        media.value.clone()
+    };
+
+    GameMedia {
+        id: media.id,
+        kind: media.kind,
+        position: media.position,
+        url,
+        accent_color: media.accent_color.clone(),
+        variants: variants_response(media.get_variants(), s3),
+    }
 }
 
-#[derive(Debug, Deserialize, Validate)]
-pub struct GameBannerCreateInput {
-    pub banner_type: BannerType,
-    pub value: Option<String>,
+/// Group the media of a game, screenshots are expected in their display order
+pub fn media_set_response(media: Vec<GameMediaModel>, s3: &S3Client) -> GameMediaSet {
+    let mut set = GameMediaSet::default();
+
+    for media in media {
+        let kind = media.kind;
+        let response = media_response(media, s3);
+
+        match kind {
+            MediaKind::Icon => set.icon = Some(response),
+            MediaKind::Logo => set.logo = Some(response),
+            MediaKind::Hero => set.hero = Some(response),
+            MediaKind::Trailer => set.trailer = Some(response),
+            MediaKind::Screenshot => set.screenshots.push(response),
+        }
+    }
+
+    set
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
 pub struct GameViewPath {
     #[validate(range(min = 1, message = "Game ID is required"))]
+    #[param(minimum = 1)]
     pub id: i32,
 }
 
-#[derive(MultipartForm)]
+#[derive(MultipartForm, ToSchema)]
 #[multipart(deny_unknown_fields)]
 pub struct GameBannerImageUpload {
-    #[multipart(limit = "2 MiB", rename = "value")]
-    pub image: TempFile,
+    /// Image of at most 2 MiB
+    #[multipart(limit = "2 MiB")]
+    #[schema(value_type = String, format = Binary)]
+    pub value: TempFile,
 }
 
-#[derive(MultipartForm)]
+#[derive(MultipartForm, ToSchema)]
 pub struct GameBannerColorUpload {
+    #[schema(value_type = String)]
     pub color: Text<String>,
 }
 
+#[derive(MultipartForm, ToSchema)]
+#[multipart(deny_unknown_fields)]
+pub struct GameMediaUpload {
+    #[schema(value_type = MediaKind)]
+    pub kind: Text<MediaKind>,
+    /// Image of at most 10 MiB
+    #[multipart(limit = "10 MiB")]
+    #[schema(value_type = String, format = Binary)]
+    pub file: TempFile,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct GameTrailerInput {
+    #[validate(url(message = "Invalid trailer URL"))]
+    #[schema(format = "uri")]
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct GameMediaReorderInput {
+    /// Screenshot IDs, in their new order
+    #[validate(length(min = 1, message = "At least one screenshot is required"))]
+    #[schema(min_items = 1)]
+    pub ids: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct GameMediaViewPath {
+    #[validate(range(min = 1, message = "Game ID is required"))]
+    #[param(minimum = 1)]
+    pub id: i32,
+    #[validate(range(min = 1, message = "Media ID is required"))]
+    #[param(minimum = 1)]
+    pub media_id: i32,
+}
+
 pub type GameBannerUpload =
     Either<MultipartForm<GameBannerImageUpload>, MultipartForm<GameBannerColorUpload>>;
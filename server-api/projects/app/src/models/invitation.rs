@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::entities::{invitation::Model as InvitationModel, user::UserRole};
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct InvitationCreateInput {
+    pub role: UserRole,
+    #[serde(default)]
+    pub game_ids: Vec<i32>,
+    #[validate(range(min = 1, message = "An invitation must be usable at least once"))]
+    #[schema(minimum = 1)]
+    pub max_uses: Option<i32>,
+    /// Lifetime of the invitation, in hours, up to 30 days
+    #[validate(range(
+        min = 1,
+        max = 720,
+        message = "An invitation must be valid between one hour and 30 days"
+    ))]
+    #[schema(minimum = 1, maximum = 720)]
+    pub expires_in: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct InvitationViewPath {
+    #[validate(range(min = 1, message = "Invitation ID is required"))]
+    #[param(minimum = 1)]
+    pub id: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationCreated {
+    #[serde(flatten)]
+    pub invitation: InvitationModel,
+    /// Only returned once, it can't be retrieved afterwards
+    pub code: String,
+}
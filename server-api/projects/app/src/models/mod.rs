@@ -1,5 +1,14 @@
+pub mod account;
 pub mod admin;
+pub mod audit;
+pub mod export;
 pub mod games;
+pub mod invitation;
+pub mod oidc;
 pub mod pagination;
+pub mod passkey;
 pub mod search;
+pub mod setup;
+pub mod storage;
+pub mod two_factor;
 pub mod user;
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+pub use game_sync_common::auth::OidcAuthorization;
+
+#[derive(Debug, Deserialize, Validate, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackInput {
+    #[validate(length(min = 1, message = "Code is required"))]
+    #[schema(min_length = 1)]
+    #[param(min_length = 1)]
+    pub code: String,
+    #[validate(length(min = 1, message = "State is required"))]
+    #[schema(min_length = 1)]
+    #[param(min_length = 1)]
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct OidcAuthorizeInput {
+    /// Loopback URL listened by the desktop client, e.g. `http://127.0.0.1:43125/callback`
+    #[validate(url)]
+    #[schema(format = "uri")]
+    pub redirect_uri: String,
+}
@@ -1,9 +1,27 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
 use validator::Validate;
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Validate)]
+use crate::core::errors::{AppError, AppResult};
+
+pub use game_sync_common::pagination::{Paginated, PaginationMeta};
+
+/// Maximum number of items in a page
+const MAX_PER_PAGE: u64 = 100;
+
+/// Listings are paginated by page by default, or by cursor when a `cursor` is given.
+/// An empty cursor requests the first page.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Pagination {
+    #[param(minimum = 1)]
     page: Option<u64>,
+    #[param(minimum = 1, maximum = 100)]
     per_page: Option<u64>,
+    cursor: Option<String>,
+    /// Count the items when paginating by cursor, skipped by default as it is slow on big listings
+    with_total: Option<bool>,
 }
 
 impl Default for Pagination {
@@ -11,30 +29,108 @@ impl Default for Pagination {
         Self {
             page: Some(1),
             per_page: Some(10),
+            cursor: None,
+            with_total: None,
         }
     }
 }
 
 impl Pagination {
+    /// Pages start at 1
     pub fn get_page(&self) -> u64 {
-        self.page.unwrap_or(0)
+        self.page.unwrap_or(1).max(1)
     }
 
     pub fn get_per_page(&self) -> u64 {
-        self.per_page.unwrap_or(10)
+        self.per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE)
     }
+
+    /// `Some` when paginating by cursor, with the decoded cursor if this isn't the first page
+    pub fn get_cursor(&self) -> AppResult<Option<Option<Cursor>>> {
+        match self.cursor.as_deref() {
+            None => Ok(None),
+            Some("") => Ok(Some(None)),
+            Some(cursor) => Cursor::decode(cursor).map(|cursor| Some(Some(cursor))),
+        }
+    }
+
+    pub fn with_total(&self) -> bool {
+        self.with_total.unwrap_or(false)
+    }
+}
+
+/// Position in a listing: the sort key and the ID of the last item of a page.
+/// It is given to the clients as an opaque string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    /// Name of the sort, a cursor can't be used with another sort
+    #[serde(rename = "s")]
+    pub sort: String,
+    /// Sort key of the last item, formatted as text by the database
+    #[serde(rename = "k")]
+    pub key: String,
+    #[serde(rename = "i")]
+    pub id: i64,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
-pub struct PaginationMeta {
-    pub current_page: u64,
-    pub total_items: u64,
-    pub total_pages: u64,
-    pub per_page: u64,
+impl Cursor {
+    pub fn encode(&self) -> AppResult<String> {
+        let json = serde_json::to_vec(self).map_err(|e| AppError::Other(e.into()))?;
+
+        Ok(URL_SAFE_NO_PAD.encode(json))
+    }
+
+    pub fn decode(cursor: &str) -> AppResult<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or(AppError::BadRequest("Invalid cursor".to_string()))
+    }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
-pub struct Paginated<T> {
-    pub data: Vec<T>,
-    pub meta: PaginationMeta,
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    use super::{Cursor, Pagination};
+    use crate::core::errors::AppError;
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = Cursor {
+            sort: "updated.desc".to_string(),
+            key: "2023-12-14 10:15:33.123456+00".to_string(),
+            id: 42,
+        };
+
+        let decoded = Cursor::decode(&cursor.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded.sort, cursor.sort);
+        assert_eq!(decoded.key, cursor.key);
+        assert_eq!(decoded.id, cursor.id);
+    }
+
+    #[test]
+    fn invalid_cursors_are_bad_requests() {
+        let not_json = URL_SAFE_NO_PAD.encode("not json");
+        let missing_id = URL_SAFE_NO_PAD.encode(r#"{"s":"name.asc","k":"Celeste"}"#);
+
+        for cursor in ["not base64!", not_json.as_str(), missing_id.as_str()] {
+            assert!(matches!(
+                Cursor::decode(cursor),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn empty_cursor_requests_the_first_page() {
+        let pagination = Pagination {
+            cursor: Some(String::new()),
+            ..Default::default()
+        };
+
+        assert!(matches!(pagination.get_cursor(), Ok(Some(None))));
+    }
 }
@@ -0,0 +1,29 @@
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+use webauthn_rs::prelude::RegisterPublicKeyCredential;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct PasskeyRegisterInput {
+    #[validate(length(min = 1, max = 64, message = "Name is required"))]
+    #[schema(min_length = 1, max_length = 64)]
+    pub name: String,
+    /// WebAuthn attestation returned by the authenticator
+    #[schema(value_type = Object)]
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct PasskeyLoginStartInput {
+    #[validate(email)]
+    #[schema(format = "email")]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct PasskeyPath {
+    #[validate(range(min = 1, message = "Passkey ID is required"))]
+    #[param(minimum = 1)]
+    pub id: i32,
+}
@@ -1,44 +1,166 @@
 use sea_orm::Order;
+use time::Date;
+use utoipa::IntoParams;
 use validator::Validate;
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
-pub enum SortOrder {
-    #[serde(rename = "asc")]
-    Asc,
-    #[serde(rename = "desc")]
-    Desc,
-}
-
-impl Into<Order> for SortOrder {
-    fn into(self) -> Order {
-        match self {
-            Self::Asc => Order::Asc,
-            Self::Desc => Order::Desc,
-        }
-    }
-}
+pub use game_sync_common::games::{SortBy, SortOrder};
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Validate)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Search {
     search: Option<String>,
+    sort: Option<SortBy>,
     sort_order: Option<SortOrder>,
+    developer: Option<String>,
+    publisher: Option<String>,
+    /// Comma separated lists, the games must have all the given values
+    tags: Option<String>,
+    genres: Option<String>,
+    platforms: Option<String>,
+    #[serde(default, with = "crate::helpers::dates::option")]
+    released_after: Option<Date>,
+    #[serde(default, with = "crate::helpers::dates::option")]
+    released_before: Option<Date>,
 }
 
 impl Default for Search {
     fn default() -> Self {
         Self {
             search: None,
-            sort_order: Some(SortOrder::Asc),
+            sort: None,
+            sort_order: None,
+            developer: None,
+            publisher: None,
+            tags: None,
+            genres: None,
+            platforms: None,
+            released_after: None,
+            released_before: None,
         }
     }
 }
 
 impl Search {
-    pub fn get_sort_order(&self) -> SortOrder {
-        self.sort_order.clone().unwrap_or(SortOrder::Asc)
+    /// Relevance when searching, name otherwise
+    pub fn get_sort(&self) -> SortBy {
+        match (self.sort, self.get_search()) {
+            (Some(SortBy::Relevance), None) | (None, None) => SortBy::Name,
+            (Some(sort), _) => sort,
+            (None, Some(_)) => SortBy::Relevance,
+        }
+    }
+
+    /// Dates are sorted from the most recent by default, the rest alphabetically
+    pub fn get_sort_order(&self) -> Order {
+        let sort_order = self.sort_order.clone().unwrap_or(match self.sort {
+            Some(SortBy::Updated) | Some(SortBy::Released) => SortOrder::Desc,
+            _ => SortOrder::Asc,
+        });
+
+        match sort_order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        }
     }
 
     pub fn get_search(&self) -> Option<String> {
-        self.search.clone()
+        self.search
+            .as_deref()
+            .map(str::trim)
+            .filter(|search| !search.is_empty())
+            .map(str::to_owned)
+    }
+
+    /// Prefix query matching all the words of the search, e.g. `zelda breath` becomes
+    /// `zelda:* & breath:*`. Punctuation is dropped, so the input can't break the query syntax.
+    pub fn get_ts_query(&self) -> Option<String> {
+        let search = self.get_search()?;
+
+        let terms: Vec<String> = search
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .map(|term| format!("{}:*", term.to_lowercase()))
+            .collect();
+
+        if terms.is_empty() {
+            return None;
+        }
+
+        Some(terms.join(" & "))
+    }
+
+    pub fn get_developer(&self) -> Option<String> {
+        self.developer.clone()
+    }
+
+    pub fn get_publisher(&self) -> Option<String> {
+        self.publisher.clone()
+    }
+
+    pub fn get_tags(&self) -> Vec<String> {
+        split_list(&self.tags)
+    }
+
+    pub fn get_genres(&self) -> Vec<String> {
+        split_list(&self.genres)
+    }
+
+    pub fn get_platforms(&self) -> Vec<String> {
+        split_list(&self.platforms)
+    }
+
+    pub fn get_released_after(&self) -> Option<Date> {
+        self.released_after
+    }
+
+    pub fn get_released_before(&self) -> Option<Date> {
+        self.released_before
+    }
+}
+
+/// Split a comma separated list, with its values normalized like the stored tags
+fn split_list(list: &Option<String>) -> Vec<String> {
+    list.as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Search;
+
+    fn ts_query(search: &str) -> Option<String> {
+        Search {
+            search: Some(search.to_string()),
+            ..Default::default()
+        }
+        .get_ts_query()
+    }
+
+    #[test]
+    fn words_are_prefix_matched() {
+        assert_eq!(
+            ts_query("Zelda Breath"),
+            Some("zelda:* & breath:*".to_string())
+        );
+    }
+
+    #[test]
+    fn query_syntax_is_dropped() {
+        assert_eq!(
+            ts_query("half-life & (portal | !doom):*"),
+            Some("half:* & life:* & portal:* & doom:*".to_string())
+        );
+        assert_eq!(ts_query("Pokémon"), Some("pokémon:*".to_string()));
+    }
+
+    #[test]
+    fn blank_searches_have_no_query() {
+        assert_eq!(ts_query("   "), None);
+        assert_eq!(ts_query("&|!()':*"), None);
+        assert_eq!(Search::default().get_ts_query(), None);
     }
 }
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::helpers::validation::required_str::validate_required_str;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SetupInput {
+    /// Token printed in the logs at startup
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub token: String,
+    #[validate(email)]
+    #[schema(format = "email")]
+    pub email: String,
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub password: String,
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+use crate::core::s3::StoredObject;
+
+/// Result of a garbage collector run, or of a dry-run
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GcReport {
+    /// Number of files listed under `games/`
+    pub scanned: usize,
+    /// Unreferenced files older than the grace period
+    pub orphans: Vec<StoredObject>,
+    pub orphans_size: u64,
+    /// Stale pending uploads found in the bucket, they are marked as finished
+    pub finished_uploads: Vec<PendingUpload>,
+    /// Stale pending uploads missing from the bucket, they are forgotten
+    pub aborted_uploads: Vec<PendingUpload>,
+}
+
+/// Upload of a version or a save whose presigned URL was issued, but which wasn't found in the
+/// bucket yet
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PendingUpload {
+    pub id: i32,
+    pub key: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+pub use game_sync_common::auth::TwoFactorChallenge;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct TwoFactorCodeInput {
+    /// Either a TOTP code or a recovery code
+    #[validate(length(min = 6, max = 11, message = "Invalid code"))]
+    #[schema(min_length = 6, max_length = 11)]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    pub provisioning_uri: String,
+    /// PNG image of the provisioning URI, encoded in base64
+    pub qr_code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
@@ -1,21 +1,108 @@
 use std::fmt::Debug;
 
+use crate::entities::user::UserRole;
 use crate::helpers::validation::required_str::validate_required_str;
 use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UserLoginRequest {
     #[validate(email)]
+    #[schema(format = "email")]
     pub email: String,
     #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
     pub password: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UserCreateInput {
     #[validate(email)]
+    #[schema(format = "email")]
     pub email: String,
     #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
     pub password: String,
+    /// Required when registration is invite-only
+    pub invitation_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UserAdminCreateInput {
+    #[validate(email)]
+    #[schema(format = "email")]
+    pub email: String,
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub password: String,
+    pub role: UserRole,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UserUpdateInput {
+    #[validate(email)]
+    #[schema(format = "email")]
+    pub email: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UserPasswordInput {
+    #[validate(
+        custom = "validate_required_str",
+        length(min = 8, message = "The password must have at least 8 characters")
+    )]
+    #[schema(min_length = 8)]
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct UserViewPath {
+    #[validate(range(min = 1, message = "User ID is required"))]
+    #[param(minimum = 1)]
+    pub id: i32,
+}
+
+#[derive(Debug, Default, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserFilter {
+    pub role: Option<UserRole>,
+    pub disabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ProfileUpdateInput {
+    #[validate(email)]
+    #[schema(format = "email")]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ProfilePasswordInput {
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub current_password: String,
+    #[validate(custom = "validate_required_str")]
+    #[schema(min_length = 1)]
+    pub password: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+
+    use super::UserPasswordInput;
+
+    #[test]
+    fn passwords_have_at_least_8_characters() {
+        let input = |password: &str| UserPasswordInput {
+            password: password.to_owned(),
+        };
+
+        assert!(input("").validate().is_err());
+        assert!(input("1234567").validate().is_err());
+        assert!(input("12345678").validate().is_ok());
+    }
 }
@@ -1,9 +1,85 @@
-use super::user;
-use crate::core::{database::DbPool, errors::AppError};
+use rand::Rng;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, Set,
+    Statement, TransactionTrait,
+};
+use time::OffsetDateTime;
+
+use crate::core::{
+    database::DbPool,
+    errors::{AppError, AppResult},
+};
+use crate::entities::{
+    prelude::*,
+    user::{self, Model as UserModel, UserRole},
+};
 
 /// The application is configured only is there is at least 1 admin user to manage the app
 pub async fn is_configured(db: &DbPool) -> Result<bool, AppError> {
-    let user = user::count_users(db).await?;
+    let admins = User::find()
+        .filter(user::Column::Role.eq(UserRole::Admin))
+        .count(db)
+        .await?;
+
+    Ok(admins > 0)
+}
+
+/// Token required to create the first admin, it is only generated while the app isn't configured
+pub async fn generate_setup_token(db: &DbPool) -> AppResult<Option<String>> {
+    if is_configured(db).await? {
+        return Ok(None);
+    }
+
+    let token = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+
+    Ok(Some(token))
+}
+
+/// Create the first admin, failing if the app has been configured in the meantime
+#[tracing::instrument("Create first admin", skip(db, password))]
+pub async fn create_first_admin(db: &DbPool, email: &str, password: &str) -> AppResult<UserModel> {
+    let txn = db.begin().await?;
+
+    // Serialize concurrent setups, the lock is released with the transaction
+    txn.execute(Statement::from_string(
+        txn.get_database_backend(),
+        r#"LOCK TABLE "user" IN SHARE ROW EXCLUSIVE MODE"#.to_owned(),
+    ))
+    .await?;
+
+    let admins = User::find()
+        .filter(user::Column::Role.eq(UserRole::Admin))
+        .count(&txn)
+        .await?;
+
+    if admins > 0 {
+        return Err(AppError::AlreadyExists(
+            "The application is already configured".to_string(),
+        ));
+    }
+
+    let exists = User::find()
+        .filter(user::Column::Email.eq(email.to_lowercase()))
+        .one(&txn)
+        .await?;
+
+    if exists.is_some() {
+        return Err(AppError::AlreadyExists("User already exists".to_string()));
+    }
+
+    let user = user::ActiveModel {
+        email: Set(email.to_owned()),
+        password: Set(password.to_owned()),
+        role: Set(UserRole::Admin),
+        email_verified_at: Set(Some(OffsetDateTime::now_utc())),
+        ..Default::default()
+    };
+
+    let user = user.insert(&txn).await?;
+
+    txn.commit().await?;
+
+    tracing::info!("Created the first admin {}", user.id);
 
-    Ok(user > 0)
+    Ok(user)
 }
@@ -0,0 +1,140 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, Order, QueryFilter, Set,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use time::{Duration, OffsetDateTime};
+
+use crate::entities::{audit_log, prelude::*};
+use crate::{
+    core::{
+        audit::AuditContext,
+        database::DbPool,
+        errors::{AppError, AppResult},
+    },
+    entities::audit_log::Model as AuditLogModel,
+    models::{
+        audit::AuditLogFilter,
+        pagination::{Paginated, Pagination},
+    },
+    repositories::pagination::{self, KeyType, Keyset},
+};
+
+/// Record an action in the audit log. `before` and `after` are reduced to the fields that changed.
+#[tracing::instrument("Record audit log", skip(db, ctx, before, after))]
+pub async fn record<C, T>(
+    db: &C,
+    ctx: &AuditContext,
+    action: &str,
+    target_type: &str,
+    target_id: Option<String>,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> AppResult<()>
+where
+    C: ConnectionTrait,
+    T: Serialize,
+{
+    let before = before
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| AppError::Other(e.into()))?;
+    let after = after
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| AppError::Other(e.into()))?;
+
+    let entry = audit_log::ActiveModel {
+        actor_id: Set(ctx.actor_id),
+        action: Set(action.to_owned()),
+        target_type: Set(target_type.to_owned()),
+        target_id: Set(target_id),
+        changes: Set(diff(before, after)),
+        ip: Set(ctx.ip.clone()),
+        user_agent: Set(ctx.user_agent.clone()),
+        ..Default::default()
+    };
+
+    entry.insert(db).await?;
+
+    Ok(())
+}
+
+/// Only keep the fields whose value differs between both states
+fn diff(before: Option<Value>, after: Option<Value>) -> Option<Value> {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let mut changed_before = Map::new();
+            let mut changed_after = Map::new();
+
+            for (key, value) in after {
+                if before.get(&key) != Some(&value) {
+                    if let Some(previous) = before.get(&key) {
+                        changed_before.insert(key.clone(), previous.clone());
+                    }
+                    changed_after.insert(key, value);
+                }
+            }
+
+            Some(serde_json::json!({ "before": changed_before, "after": changed_after }))
+        }
+        (None, None) => None,
+        (before, after) => Some(serde_json::json!({ "before": before, "after": after })),
+    }
+}
+
+pub async fn paginate_audit_logs(
+    db: &DbPool,
+    pagination_query: &Pagination,
+    filter: &AuditLogFilter,
+) -> AppResult<Paginated<AuditLogModel>> {
+    let mut query = AuditLog::find();
+
+    if let Some(actor_id) = filter.actor_id {
+        query = query.filter(audit_log::Column::ActorId.eq(actor_id));
+    }
+
+    if let Some(action) = &filter.action {
+        query = query.filter(audit_log::Column::Action.starts_with(action));
+    }
+
+    if let Some(target_type) = &filter.target_type {
+        query = query.filter(audit_log::Column::TargetType.eq(target_type));
+    }
+
+    if let Some(target_id) = &filter.target_id {
+        query = query.filter(audit_log::Column::TargetId.eq(target_id));
+    }
+
+    if let Some(from) = filter.from {
+        query = query.filter(audit_log::Column::CreatedAt.gte(from));
+    }
+
+    if let Some(to) = filter.to {
+        query = query.filter(audit_log::Column::CreatedAt.lt(to));
+    }
+
+    // Entries are sorted by creation, their IDs are increasing
+    let keyset = Keyset::column(
+        "id",
+        audit_log::Column::Id,
+        KeyType::BigInt,
+        audit_log::Column::Id,
+        Order::Desc,
+    );
+
+    pagination::paginate(db, query, pagination_query, keyset).await
+}
+
+/// Delete the entries older than the retention period, returning how many were deleted
+#[tracing::instrument("Purge audit logs", skip(db))]
+pub async fn purge_audit_logs(db: &DbPool, retention_days: u32) -> AppResult<u64> {
+    let limit = OffsetDateTime::now_utc() - Duration::days(retention_days.into());
+
+    let result = AuditLog::delete_many()
+        .filter(audit_log::Column::CreatedAt.lt(limit))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
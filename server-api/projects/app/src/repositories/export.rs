@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use sea_orm::{ConnectionTrait, Statement, TransactionTrait};
+use time::OffsetDateTime;
+
+use crate::{
+    core::{
+        database::{self, DbPool},
+        errors::{AppError, AppResult},
+    },
+    models::export::InstanceExport,
+};
+
+/// Tables in the order of their foreign keys, with whether they have a serial `id` column
+const TABLES: [(&str, bool); 12] = [
+    ("user", true),
+    ("game", true),
+    ("banner", true),
+    ("game_media", true),
+    ("game_tag", false),
+    ("file", true),
+    ("game_grant", false),
+    ("invitation", true),
+    ("passkey", true),
+    ("recovery_code", true),
+    ("user_token", true),
+    ("audit_log", true),
+];
+
+/// Read every table, in a transaction to get a consistent snapshot
+#[tracing::instrument("Export instance", skip(db))]
+pub async fn export(db: &DbPool) -> AppResult<InstanceExport> {
+    let txn = db.begin().await?;
+    let mut tables = BTreeMap::new();
+
+    for (table, _) in TABLES {
+        let row = txn
+            .query_one(Statement::from_string(
+                txn.get_database_backend(),
+                format!(r#"SELECT coalesce(json_agg(t), '[]'::json) AS "rows" FROM "{table}" t"#),
+            ))
+            .await?
+            .ok_or(AppError::NotFoundError)?;
+
+        let rows: serde_json::Value = row.try_get("", "rows")?;
+        let rows = match rows {
+            serde_json::Value::Array(rows) => rows,
+            _ => Vec::new(),
+        };
+
+        tables.insert(table.to_owned(), rows);
+    }
+
+    let schema_version = database::schema_version(db).await?;
+    txn.commit().await?;
+
+    Ok(InstanceExport {
+        schema_version,
+        exported_at: OffsetDateTime::now_utc(),
+        tables,
+    })
+}
+
+/// Insert the rows of an export into an empty instance, with the same schema
+#[tracing::instrument("Import instance", skip(db, export))]
+pub async fn import(db: &DbPool, export: &InstanceExport) -> AppResult<()> {
+    let schema_version = database::schema_version(db).await?;
+    if export.schema_version != schema_version {
+        return Err(AppError::BadRequest(format!(
+            "The export was made with the schema {}, the database has {}",
+            export.schema_version.as_deref().unwrap_or("none"),
+            schema_version.as_deref().unwrap_or("none")
+        )));
+    }
+
+    let txn = db.begin().await?;
+
+    for (table, _) in TABLES {
+        let row = txn
+            .query_one(Statement::from_string(
+                txn.get_database_backend(),
+                format!(r#"SELECT EXISTS (SELECT 1 FROM "{table}") AS "used""#),
+            ))
+            .await?
+            .ok_or(AppError::NotFoundError)?;
+
+        if row.try_get::<bool>("", "used")? {
+            return Err(AppError::BadRequest(format!(
+                "The table {} isn't empty, imports are only done in a new instance",
+                table
+            )));
+        }
+    }
+
+    for (table, serial) in TABLES {
+        let rows = export.tables.get(table).cloned().unwrap_or_default();
+        let count = rows.len();
+
+        // The columns are matched by name, the missing ones are left null
+        txn.execute(Statement::from_sql_and_values(
+            txn.get_database_backend(),
+            &format!(
+                r#"INSERT INTO "{table}" SELECT * FROM json_populate_recordset(NULL::"{table}", $1)"#
+            ),
+            [serde_json::Value::Array(rows).into()],
+        ))
+        .await?;
+
+        // The next rows created by the server must not reuse the imported ids
+        if serial {
+            txn.execute(Statement::from_string(
+                txn.get_database_backend(),
+                format!(
+                    r#"SELECT setval(pg_get_serial_sequence('"{table}"', 'id'), coalesce(max("id"), 0) + 1, false) FROM "{table}""#
+                ),
+            ))
+            .await?;
+        }
+
+        tracing::info!("Imported {} rows of {}", count, table);
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{
+        sea_query::TableRef, EntityName, EntityTrait, IdenStatic, Iterable, RelationTrait,
+    };
+
+    use super::TABLES;
+    use crate::entities::prelude::*;
+
+    /// Table of the entity, whether it has an `id` column and the tables it references
+    fn describe<E: EntityTrait>() -> (String, bool, Vec<String>) {
+        let has_id = E::Column::iter().any(|column| column.as_str() == "id");
+        let references = E::Relation::iter()
+            .map(|relation| relation.def())
+            .filter(|def| !def.is_owner)
+            .map(|def| match def.to_tbl {
+                TableRef::Table(table) => table.to_string(),
+                table => panic!("Unexpected table reference {:?}", table),
+            })
+            .collect();
+
+        (E::default().table_name().to_owned(), has_id, references)
+    }
+
+    #[test]
+    fn tables_cover_the_entities_in_foreign_key_order() {
+        // Every entity of `entities::prelude`
+        let entities = [
+            describe::<AuditLog>(),
+            describe::<File>(),
+            describe::<Game>(),
+            describe::<GameBanner>(),
+            describe::<GameGrant>(),
+            describe::<GameMedia>(),
+            describe::<GameTag>(),
+            describe::<Invitation>(),
+            describe::<Passkey>(),
+            describe::<RecoveryCode>(),
+            describe::<User>(),
+            describe::<UserToken>(),
+        ];
+        let position = |table: &str| TABLES.iter().position(|(name, _)| *name == table);
+
+        assert_eq!(entities.len(), TABLES.len());
+
+        for (table, has_id, references) in &entities {
+            let index = position(table).unwrap_or_else(|| panic!("{} isn't exported", table));
+            assert_eq!(TABLES[index].1, *has_id, "serial id of {}", table);
+
+            for reference in references {
+                assert!(
+                    position(reference).is_some_and(|reference| reference < index),
+                    "{} is imported before {}",
+                    table,
+                    reference
+                );
+            }
+        }
+    }
+}
@@ -1,81 +1,380 @@
 use actix_web::Either;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, Set,
+    sea_query::{Expr, Query},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, ModelTrait, Order,
+    QueryFilter, QueryOrder, QuerySelect, Set, Statement, TransactionTrait,
 };
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
 
+use crate::core::audit::AuditContext;
 use crate::core::database::DbPool;
 use crate::core::errors::{AppError, AppResult};
+use crate::core::jobs::JobHandle;
 
 use crate::core::s3::S3Client;
-use crate::entities::{game, game_banner};
+use crate::entities::game_tag::GameTagKind;
+use crate::entities::{game, game_banner, game_grant, game_tag};
 use crate::entities::{
-    game::Model as GameModel, game_banner::Model as GameBannerModel, prelude::*,
+    game::Model as GameModel,
+    game_banner::Model as GameBannerModel,
+    prelude::*,
+    user::{Model as UserModel, UserRole},
 };
 use crate::helpers;
-use crate::models::games::{GameBannerUpload, GameCreateInput, GameGetResponse};
-use crate::models::pagination::{Paginated, Pagination, PaginationMeta};
-use crate::models::search::Search;
+use crate::models::games::{
+    banner_response, media_set_response, FacetCount, GameBannerUpload, GameDetails, GameFacets,
+    GameInput, GameSearchResponse, GameTags,
+};
+use crate::models::pagination::Pagination;
+use crate::models::search::{Search, SortBy};
+use crate::repositories::{
+    audit, media,
+    pagination::{self, KeyType, Keyset},
+};
 
 pub async fn paginate_games(
     db: &DbPool,
+    user: &UserModel,
     pagination_query: &Pagination,
     search_query: &Search,
-) -> AppResult<Paginated<GameModel>> {
-    let page = pagination_query.get_page();
-    let per_page = pagination_query.get_per_page();
-
+) -> AppResult<GameSearchResponse> {
     tracing::debug!(
-        "page: {}, per_page: {}, search_query: {:?}",
-        page,
-        per_page,
+        "pagination: {:?}, search_query: {:?}",
+        pagination_query,
         search_query
     );
 
-    // Find all games
-    let mut game_query = Game::find();
-
-    // Filter by search query
-    if let Some(search) = search_query.get_search() {
-        game_query = game_query.filter(
-            Condition::any()
-                .add(game::Column::Name.contains(search.clone()))
-                .add(game::Column::Description.contains(search.clone())),
-            // TODO: Add search by author
+    let mut condition = search_condition(search_query);
+
+    // The users only see the games granted to them, the facets included
+    if !user.role.includes(&UserRole::Publisher) {
+        condition = condition.add(
+            game::Column::Id.in_subquery(
+                Query::select()
+                    .column(game_grant::Column::GameId)
+                    .from(GameGrant)
+                    .and_where(game_grant::Column::UserId.eq(user.id))
+                    .to_owned(),
+            ),
         );
     }
+    let keyset = search_keyset(search_query);
 
-    game_query = game_query.order_by(game::Column::Name, search_query.get_sort_order().into());
+    let results = pagination::paginate(
+        db,
+        Game::find().filter(condition.clone()),
+        pagination_query,
+        keyset,
+    )
+    .await?;
 
-    let games_paginator = game_query.paginate(db, per_page);
+    let facets = get_facets(db, condition).await?;
 
-    let counts = games_paginator.num_items_and_pages().await?;
-
-    let games = games_paginator.fetch_page(page).await?;
+    Ok(GameSearchResponse { results, facets })
+}
 
-    Ok(Paginated {
-        data: games,
-        meta: PaginationMeta {
-            current_page: page,
-            total_pages: counts.number_of_pages,
-            total_items: counts.number_of_items,
-            per_page,
+/// Order of the games list, from the requested sort
+fn search_keyset(search_query: &Search) -> Keyset<game::Column> {
+    let order = search_query.get_sort_order();
+
+    match (search_query.get_sort(), search_query.get_ts_query()) {
+        // Best matches always come first
+        // The ranks depend on the query, its cursors can't be used with another one
+        (SortBy::Relevance, Some(ts_query)) => Keyset {
+            name: format!(
+                "relevance.{}",
+                hex::encode(&Sha256::digest(ts_query.as_bytes())[..8])
+            ),
+            key: Expr::cust_with_values(
+                r#"ts_rank("game"."search_vector", to_tsquery('simple', game_sync_unaccent($1)))"#,
+                [ts_query],
+            ),
+            key_type: KeyType::Real,
+            id: game::Column::Id,
+            order: Order::Desc,
+        },
+        (SortBy::Updated, _) => Keyset::column(
+            "updated",
+            game::Column::UpdatedAt,
+            KeyType::Timestamptz,
+            game::Column::Id,
+            order,
+        ),
+        // Games without release date are considered as the oldest
+        (SortBy::Released, _) => Keyset {
+            name: "released".to_string(),
+            key: Expr::cust(r#"COALESCE("game"."release_date", DATE '0001-01-01')"#),
+            key_type: KeyType::Date,
+            id: game::Column::Id,
+            order,
         },
-    })
+        _ => Keyset::column(
+            "name",
+            game::Column::Name,
+            KeyType::Text,
+            game::Column::Id,
+            order,
+        ),
+    }
+}
+
+/// Filters of the search, shared by the games list and its facets
+fn search_condition(search_query: &Search) -> Condition {
+    // Deleted games are only listed in the trash
+    let mut condition = Condition::all().add(game::Column::DeletedAt.is_null());
+
+    // Full-text search over the name, developer, publisher, tags and description
+    if let Some(ts_query) = search_query.get_ts_query() {
+        condition = condition.add(Expr::cust_with_values(
+            r#""game"."search_vector" @@ to_tsquery('simple', game_sync_unaccent($1))"#,
+            [ts_query],
+        ));
+    }
+
+    if let Some(developer) = search_query.get_developer() {
+        condition = condition.add(game::Column::Developer.eq(developer));
+    }
+
+    if let Some(publisher) = search_query.get_publisher() {
+        condition = condition.add(game::Column::Publisher.eq(publisher));
+    }
+
+    if let Some(released_after) = search_query.get_released_after() {
+        condition = condition.add(game::Column::ReleaseDate.gte(released_after));
+    }
+
+    if let Some(released_before) = search_query.get_released_before() {
+        condition = condition.add(game::Column::ReleaseDate.lte(released_before));
+    }
+
+    let tag_filters = [
+        (GameTagKind::Tag, search_query.get_tags()),
+        (GameTagKind::Genre, search_query.get_genres()),
+        (GameTagKind::Platform, search_query.get_platforms()),
+    ];
+
+    // The games must have every requested value
+    for (kind, names) in tag_filters {
+        for name in names {
+            condition = condition.add(
+                game::Column::Id.in_subquery(
+                    Query::select()
+                        .column(game_tag::Column::GameId)
+                        .from(GameTag)
+                        .and_where(game_tag::Column::Kind.eq(kind))
+                        .and_where(game_tag::Column::Name.eq(name))
+                        .to_owned(),
+                ),
+            );
+        }
+    }
+
+    condition
+}
+
+/// Count the games matching the search for each developer, publisher, tag, genre and platform
+async fn get_facets(db: &DbPool, condition: Condition) -> AppResult<GameFacets> {
+    let mut facets = GameFacets {
+        developers: get_column_facet(db, condition.clone(), game::Column::Developer).await?,
+        publishers: get_column_facet(db, condition.clone(), game::Column::Publisher).await?,
+        ..Default::default()
+    };
+
+    let tags: Vec<(GameTagKind, String, i64)> = GameTag::find()
+        .select_only()
+        .column(game_tag::Column::Kind)
+        .column(game_tag::Column::Name)
+        .column_as(game_tag::Column::GameId.count(), "count")
+        .inner_join(Game)
+        .filter(condition)
+        .group_by(game_tag::Column::Kind)
+        .group_by(game_tag::Column::Name)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    for (kind, value, count) in tags {
+        let facet = match kind {
+            GameTagKind::Tag => &mut facets.tags,
+            GameTagKind::Genre => &mut facets.genres,
+            GameTagKind::Platform => &mut facets.platforms,
+        };
+
+        facet.push(FacetCount { value, count });
+    }
+
+    for facet in [&mut facets.tags, &mut facets.genres, &mut facets.platforms] {
+        sort_facet(facet);
+    }
+
+    Ok(facets)
+}
+
+async fn get_column_facet(
+    db: &DbPool,
+    condition: Condition,
+    column: game::Column,
+) -> AppResult<Vec<FacetCount>> {
+    let values: Vec<(String, i64)> = Game::find()
+        .select_only()
+        .column(column)
+        .column_as(game::Column::Id.count(), "count")
+        .filter(condition)
+        .filter(column.is_not_null())
+        .group_by(column)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut facet = values
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    sort_facet(&mut facet);
+
+    Ok(facet)
+}
+
+/// Most used values first, then by name
+fn sort_facet(facet: &mut Vec<FacetCount>) {
+    facet.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+}
+
+/// Normalize tags names: trimmed, lowercase and without duplicates
+fn normalize_tags(names: &[String]) -> AppResult<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(names.len());
+
+    for name in names {
+        let name = name.trim().to_lowercase();
+
+        if name.is_empty() || name.contains(',') || name.len() > 32 {
+            return Err(AppError::BadRequest(format!(
+                "Invalid tag \"{}\": tags must be 1 to 32 characters long, without comma",
+                name
+            )));
+        }
+
+        if !normalized.contains(&name) {
+            normalized.push(name);
+        }
+    }
+
+    Ok(normalized)
 }
 
-pub async fn create_games(db: &DbPool, game_input: &GameCreateInput) -> AppResult<GameModel> {
+/// Rebuild the full-text search document of a game, from its fields and tags.
+/// Name matches rank first, then developer, publisher and tags, then description.
+async fn refresh_search_vector<C: ConnectionTrait>(db: &C, game_id: i32) -> AppResult<()> {
+    db.execute(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"UPDATE "game" SET "search_vector" =
+            setweight(to_tsvector('simple', game_sync_unaccent(coalesce("name", ''))), 'A')
+            || setweight(to_tsvector('simple', game_sync_unaccent(
+                coalesce("developer", '') || ' ' || coalesce("publisher", '') || ' ' ||
+                coalesce((SELECT string_agg("name", ' ') FROM "game_tag" WHERE "game_tag"."game_id" = "game"."id"), '')
+            )), 'B')
+            || setweight(to_tsvector('simple', game_sync_unaccent(coalesce("description", ''))), 'C')
+        WHERE "id" = $1"#,
+        [game_id.into()],
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Replace the tags, genres and platforms of a game
+async fn set_game_tags<C: ConnectionTrait>(
+    db: &C,
+    game_id: i32,
+    game_input: &GameInput,
+) -> AppResult<()> {
+    let platforms: Vec<String> = game_input
+        .platforms
+        .iter()
+        .map(|platform| platform.as_str().to_string())
+        .collect();
+
+    let tags = [
+        (GameTagKind::Tag, normalize_tags(&game_input.tags)?),
+        (GameTagKind::Genre, normalize_tags(&game_input.genres)?),
+        (GameTagKind::Platform, normalize_tags(&platforms)?),
+    ];
+
+    GameTag::delete_many()
+        .filter(game_tag::Column::GameId.eq(game_id))
+        .exec(db)
+        .await?;
+
+    let models: Vec<_> = tags
+        .into_iter()
+        .flat_map(|(kind, names)| {
+            names.into_iter().map(move |name| game_tag::ActiveModel {
+                game_id: Set(game_id),
+                kind: Set(kind),
+                name: Set(name),
+            })
+        })
+        .collect();
+
+    if !models.is_empty() {
+        GameTag::insert_many(models).exec(db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_game_tags(db: &DbPool, game_id: i32) -> AppResult<GameTags> {
+    let tags = GameTag::find()
+        .filter(game_tag::Column::GameId.eq(game_id))
+        .order_by_asc(game_tag::Column::Name)
+        .all(db)
+        .await?;
+
+    let mut game_tags = GameTags::default();
+    for tag in tags {
+        match tag.kind {
+            GameTagKind::Tag => game_tags.tags.push(tag.name),
+            GameTagKind::Genre => game_tags.genres.push(tag.name),
+            GameTagKind::Platform => game_tags.platforms.push(tag.name),
+        }
+    }
+
+    Ok(game_tags)
+}
+
+fn system_requirements_value(game_input: &GameInput) -> AppResult<Option<serde_json::Value>> {
+    game_input
+        .system_requirements
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| AppError::Other(e.into()))
+}
+
+pub async fn create_games(
+    db: &DbPool,
+    ctx: &AuditContext,
+    game_input: &GameInput,
+) -> AppResult<GameModel> {
     let game = game::ActiveModel {
         name: Set(game_input.name.clone()),
         description: Set(game_input.description.clone()),
+        developer: Set(game_input.developer.clone()),
+        publisher: Set(game_input.publisher.clone()),
+        release_date: Set(game_input.release_date),
+        system_requirements: Set(system_requirements_value(game_input)?),
         ..Default::default()
     };
 
-    let game = game.insert(db).await?;
+    let txn = db.begin().await?;
+    let game = game.insert(&txn).await?;
+    set_game_tags(&txn, game.id, game_input).await?;
+    refresh_search_vector(&txn, game.id).await?;
 
     if let Some(banner_input) = &game_input.banner_type {
         create_game_banner(
-            db,
+            &txn,
             &game,
             banner_input.banner_type.clone(),
             banner_input.value.clone().unwrap_or_default(),
@@ -83,22 +382,39 @@ pub async fn create_games(db: &DbPool, game_input: &GameCreateInput) -> AppResul
         .await?;
     }
 
+    audit::record(
+        &txn,
+        ctx,
+        "game.create",
+        "game",
+        Some(game.id.to_string()),
+        None,
+        Some(&game),
+    )
+    .await?;
+    txn.commit().await?;
+
     Ok(game)
 }
 
-pub async fn create_game_banner(
-    db: &DbPool,
+pub async fn create_game_banner<C: ConnectionTrait>(
+    db: &C,
     game_obj: &GameModel,
     banner_type: game_banner::BannerType,
     value: String,
 ) -> AppResult<GameBannerModel> {
-    let banner = game_banner::ActiveModel {
+    let mut banner = game_banner::ActiveModel {
         game_id: Set(game_obj.id),
-        banner_type: Set(banner_type),
-        value: Set(value),
         ..Default::default()
     };
 
+    if banner_type == game_banner::BannerType::Color {
+        banner.set_color(value);
+    } else {
+        banner.banner_type = Set(banner_type);
+        banner.value = Set(value);
+    }
+
     let banner = banner.insert(db).await?;
 
     Ok(banner)
@@ -106,19 +422,25 @@ pub async fn create_game_banner(
 
 pub async fn update_games(
     db: &DbPool,
+    ctx: &AuditContext,
     id: i32,
-    game_input: &GameCreateInput,
+    game_input: &GameInput,
 ) -> AppResult<GameModel> {
-    let (game, banner) = Game::find_by_id(id)
+    let (previous, banner) = Game::find_by_id(id)
+        .filter(game::Column::DeletedAt.is_null())
         .find_also_related(GameBanner)
         .one(db)
         .await?
         .ok_or(AppError::NotFoundError)?;
 
-    let mut game: game::ActiveModel = game.into();
+    let mut game: game::ActiveModel = previous.clone().into();
 
     game.name = Set(game_input.name.clone());
     game.description = Set(game_input.description.clone());
+    game.developer = Set(game_input.developer.clone());
+    game.publisher = Set(game_input.publisher.clone());
+    game.release_date = Set(game_input.release_date);
+    game.system_requirements = Set(system_requirements_value(game_input)?);
 
     if let Some(banner) = banner {
         let mut banner: game_banner::ActiveModel = banner.into();
@@ -127,51 +449,70 @@ pub async fn update_games(
             banner.banner_type = Set(banner_input.banner_type.clone());
             banner.value = Set(banner_input.value.clone().unwrap_or_default());
         } else {
-            banner.banner_type = Set(game_banner::BannerType::Color);
-            banner.value =
-                Set(helpers::colors::Color::from_text(game_input.name.clone()).to_string());
+            banner
+                .set_color(helpers::colors::Color::from_text(game_input.name.clone()).to_string());
         }
     }
 
-    let game = game.update(db).await?;
+    let txn = db.begin().await?;
+    let game = game.update(&txn).await?;
+    set_game_tags(&txn, game.id, game_input).await?;
+    refresh_search_vector(&txn, game.id).await?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "game.update",
+        "game",
+        Some(game.id.to_string()),
+        Some(&previous),
+        Some(&game),
+    )
+    .await?;
+    txn.commit().await?;
 
     Ok(game)
 }
 
-pub async fn get_game(db: &DbPool, id: i32) -> AppResult<Option<GameGetResponse>> {
-    let game = Game::find_by_id(id)
+pub async fn get_game(db: &DbPool, s3: &S3Client, id: i32) -> AppResult<Option<GameDetails>> {
+    let Some((game, banner)) = Game::find_by_id(id)
+        .filter(game::Column::DeletedAt.is_null())
         .find_also_related(GameBanner)
         .one(db)
         .await?
-        .map_or(None, |(game, banner)| {
-            // Our game model
-            Some(GameGetResponse {
-                id: game.id,
-                name: game.name,
-                description: game.description,
-
-                // Our game banner model
-                banner: banner.map_or(None, |banner| {
-                    Some(GameBannerModel {
-                        id: banner.id,
-                        banner_type: banner.banner_type,
-                        value: banner.value,
-                        game_id: banner.game_id,
-                    })
-                }),
-            })
-        });
+    else {
+        return Ok(None);
+    };
 
-    Ok(game)
+    let media = media::get_game_media(db, game.id).await?;
+    let tags = get_game_tags(db, game.id).await?;
+
+    Ok(Some(GameDetails {
+        // Our game model
+        system_requirements: game.get_system_requirements(),
+        id: game.id,
+        name: game.name,
+        description: game.description,
+        developer: game.developer,
+        publisher: game.publisher,
+        release_date: game.release_date,
+        tags,
+
+        // Our game banner model
+        banner: banner.map(|banner| banner_response(banner, s3)),
+        media: media_set_response(media, s3),
+    }))
 }
 
 pub async fn update_game_banner(
     db: &DbPool,
     s3: &S3Client,
+    ctx: &AuditContext,
     id: i32,
     banner_form: &GameBannerUpload,
 ) -> AppResult<()> {
-    let (game, banner) = Game::find_by_id(id)
+    let (game, previous) = Game::find_by_id(id)
+        .filter(game::Column::DeletedAt.is_null())
         .find_also_related(GameBanner)
         .one(db)
         .await?
@@ -179,7 +520,7 @@ pub async fn update_game_banner(
 
     let mut is_updating = true;
     // If there is no banner for this game, an empty value
-    let mut banner = if let Some(banner) = banner {
+    let mut banner = if let Some(banner) = previous.clone() {
         banner.into()
     } else {
         is_updating = false;
@@ -188,51 +529,457 @@ pub async fn update_game_banner(
 
     match banner_form {
         Either::Left(image_form) => {
-            let ext = image_form.image.content_type.clone();
-
-            let Some(ext) = ext else {
-                return Err(AppError::BadRequest(
-                    "Image content type is required".to_string(),
-                ));
-            };
-
-            let filename = format!("banner.{}", ext);
-            let key_prefix = format!("games/{}/", id);
-
-            let file_path = image_form.image.file.path();
-            let image_url = s3.upload_file(filename, file_path, &key_prefix).await?;
-
-            banner.value = Set(image_url);
+            // Each upload gets its own folder, the previous one is cleaned by the storage GC
+            let key_prefix = format!("{}banner/{}/", game_key_prefix(id), uuid::Uuid::new_v4());
+            let image = media::store_image(
+                s3,
+                image_form.value.file.path(),
+                &helpers::images::BANNER_SIZES,
+                &key_prefix,
+            )
+            .await?;
+
+            banner.banner_type = Set(game_banner::BannerType::Image);
+            banner.value = Set(image.value);
+            banner.accent_color = Set(Some(image.accent_color));
+            banner.variants = Set(Some(image.variants));
         }
         Either::Right(color_form) => {
-            banner.value = Set(color_form.color.clone());
+            banner.set_color(color_form.color.clone());
         }
     }
 
-    if is_updating {
-        banner.update(db).await?;
+    let txn = db.begin().await?;
+    let banner = if is_updating {
+        banner.update(&txn).await?
     } else {
-        banner.insert(db).await?;
-    }
+        banner.insert(&txn).await?
+    };
+
+    audit::record(
+        &txn,
+        ctx,
+        "game.banner.update",
+        "game",
+        Some(id.to_string()),
+        previous.as_ref(),
+        Some(&banner),
+    )
+    .await?;
+    txn.commit().await?;
 
     Ok(())
 }
 
 // Deleting a game banner means resetting the banner value to the default color
-pub async fn delete_game_banner(db: &DbPool, id: i32) -> AppResult<()> {
+pub async fn delete_game_banner(db: &DbPool, ctx: &AuditContext, id: i32) -> AppResult<()> {
     let (game, banner) = Game::find_by_id(id)
+        .filter(game::Column::DeletedAt.is_null())
         .find_also_related(GameBanner)
         .one(db)
         .await?
         .ok_or(AppError::NotFoundError)?;
 
-    if let Some(banner) = banner {
-        let mut banner: game_banner::ActiveModel = banner.into();
+    if let Some(previous) = banner {
+        let mut banner: game_banner::ActiveModel = previous.clone().into();
+
+        banner.set_color(helpers::colors::Color::from_text(game.name).to_string());
 
-        banner.value = Set(helpers::colors::Color::from_text(game.name).to_string());
+        let txn = db.begin().await?;
+        let banner = banner.update(&txn).await?;
 
-        banner.update(db).await?;
+        audit::record(
+            &txn,
+            ctx,
+            "game.banner.delete",
+            "game",
+            Some(id.to_string()),
+            Some(&previous),
+            Some(&banner),
+        )
+        .await?;
+        txn.commit().await?;
     }
 
     Ok(())
 }
+
+/// Soft-delete the game, it can be restored until the end of the restore window
+#[tracing::instrument("Delete game", skip(db, ctx))]
+pub async fn delete_game(db: &DbPool, ctx: &AuditContext, id: i32) -> AppResult<GameModel> {
+    let previous = Game::find_by_id(id)
+        .filter(game::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    let mut game: game::ActiveModel = previous.clone().into();
+    game.deleted_at = Set(Some(OffsetDateTime::now_utc()));
+
+    let txn = db.begin().await?;
+    let game = game.update(&txn).await?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "game.delete",
+        "game",
+        Some(game.id.to_string()),
+        Some(&previous),
+        Some(&game),
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(game)
+}
+
+/// Games deleted more recently than the restore window
+pub async fn get_deleted_games(db: &DbPool, restore_window_days: u32) -> AppResult<Vec<GameModel>> {
+    let games = Game::find()
+        .filter(game::Column::DeletedAt.gt(restore_window_start(restore_window_days)))
+        .order_by_desc(game::Column::DeletedAt)
+        .all(db)
+        .await?;
+
+    Ok(games)
+}
+
+#[tracing::instrument("Restore game", skip(db, ctx))]
+pub async fn restore_game(
+    db: &DbPool,
+    ctx: &AuditContext,
+    restore_window_days: u32,
+    id: i32,
+) -> AppResult<GameModel> {
+    let previous = Game::find_by_id(id)
+        .filter(game::Column::DeletedAt.gt(restore_window_start(restore_window_days)))
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    let mut game: game::ActiveModel = previous.clone().into();
+    game.deleted_at = Set(None);
+
+    let txn = db.begin().await?;
+    let game = game.update(&txn).await?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "game.restore",
+        "game",
+        Some(game.id.to_string()),
+        Some(&previous),
+        Some(&game),
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(game)
+}
+
+/// Games whose restore window is over, waiting to be purged
+pub async fn get_expired_deleted_games(
+    db: &DbPool,
+    restore_window_days: u32,
+) -> AppResult<Vec<GameModel>> {
+    let games = Game::find()
+        .filter(game::Column::DeletedAt.lte(restore_window_start(restore_window_days)))
+        .all(db)
+        .await?;
+
+    Ok(games)
+}
+
+pub async fn get_deleted_game(db: &DbPool, id: i32) -> AppResult<Option<GameModel>> {
+    let game = Game::find_by_id(id)
+        .filter(game::Column::DeletedAt.is_not_null())
+        .one(db)
+        .await?;
+
+    Ok(game)
+}
+
+/// Permanently delete a soft-deleted game and every file stored under its key prefix:
+/// banner, versions and save data. The row is only removed once the storage is clean,
+/// so a failed purge is retried by the next run of the cleanup job.
+///
+/// The bucket is versioned: every version and delete marker is removed, deleting the current
+/// files only would keep their content behind delete markers.
+#[tracing::instrument("Purge game", skip(db, s3, ctx, game, job), fields(game_id = game.id))]
+pub async fn purge_game(
+    db: &DbPool,
+    s3: &S3Client,
+    ctx: &AuditContext,
+    game: GameModel,
+    job: &JobHandle,
+) -> AppResult<()> {
+    let versions = s3.list_object_versions(&game_key_prefix(game.id)).await?;
+    job.set_total(versions.len() as u64);
+
+    for version in versions {
+        s3.delete_object_version(&version).await?;
+        job.advance();
+    }
+
+    let txn = db.begin().await?;
+    audit::record(
+        &txn,
+        ctx,
+        "game.purge",
+        "game",
+        Some(game.id.to_string()),
+        Some(&game),
+        None,
+    )
+    .await?;
+
+    // The banner and the grants are removed by the foreign keys
+    game.delete(&txn).await?;
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Publishers and admins can access every game, the users only the games granted to them, e.g.
+/// by their invitation. The deleted games and the games of the others are reported as not found.
+///
+/// A grant only gives read and save access, the writes are reserved to the publishers by the routes.
+pub async fn ensure_game_access(db: &DbPool, user: &UserModel, game_id: i32) -> AppResult<()> {
+    Game::find_by_id(game_id)
+        .filter(game::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    if user.role.includes(&UserRole::Publisher) {
+        return Ok(());
+    }
+
+    GameGrant::find_by_id((user.id, game_id))
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    Ok(())
+}
+
+/// Prefix of the files of all the games
+pub const GAMES_KEY_PREFIX: &str = "games/";
+
+/// Every file of a game is stored under this prefix
+pub fn game_key_prefix(id: i32) -> String {
+    format!("{}{}/", GAMES_KEY_PREFIX, id)
+}
+
+/// Prefix of the uploaded versions of a game
+pub fn version_key_prefix(id: i32) -> String {
+    format!("{}versions/", game_key_prefix(id))
+}
+
+/// Prefix of the saves of a game, one file per user
+pub fn save_key_prefix(id: i32) -> String {
+    format!("{}saves/", game_key_prefix(id))
+}
+
+fn restore_window_start(restore_window_days: u32) -> OffsetDateTime {
+    OffsetDateTime::now_utc() - time::Duration::days(restore_window_days.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, EntityTrait, MockDatabase, QueryFilter, Value};
+    use serde_json::json;
+    use time::OffsetDateTime;
+
+    use super::{ensure_game_access, search_condition, search_keyset};
+    use crate::{
+        core::errors::AppError,
+        entities::{
+            game::Model as GameModel,
+            game_grant::Model as GameGrantModel,
+            prelude::Game,
+            user::{Model as UserModel, UserRole},
+        },
+        models::{
+            pagination::{Cursor, Pagination},
+            search::Search,
+        },
+        repositories::pagination,
+    };
+
+    fn user(role: UserRole) -> UserModel {
+        let now = OffsetDateTime::now_utc();
+
+        UserModel {
+            id: 1,
+            email: "player@example.com".to_owned(),
+            password: "hash".to_owned(),
+            role,
+            totp_secret: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            sessions_valid_after: None,
+            oidc_subject: None,
+            email_verified_at: Some(now),
+            disabled_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn game() -> GameModel {
+        let now = OffsetDateTime::now_utc();
+
+        GameModel {
+            id: 1,
+            name: "Game".to_owned(),
+            description: None,
+            developer: None,
+            publisher: None,
+            release_date: None,
+            system_requirements: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn deleted_games_are_not_found_for_publishers() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<GameModel>::new()])
+            .into_connection();
+
+        let result = ensure_game_access(&db, &user(UserRole::Admin), 1).await;
+        assert!(matches!(result, Err(AppError::NotFoundError)));
+
+        let log = db.into_transaction_log();
+        assert!(log[0].statements()[0]
+            .sql
+            .contains(r#""game"."deleted_at" IS NULL"#));
+    }
+
+    #[actix_rt::test]
+    async fn users_need_a_grant() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![game()]])
+            .append_query_results([Vec::<GameGrantModel>::new()])
+            .append_query_results([vec![game()]])
+            .append_query_results([vec![GameGrantModel {
+                user_id: 1,
+                game_id: 1,
+                created_at: OffsetDateTime::now_utc(),
+            }]])
+            .into_connection();
+
+        let result = ensure_game_access(&db, &user(UserRole::User), 1).await;
+        assert!(matches!(result, Err(AppError::NotFoundError)));
+
+        assert!(ensure_game_access(&db, &user(UserRole::User), 1)
+            .await
+            .is_ok());
+    }
+
+    const RANK: &str =
+        r#"ts_rank("game"."search_vector", to_tsquery('simple', game_sync_unaccent(?)))"#;
+
+    /// SQL of the statement, with the placeholders replaced by `?` whatever their number
+    fn sql(db_log: &[sea_orm::Transaction], index: usize) -> String {
+        let sql = &db_log[index].statements()[0].sql;
+        let mut normalized = String::with_capacity(sql.len());
+        let mut chars = sql.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '$' {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+                normalized.push('?');
+            } else {
+                normalized.push(c);
+            }
+        }
+
+        normalized
+    }
+
+    fn values(db_log: &[sea_orm::Transaction], index: usize) -> Vec<Value> {
+        db_log[index].statements()[0]
+            .values
+            .clone()
+            .map(|values| values.0)
+            .unwrap_or_default()
+    }
+
+    /// Page after a cursor of the relevance sort of `search`
+    async fn relevance_page(
+        search: &Search,
+        cursor_search: &Search,
+    ) -> (Result<(), AppError>, Vec<sea_orm::Transaction>) {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<GameModel>::new()])
+            .into_connection();
+
+        let cursor = Cursor {
+            sort: format!("{}.desc", search_keyset(cursor_search).name),
+            key: "0.0607927".to_owned(),
+            id: 42,
+        };
+        let pagination: Pagination =
+            serde_json::from_value(json!({ "cursor": cursor.encode().unwrap() })).unwrap();
+
+        let result = pagination::paginate(
+            &db,
+            Game::find().filter(search_condition(search)),
+            &pagination,
+            search_keyset(search),
+        )
+        .await
+        .map(|_| ());
+
+        (result, db.into_transaction_log())
+    }
+
+    #[actix_rt::test]
+    async fn searches_are_unaccented_and_ranked() {
+        let search: Search =
+            serde_json::from_value(json!({ "search": "Pokémon Émeraude" })).unwrap();
+        let (result, log) = relevance_page(&search, &search).await;
+        assert!(result.is_ok());
+
+        let sql = sql(&log, 0);
+        assert!(sql
+            .contains(r#""game"."search_vector" @@ to_tsquery('simple', game_sync_unaccent(?))"#));
+        assert!(sql.contains(&format!(r#"ORDER BY {RANK} DESC, "game"."id" DESC"#)));
+
+        // The accents are removed by the database, from the query as from the games
+        let values = values(&log, 0);
+        assert!(values.contains(&Value::from("pokémon:* & émeraude:*")));
+    }
+
+    #[actix_rt::test]
+    async fn relevance_cursors_continue_after_their_rank() {
+        let search: Search = serde_json::from_value(json!({ "search": "zelda" })).unwrap();
+        let (result, log) = relevance_page(&search, &search).await;
+        assert!(result.is_ok());
+
+        let sql = sql(&log, 0);
+        assert!(sql.contains(&format!(
+            r#"({RANK} < ? OR ({RANK} = ? AND "game"."id" < ?))"#
+        )));
+
+        let values = values(&log, 0);
+        assert!(values.contains(&Value::from(0.0607927f32)));
+        assert!(values.contains(&Value::from(42i64)));
+    }
+
+    #[actix_rt::test]
+    async fn relevance_cursors_are_tied_to_their_query() {
+        let search: Search = serde_json::from_value(json!({ "search": "zelda" })).unwrap();
+        let other: Search = serde_json::from_value(json!({ "search": "mario" })).unwrap();
+        let (result, log) = relevance_page(&search, &other).await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert!(log.is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+use rand::Rng;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, EntityTrait, ModelTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use time::{Duration, OffsetDateTime};
+
+use crate::entities::{game, game_grant, invitation, prelude::*, user};
+use crate::{
+    core::{
+        audit::AuditContext,
+        database::DbPool,
+        errors::{AppError, AppResult},
+    },
+    entities::{invitation::Model as InvitationModel, user::Model as UserModel},
+    helpers::hashing,
+    models::{invitation::InvitationCreateInput, user::UserCreateInput},
+    repositories::{self, audit},
+};
+
+pub async fn get_invitations(db: &DbPool) -> AppResult<Vec<InvitationModel>> {
+    let invitations = Invitation::find()
+        .order_by_desc(invitation::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(invitations)
+}
+
+/// Create an invitation and return it along with its code, only the hash of the code is stored
+#[tracing::instrument("Create invitation", skip(db, ctx, input))]
+pub async fn create_invitation(
+    db: &DbPool,
+    ctx: &AuditContext,
+    input: &InvitationCreateInput,
+) -> AppResult<(InvitationModel, String)> {
+    let mut game_ids = input.game_ids.clone();
+    game_ids.sort_unstable();
+    game_ids.dedup();
+
+    let games_count = Game::find()
+        .filter(game::Column::Id.is_in(game_ids.clone()))
+        .count(db)
+        .await?;
+
+    if games_count != game_ids.len() as u64 {
+        return Err(AppError::BadRequest("Unknown game".to_string()));
+    }
+
+    let expires_at = match input.expires_in {
+        Some(hours) => Some(
+            OffsetDateTime::now_utc()
+                .checked_add(Duration::hours(hours))
+                .ok_or_else(|| AppError::BadRequest("Invalid expiration".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let code = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+
+    let invitation = invitation::ActiveModel {
+        code_hash: Set(hashing::hash_token(&code)),
+        role: Set(input.role.clone()),
+        game_ids: Set(serde_json::json!(game_ids)),
+        max_uses: Set(input.max_uses),
+        expires_at: Set(expires_at),
+        created_by: Set(ctx.actor_id),
+        ..Default::default()
+    };
+
+    let txn = db.begin().await?;
+
+    let invitation = invitation.insert(&txn).await?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "invitation.create",
+        "invitation",
+        Some(invitation.id.to_string()),
+        None,
+        Some(&invitation),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok((invitation, code))
+}
+
+#[tracing::instrument("Delete invitation", skip(db, ctx))]
+pub async fn delete_invitation(db: &DbPool, ctx: &AuditContext, id: i32) -> AppResult<()> {
+    let txn = db.begin().await?;
+
+    let invitation = Invitation::find_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "invitation.delete",
+        "invitation",
+        Some(invitation.id.to_string()),
+        Some(&invitation),
+        None,
+    )
+    .await?;
+
+    invitation.delete(&txn).await?;
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Register a user with an invitation code, giving it the role and the games of the invitation
+#[tracing::instrument("Register user with invitation", skip(db, input, code))]
+pub async fn register_with_invitation(
+    db: &DbPool,
+    input: &UserCreateInput,
+    code: &str,
+) -> AppResult<UserModel> {
+    if repositories::user::get_user_from_email(db, &input.email)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::AlreadyExists("User already exists".to_string()));
+    }
+
+    let txn = db.begin().await?;
+
+    let invitation = Invitation::find()
+        .filter(invitation::Column::CodeHash.eq(hashing::hash_token(code)))
+        .one(&txn)
+        .await?
+        .ok_or(AppError::InvalidInvitation)?;
+
+    // Increment the usage counter only if the invitation is still valid, so concurrent
+    // registrations can't exceed the limit
+    let result = Invitation::update_many()
+        .col_expr(
+            invitation::Column::Uses,
+            Expr::col(invitation::Column::Uses).add(1),
+        )
+        .filter(invitation::Column::Id.eq(invitation.id))
+        .filter(
+            Condition::any()
+                .add(invitation::Column::MaxUses.is_null())
+                .add(
+                    Expr::col(invitation::Column::Uses).lt(Expr::col(invitation::Column::MaxUses)),
+                ),
+        )
+        .filter(
+            Condition::any()
+                .add(invitation::Column::ExpiresAt.is_null())
+                .add(invitation::Column::ExpiresAt.gt(OffsetDateTime::now_utc())),
+        )
+        .exec(&txn)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::InvalidInvitation);
+    }
+
+    let user = user::ActiveModel {
+        email: Set(input.email.clone()),
+        password: Set(input.password.clone()),
+        role: Set(invitation.role.clone()),
+        ..Default::default()
+    };
+
+    let user = user.insert(&txn).await?;
+
+    let game_ids = invitation.get_game_ids();
+
+    if !game_ids.is_empty() {
+        let grants = game_ids.into_iter().map(|game_id| game_grant::ActiveModel {
+            user_id: Set(user.id),
+            game_id: Set(game_id),
+            ..Default::default()
+        });
+
+        GameGrant::insert_many(grants).exec(&txn).await?;
+    }
+
+    txn.commit().await?;
+
+    tracing::info!(
+        "User {} registered with invitation {}",
+        user.id,
+        invitation.id
+    );
+
+    Ok(user)
+}
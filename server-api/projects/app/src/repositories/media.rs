@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use actix_web::web;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+use crate::core::audit::AuditContext;
+use crate::core::database::DbPool;
+use crate::core::errors::{AppError, AppResult};
+use crate::core::s3::S3Client;
+use crate::entities::game_media::{MediaKind, Model as GameMediaModel};
+use crate::entities::{game, game_media, prelude::*};
+use crate::helpers::images::{self, ImageSize};
+use crate::models::games::StoredVariant;
+use crate::repositories::{audit, games::game_key_prefix};
+
+/// Maximum number of screenshots in the gallery of a game
+pub const MAX_SCREENSHOTS: u64 = 20;
+
+/// An uploaded image, processed and stored with all its variants
+pub struct StoredImage {
+    /// Key of the largest WebP variant, used as the reference image
+    pub value: String,
+    pub accent_color: String,
+    pub variants: serde_json::Value,
+}
+
+/// Decode an uploaded image, generate its variants and upload them under `key_prefix`.
+/// The declared content type can't be trusted, the image is decoded to check it.
+pub async fn store_image(
+    s3: &S3Client,
+    file_path: &Path,
+    sizes: &'static [ImageSize],
+    key_prefix: &str,
+) -> AppResult<StoredImage> {
+    let bytes = tokio::fs::read(file_path).await?;
+    let processed = web::block(move || images::process_image(&bytes, sizes))
+        .await
+        .map_err(|_| AppError::InternalError)??;
+
+    let mut variants = Vec::with_capacity(processed.variants.len());
+    for variant in processed.variants {
+        let key = format!(
+            "{}{}.{}",
+            key_prefix,
+            variant.size,
+            variant.format.extension()
+        );
+        s3.upload_bytes(&key, &variant.bytes, variant.format.content_type())
+            .await?;
+
+        variants.push(StoredVariant {
+            size: variant.size.to_string(),
+            format: variant.format,
+            width: variant.width,
+            height: variant.height,
+            key,
+        });
+    }
+
+    let value = variants
+        .first()
+        .map(|variant| variant.key.clone())
+        .ok_or(AppError::InternalError)?;
+
+    Ok(StoredImage {
+        value,
+        accent_color: processed.accent_color.to_hex(),
+        variants: serde_json::to_value(&variants).map_err(|e| AppError::Other(e.into()))?,
+    })
+}
+
+/// All the media of a game, galleries in their display order
+pub async fn get_game_media(db: &DbPool, game_id: i32) -> AppResult<Vec<GameMediaModel>> {
+    let media = GameMedia::find()
+        .filter(game_media::Column::GameId.eq(game_id))
+        .order_by_asc(game_media::Column::Kind)
+        .order_by_asc(game_media::Column::Position)
+        .all(db)
+        .await?;
+
+    Ok(media)
+}
+
+async fn ensure_game_exists(db: &DbPool, game_id: i32) -> AppResult<()> {
+    Game::find_by_id(game_id)
+        .filter(game::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    Ok(())
+}
+
+/// Upload an image media. Screenshots are appended to the gallery, the other kinds replace the
+/// current media of their slot. Replaced files are cleaned by the storage GC.
+pub async fn upload_media(
+    db: &DbPool,
+    s3: &S3Client,
+    ctx: &AuditContext,
+    game_id: i32,
+    kind: MediaKind,
+    file_path: &Path,
+) -> AppResult<GameMediaModel> {
+    let Some(sizes) = game_media::image_sizes(kind) else {
+        return Err(AppError::BadRequest(
+            "This kind of media can't be uploaded".to_string(),
+        ));
+    };
+
+    ensure_game_exists(db, game_id).await?;
+
+    if kind.is_gallery() {
+        let count = GameMedia::find()
+            .filter(game_media::Column::GameId.eq(game_id))
+            .filter(game_media::Column::Kind.eq(kind))
+            .count(db)
+            .await?;
+
+        if count >= MAX_SCREENSHOTS {
+            return Err(AppError::BadRequest(format!(
+                "A game can't have more than {} screenshots",
+                MAX_SCREENSHOTS
+            )));
+        }
+    }
+
+    // Each upload gets its own folder
+    let key_prefix = format!(
+        "{}media/{}/",
+        game_key_prefix(game_id),
+        uuid::Uuid::new_v4()
+    );
+    let image = store_image(s3, file_path, sizes, &key_prefix).await?;
+
+    let media = game_media::ActiveModel {
+        game_id: Set(game_id),
+        kind: Set(kind),
+        value: Set(image.value),
+        accent_color: Set(Some(image.accent_color)),
+        variants: Set(Some(image.variants)),
+        ..Default::default()
+    };
+
+    replace_media(db, ctx, media).await
+}
+
+/// Set the trailer of a game, replacing the current one
+pub async fn set_trailer(
+    db: &DbPool,
+    ctx: &AuditContext,
+    game_id: i32,
+    url: String,
+) -> AppResult<GameMediaModel> {
+    ensure_game_exists(db, game_id).await?;
+
+    let media = game_media::ActiveModel {
+        game_id: Set(game_id),
+        kind: Set(MediaKind::Trailer),
+        value: Set(url),
+        ..Default::default()
+    };
+
+    replace_media(db, ctx, media).await
+}
+
+/// Insert a media at the end of its gallery, or in place of the current media of its slot
+async fn replace_media(
+    db: &DbPool,
+    ctx: &AuditContext,
+    mut media: game_media::ActiveModel,
+) -> AppResult<GameMediaModel> {
+    let game_id = media.game_id.clone().unwrap();
+    let kind = media.kind.clone().unwrap();
+
+    let txn = db.begin().await?;
+
+    let previous = if kind.is_gallery() {
+        let last_position: Option<i32> = GameMedia::find()
+            .select_only()
+            .column_as(game_media::Column::Position.max(), "position")
+            .filter(game_media::Column::GameId.eq(game_id))
+            .filter(game_media::Column::Kind.eq(kind))
+            .into_tuple()
+            .one(&txn)
+            .await?
+            .flatten();
+
+        media.position = Set(last_position.map_or(0, |position| position + 1));
+        None
+    } else {
+        let previous = GameMedia::find()
+            .filter(game_media::Column::GameId.eq(game_id))
+            .filter(game_media::Column::Kind.eq(kind))
+            .one(&txn)
+            .await?;
+
+        if let Some(previous) = &previous {
+            previous.clone().delete(&txn).await?;
+        }
+
+        media.position = Set(0);
+        previous
+    };
+
+    let media = media.insert(&txn).await?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "game.media.update",
+        "game",
+        Some(game_id.to_string()),
+        previous.as_ref(),
+        Some(&media),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(media)
+}
+
+/// Reorder the screenshots gallery, `ids` must contain every screenshot of the game
+pub async fn reorder_screenshots(
+    db: &DbPool,
+    ctx: &AuditContext,
+    game_id: i32,
+    ids: &[i32],
+) -> AppResult<Vec<GameMediaModel>> {
+    ensure_game_exists(db, game_id).await?;
+
+    let txn = db.begin().await?;
+
+    let screenshots = GameMedia::find()
+        .filter(game_media::Column::GameId.eq(game_id))
+        .filter(game_media::Column::Kind.eq(MediaKind::Screenshot))
+        .order_by_asc(game_media::Column::Position)
+        .all(&txn)
+        .await?;
+
+    let current: HashSet<i32> = screenshots.iter().map(|media| media.id).collect();
+    let requested: HashSet<i32> = ids.iter().copied().collect();
+    if requested.len() != ids.len() || requested != current {
+        return Err(AppError::BadRequest(
+            "The new order must contain every screenshot of the game exactly once".to_string(),
+        ));
+    }
+
+    // Positions are unique, so they are first moved out of the way with negative values
+    for (index, id) in ids.iter().enumerate() {
+        GameMedia::update_many()
+            .col_expr(
+                game_media::Column::Position,
+                Expr::value(-(index as i32) - 1),
+            )
+            .filter(game_media::Column::Id.eq(*id))
+            .exec(&txn)
+            .await?;
+    }
+
+    GameMedia::update_many()
+        .col_expr(
+            game_media::Column::Position,
+            Expr::col(game_media::Column::Position).mul(-1).sub(1),
+        )
+        .filter(game_media::Column::GameId.eq(game_id))
+        .filter(game_media::Column::Kind.eq(MediaKind::Screenshot))
+        .exec(&txn)
+        .await?;
+
+    let reordered = GameMedia::find()
+        .filter(game_media::Column::GameId.eq(game_id))
+        .filter(game_media::Column::Kind.eq(MediaKind::Screenshot))
+        .order_by_asc(game_media::Column::Position)
+        .all(&txn)
+        .await?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "game.media.reorder",
+        "game",
+        Some(game_id.to_string()),
+        Some(&screenshots),
+        Some(&reordered),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(reordered)
+}
+
+/// Delete a media of a game, its files are cleaned by the storage GC
+pub async fn delete_media(
+    db: &DbPool,
+    ctx: &AuditContext,
+    game_id: i32,
+    media_id: i32,
+) -> AppResult<()> {
+    ensure_game_exists(db, game_id).await?;
+
+    let media = GameMedia::find_by_id(media_id)
+        .filter(game_media::Column::GameId.eq(game_id))
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    let txn = db.begin().await?;
+    media.clone().delete(&txn).await?;
+
+    audit::record(
+        &txn,
+        ctx,
+        "game.media.delete",
+        "game",
+        Some(game_id.to_string()),
+        Some(&media),
+        None,
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(())
+}
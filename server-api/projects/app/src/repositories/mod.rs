@@ -1,3 +1,12 @@
 pub mod app;
+pub mod audit;
+pub mod export;
 pub mod games;
+pub mod invitations;
+pub mod media;
+pub mod pagination;
+pub mod passkeys;
+pub mod storage;
+pub mod two_factor;
 pub mod user;
+pub mod user_tokens;
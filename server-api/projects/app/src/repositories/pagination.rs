@@ -0,0 +1,235 @@
+use sea_orm::{
+    sea_query::{Alias, Expr, SimpleExpr},
+    ColumnTrait, Condition, EntityTrait, ModelTrait, Order, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Select, Value,
+};
+
+use time::{macros::format_description, Date, OffsetDateTime};
+
+use crate::core::database::DbPool;
+use crate::core::errors::{AppError, AppResult};
+use crate::models::pagination::{Cursor, Paginated, Pagination, PaginationMeta};
+
+/// Type of a sort key, the key of a cursor is parsed back to it before reaching the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    BigInt,
+    Real,
+    Date,
+    Timestamptz,
+    Text,
+}
+
+impl KeyType {
+    /// Read a key formatted as text by the database, `None` when it isn't valid
+    fn parse(&self, key: &str) -> Option<Value> {
+        match self {
+            KeyType::BigInt => key.parse::<i64>().ok().map(Value::from),
+            KeyType::Real => key.parse::<f32>().ok().map(Value::from),
+            KeyType::Date => Date::parse(key, format_description!("[year]-[month]-[day]"))
+                .ok()
+                .map(Value::from),
+            KeyType::Timestamptz => OffsetDateTime::parse(
+                key,
+                format_description!(
+                    "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]][offset_hour sign:mandatory][optional [:[offset_minute]]]"
+                ),
+            )
+            .ok()
+            .map(Value::from),
+            // The database refuses the text values containing a NUL character
+            KeyType::Text => (!key.contains('\0')).then(|| Value::from(key)),
+        }
+    }
+}
+
+/// Order of a listing: by a sort key, then by ID to break ties, both in the same direction.
+/// The same order is used to paginate by page and by cursor.
+pub struct Keyset<C: ColumnTrait> {
+    /// Name of the sort, a cursor can only be used with the sort it was created for
+    pub name: String,
+    /// Sort key, it must not be null
+    pub key: SimpleExpr,
+    /// Type of the key, used to read it back from the cursor
+    pub key_type: KeyType,
+    pub id: C,
+    pub order: Order,
+}
+
+impl<C: ColumnTrait> Keyset<C> {
+    /// Sort by a column which isn't nullable
+    pub fn column(name: &str, column: C, key_type: KeyType, id: C, order: Order) -> Self {
+        Self {
+            name: name.to_owned(),
+            key: Expr::col((column.entity_name(), column)).into(),
+            key_type,
+            id,
+            order,
+        }
+    }
+
+    /// Name stored in the cursors, with the direction of the sort
+    fn sort_name(&self) -> String {
+        match self.order {
+            Order::Desc => format!("{}.desc", self.name),
+            _ => format!("{}.asc", self.name),
+        }
+    }
+
+    /// Condition selecting the items after the cursor. The key of the cursor is checked here, a
+    /// tampered one would make the query fail.
+    fn after(&self, cursor: &Cursor) -> AppResult<Condition> {
+        let key = Expr::expr(self.key.clone());
+        let value = self
+            .key_type
+            .parse(&cursor.key)
+            .map(Expr::val)
+            .ok_or(AppError::BadRequest("Invalid cursor".to_string()))?;
+        let id = Expr::col((self.id.entity_name(), self.id));
+
+        Ok(match self.order {
+            Order::Desc => Condition::any()
+                .add(key.clone().lt(value.clone()))
+                .add(Condition::all().add(key.eq(value)).add(id.lt(cursor.id))),
+            _ => Condition::any()
+                .add(key.clone().gt(value.clone()))
+                .add(Condition::all().add(key.eq(value)).add(id.gt(cursor.id))),
+        })
+    }
+}
+
+/// Paginate a query by page, or by cursor when requested. `query` must not be ordered.
+pub async fn paginate<E>(
+    db: &DbPool,
+    query: Select<E>,
+    pagination: &Pagination,
+    keyset: Keyset<E::Column>,
+) -> AppResult<Paginated<E::Model>>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+{
+    let per_page = pagination.get_per_page();
+    let ordered = query
+        .clone()
+        .order_by(keyset.key.clone(), keyset.order.clone())
+        .order_by(keyset.id, keyset.order.clone());
+
+    let Some(cursor) = pagination.get_cursor()? else {
+        // Pages start at 1 for the clients, but at 0 for the paginator
+        let page = pagination.get_page();
+        let paginator = ordered.paginate(db, per_page);
+        let counts = paginator.num_items_and_pages().await?;
+        let data = paginator.fetch_page(page - 1).await?;
+
+        return Ok(Paginated {
+            data,
+            meta: PaginationMeta {
+                current_page: Some(page),
+                total_pages: Some(counts.number_of_pages),
+                total_items: Some(counts.number_of_items),
+                per_page,
+                next_cursor: None,
+            },
+        });
+    };
+
+    let total_items = if pagination.with_total() {
+        Some(query.clone().count(db).await?)
+    } else {
+        None
+    };
+
+    let mut page_query = ordered;
+    if let Some(cursor) = cursor {
+        if cursor.sort != keyset.sort_name() {
+            return Err(AppError::BadRequest(
+                "The cursor was created for another sort".to_string(),
+            ));
+        }
+
+        page_query = page_query.filter(keyset.after(&cursor)?);
+    }
+
+    // Fetch one more item to know if there is a next page
+    let mut data = page_query.limit(per_page + 1).all(db).await?;
+    let has_next = data.len() as u64 > per_page;
+    data.truncate(per_page as usize);
+
+    let next_cursor = match data.last() {
+        Some(last) if has_next => Some(cursor_of(db, query, &keyset, last).await?.encode()?),
+        _ => None,
+    };
+
+    Ok(Paginated {
+        data,
+        meta: PaginationMeta {
+            current_page: None,
+            total_pages: None,
+            total_items,
+            per_page,
+            next_cursor,
+        },
+    })
+}
+
+/// Read the sort key of an item back from the database, as text so it round-trips exactly
+async fn cursor_of<E: EntityTrait>(
+    db: &DbPool,
+    query: Select<E>,
+    keyset: &Keyset<E::Column>,
+    item: &E::Model,
+) -> AppResult<Cursor> {
+    let id = match item.get(keyset.id) {
+        Value::Int(Some(id)) => i64::from(id),
+        Value::BigInt(Some(id)) => id,
+        _ => return Err(AppError::InternalError),
+    };
+
+    let key: Option<String> = query
+        .select_only()
+        .column_as(keyset.key.clone().cast_as(Alias::new("text")), "key")
+        .filter(Expr::col((keyset.id.entity_name(), keyset.id)).eq(id))
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    Ok(Cursor {
+        sort: keyset.sort_name(),
+        key: key.ok_or(AppError::InternalError)?,
+        id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Value;
+
+    use super::KeyType;
+
+    #[test]
+    fn keys_formatted_by_the_database_are_parsed() {
+        assert_eq!(KeyType::BigInt.parse("42"), Some(Value::from(42i64)));
+        assert_eq!(
+            KeyType::Real.parse("0.0607927"),
+            Some(Value::from(0.0607927f32))
+        );
+        assert!(KeyType::Date.parse("0001-01-01").is_some());
+        assert!(KeyType::Timestamptz
+            .parse("2023-12-14 10:15:33.123456+00")
+            .is_some());
+        assert!(KeyType::Timestamptz
+            .parse("2023-12-14 10:15:33+05:30")
+            .is_some());
+        assert_eq!(KeyType::Text.parse("Celeste"), Some(Value::from("Celeste")));
+    }
+
+    #[test]
+    fn tampered_keys_are_rejected() {
+        assert_eq!(KeyType::BigInt.parse("1 OR 1=1"), None);
+        assert_eq!(KeyType::Real.parse("high"), None);
+        assert_eq!(KeyType::Date.parse("2023-13-01"), None);
+        assert_eq!(KeyType::Timestamptz.parse("yesterday"), None);
+        assert_eq!(KeyType::Text.parse("Celeste\0"), None);
+    }
+}
@@ -0,0 +1,93 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, Set};
+use time::OffsetDateTime;
+use webauthn_rs::prelude::{AuthenticationResult, Passkey as WebauthnPasskey};
+
+use crate::entities::{passkey, prelude::*};
+use crate::{
+    core::{
+        database::DbPool,
+        errors::{AppError, AppResult},
+    },
+    entities::passkey::Model as PasskeyModel,
+};
+
+pub async fn get_user_passkeys(db: &DbPool, user_id: i32) -> AppResult<Vec<PasskeyModel>> {
+    let passkeys = Passkey::find()
+        .filter(passkey::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+
+    Ok(passkeys)
+}
+
+/// Deserialize the WebAuthn credentials of the stored passkeys
+pub fn to_credentials(passkeys: &[PasskeyModel]) -> AppResult<Vec<WebauthnPasskey>> {
+    passkeys
+        .iter()
+        .map(|passkey| {
+            serde_json::from_value(passkey.credential.clone())
+                .map_err(|e| AppError::Other(e.into()))
+        })
+        .collect()
+}
+
+#[tracing::instrument("Create passkey", skip(db, credential))]
+pub async fn create_passkey(
+    db: &DbPool,
+    user_id: i32,
+    name: &str,
+    credential: &WebauthnPasskey,
+) -> AppResult<PasskeyModel> {
+    let passkey = passkey::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(name.to_owned()),
+        credential: Set(serde_json::to_value(credential).map_err(|e| AppError::Other(e.into()))?),
+        ..Default::default()
+    };
+
+    let passkey = passkey.insert(db).await?;
+
+    Ok(passkey)
+}
+
+/// Update the stored credential (signature counter, backup state) after a successful login
+#[tracing::instrument("Update passkey usage", skip(db, passkeys, result))]
+pub async fn update_passkey_usage(
+    db: &DbPool,
+    passkeys: Vec<PasskeyModel>,
+    result: &AuthenticationResult,
+) -> AppResult<()> {
+    for passkey in passkeys {
+        let mut credential: WebauthnPasskey = serde_json::from_value(passkey.credential.clone())
+            .map_err(|e| AppError::Other(e.into()))?;
+
+        if credential.cred_id() != result.cred_id() {
+            continue;
+        }
+
+        credential.update_credential(result);
+
+        let mut passkey: passkey::ActiveModel = passkey.into();
+        passkey.credential =
+            Set(serde_json::to_value(&credential).map_err(|e| AppError::Other(e.into()))?);
+        passkey.last_used_at = Set(Some(OffsetDateTime::now_utc()));
+        passkey.update(db).await?;
+
+        break;
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument("Delete passkey", skip(db))]
+pub async fn delete_passkey(db: &DbPool, user_id: i32, id: i32) -> AppResult<()> {
+    let passkey = Passkey::find_by_id(id)
+        .filter(passkey::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFoundError)?;
+
+    passkey.delete(db).await?;
+
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QuerySelect, Set,
+};
+use time::{Duration, OffsetDateTime};
+
+use crate::entities::{file, prelude::*};
+use crate::{
+    core::{
+        database::DbPool,
+        errors::AppResult,
+        s3::{S3Client, StoredObject, UPLOAD_URL_LIFETIME},
+    },
+    models::storage::{GcReport, PendingUpload},
+    repositories::games::GAMES_KEY_PREFIX,
+};
+
+/// Folders of a game which only hold banners and media, the only files the garbage collector
+/// deletes. The other files, e.g. the versions, aren't all tracked by the database yet.
+const GC_OWNED_FOLDERS: [&str; 2] = ["banner", "media"];
+
+/// Record an upload whose presigned URL is issued, it stays pending until the garbage collector
+/// finds the file in the bucket. A file uploaded again goes back to pending.
+pub async fn record_pending_upload(db: &DbPool, path: &str) -> AppResult<file::Model> {
+    let existing = File::find()
+        .filter(file::Column::Path.eq(path))
+        .one(db)
+        .await?;
+
+    let file = match existing {
+        Some(file) => {
+            let mut file: file::ActiveModel = file.into();
+            file.created_at = Set(OffsetDateTime::now_utc());
+            file.confirmed_at = Set(None);
+            file.update(db).await?
+        }
+        None => {
+            file::ActiveModel {
+                path: Set(path.to_owned()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?
+        }
+    };
+
+    Ok(file)
+}
+
+/// Mark the finished uploads of a garbage collector run as confirmed, and forget the aborted ones
+pub async fn resolve_pending_uploads(db: &DbPool, report: &GcReport) -> AppResult<()> {
+    let ids =
+        |uploads: &[PendingUpload]| uploads.iter().map(|upload| upload.id).collect::<Vec<_>>();
+    // Only the uploads still stale, one may have been issued again in between
+    let limit = pending_upload_limit(0);
+
+    if !report.finished_uploads.is_empty() {
+        File::update_many()
+            .col_expr(
+                file::Column::ConfirmedAt,
+                Expr::value(OffsetDateTime::now_utc()),
+            )
+            .filter(file::Column::Id.is_in(ids(&report.finished_uploads)))
+            .filter(file::Column::ConfirmedAt.is_null())
+            .filter(file::Column::CreatedAt.lt(limit))
+            .exec(db)
+            .await?;
+    }
+
+    if !report.aborted_uploads.is_empty() {
+        File::delete_many()
+            .filter(file::Column::Id.is_in(ids(&report.aborted_uploads)))
+            .filter(file::Column::ConfirmedAt.is_null())
+            .filter(file::Column::CreatedAt.lt(limit))
+            .exec(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Pending uploads issued before this time can't finish anymore, their presigned URL expired
+fn pending_upload_limit(grace_period_hours: u32) -> OffsetDateTime {
+    OffsetDateTime::now_utc() - Duration::hours(grace_period_hours.into()).max(UPLOAD_URL_LIFETIME)
+}
+
+/// Whether the garbage collector manages the key, `games/{id}/banner/…` or `games/{id}/media/…`
+fn is_gc_owned(key: &str) -> bool {
+    let Some(rest) = key.strip_prefix(GAMES_KEY_PREFIX) else {
+        return false;
+    };
+
+    let mut segments = rest.splitn(3, '/');
+    let id = segments.next().unwrap_or_default();
+    let folder = segments.next().unwrap_or_default();
+
+    id.parse::<i32>().is_ok() && GC_OWNED_FOLDERS.contains(&folder) && segments.next().is_some()
+}
+
+/// Keys of every file referenced by the database: banners, media and their variants, and files.
+/// Soft-deleted games still reference their files until they are purged.
+pub async fn get_referenced_keys(db: &DbPool) -> AppResult<HashSet<String>> {
+    let banners = GameBanner::find().all(db).await?;
+    let banners = banners.into_iter().flat_map(|banner| {
+        let variants = banner.get_variants();
+        std::iter::once(banner.value).chain(variants.into_iter().map(|variant| variant.key))
+    });
+
+    // Trailers are external URLs, they can't match a key of the bucket
+    let media = GameMedia::find().all(db).await?;
+    let media = media.into_iter().flat_map(|media| {
+        let variants = media.get_variants();
+        std::iter::once(media.value).chain(variants.into_iter().map(|variant| variant.key))
+    });
+
+    let files: Vec<String> = File::find()
+        .select_only()
+        .column(file::Column::Path)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(banners.chain(media).chain(files).collect())
+}
+
+/// List the files of the game banners and media which aren't referenced anymore and are older
+/// than the grace period, and the pending uploads which can't finish anymore
+#[tracing::instrument("Find orphaned files", skip(db, s3))]
+pub async fn find_orphans(
+    db: &DbPool,
+    s3: &S3Client,
+    grace_period_hours: u32,
+) -> AppResult<GcReport> {
+    // List the bucket first, so a file uploaded in between is never seen as unreferenced
+    let objects = s3.list_objects(GAMES_KEY_PREFIX).await?;
+    let referenced = get_referenced_keys(db).await?;
+
+    let pending = File::find()
+        .filter(file::Column::ConfirmedAt.is_null())
+        .filter(file::Column::CreatedAt.lt(pending_upload_limit(grace_period_hours)))
+        .all(db)
+        .await?;
+    let (finished_uploads, aborted_uploads) = split_pending_uploads(pending, &objects);
+
+    let limit = OffsetDateTime::now_utc() - Duration::hours(grace_period_hours.into());
+    let scanned = objects.len();
+    let orphans = select_orphans(objects, &referenced, limit);
+
+    Ok(GcReport {
+        scanned,
+        orphans_size: orphans.iter().map(|object| object.size).sum(),
+        orphans,
+        finished_uploads,
+        aborted_uploads,
+    })
+}
+
+/// Files managed by the garbage collector, unreferenced and last modified before the limit
+fn select_orphans(
+    objects: Vec<StoredObject>,
+    referenced: &HashSet<String>,
+    limit: OffsetDateTime,
+) -> Vec<StoredObject> {
+    objects
+        .into_iter()
+        .filter(|object| is_gc_owned(&object.key))
+        .filter(|object| object.last_modified < limit && !referenced.contains(&object.key))
+        .collect()
+}
+
+/// Split the stale pending uploads into the finished ones, found in the bucket, and the aborted ones
+fn split_pending_uploads(
+    pending: Vec<file::Model>,
+    objects: &[StoredObject],
+) -> (Vec<PendingUpload>, Vec<PendingUpload>) {
+    let keys: HashSet<&str> = objects.iter().map(|object| object.key.as_str()).collect();
+
+    pending
+        .into_iter()
+        .map(|file| PendingUpload {
+            id: file.id,
+            key: file.path,
+            created_at: file.created_at,
+        })
+        .partition(|upload| keys.contains(upload.key.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use time::{Duration, OffsetDateTime};
+
+    use super::{is_gc_owned, select_orphans, split_pending_uploads};
+    use crate::{core::s3::StoredObject, entities::file};
+
+    fn object(key: &str, age_hours: i64) -> StoredObject {
+        StoredObject {
+            key: key.to_owned(),
+            size: 10,
+            last_modified: OffsetDateTime::now_utc() - Duration::hours(age_hours),
+        }
+    }
+
+    fn pending(id: i32, path: &str) -> file::Model {
+        file::Model {
+            id,
+            path: path.to_owned(),
+            created_at: OffsetDateTime::now_utc() - Duration::days(2),
+            confirmed_at: None,
+        }
+    }
+
+    #[test]
+    fn only_banners_and_media_are_gc_owned() {
+        assert!(is_gc_owned("games/1/banner/abc"));
+        assert!(is_gc_owned("games/12/media/abc-480.webp"));
+
+        assert!(!is_gc_owned("games/1/banner/"));
+        assert!(!is_gc_owned("games/1/versions/game.zip"));
+        assert!(!is_gc_owned("games/1/saves/1"));
+        assert!(!is_gc_owned("games/abc/media/abc"));
+        assert!(!is_gc_owned("games/1/media"));
+        assert!(!is_gc_owned("other/1/media/abc"));
+    }
+
+    #[test]
+    fn orphans_are_old_unreferenced_gc_owned_files() {
+        let referenced = HashSet::from(["games/1/banner/referenced".to_owned()]);
+        let objects = vec![
+            object("games/1/banner/referenced", 48),
+            object("games/1/banner/orphan", 48),
+            object("games/1/media/recent", 1),
+            object("games/1/versions/game.zip", 48),
+        ];
+
+        let orphans = select_orphans(
+            objects,
+            &referenced,
+            OffsetDateTime::now_utc() - Duration::hours(24),
+        );
+
+        let keys: Vec<_> = orphans.iter().map(|object| object.key.as_str()).collect();
+        assert_eq!(keys, ["games/1/banner/orphan"]);
+    }
+
+    #[test]
+    fn pending_uploads_missing_from_the_bucket_are_aborted() {
+        let objects = vec![object("games/1/versions/game.zip", 30)];
+
+        let (finished, aborted) = split_pending_uploads(
+            vec![
+                pending(1, "games/1/versions/game.zip"),
+                pending(2, "games/1/saves/3"),
+            ],
+            &objects,
+        );
+
+        assert_eq!(
+            finished.iter().map(|upload| upload.id).collect::<Vec<_>>(),
+            [1]
+        );
+        assert_eq!(
+            aborted.iter().map(|upload| upload.id).collect::<Vec<_>>(),
+            [2]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use time::OffsetDateTime;
+
+use crate::core::config::{SecretKey, TwoFactorConfig};
+use crate::entities::{prelude::*, recovery_code, user};
+use crate::{
+    core::{database::DbPool, errors::AppResult},
+    entities::user::Model as UserModel,
+    helpers::{hashing, two_factor},
+};
+
+/// Persist the encrypted TOTP secret of the user and generate its recovery codes.
+/// `step` is the time step of the code validating the enrollment, it can't be used again.
+#[tracing::instrument("Enable two-factor", skip(db, secret_key, user, secret), fields(user_id = user.id))]
+pub async fn enable(
+    db: &DbPool,
+    secret_key: &SecretKey,
+    user: &UserModel,
+    secret: &str,
+    step: i64,
+) -> AppResult<Vec<String>> {
+    let txn = db.begin().await?;
+
+    let mut active_user: user::ActiveModel = user.clone().into();
+    active_user.totp_secret = Set(Some(two_factor::encrypt_secret(secret_key, secret)?));
+    active_user.totp_enabled_at = Set(Some(OffsetDateTime::now_utc()));
+    active_user.totp_last_step = Set(Some(step));
+    active_user.update(&txn).await?;
+
+    let codes = replace_recovery_codes(&txn, user.id).await?;
+
+    txn.commit().await?;
+
+    Ok(codes)
+}
+
+#[tracing::instrument("Disable two-factor", skip(db, user), fields(user_id = user.id))]
+pub async fn disable(db: &DbPool, user: &UserModel) -> AppResult<()> {
+    let txn = db.begin().await?;
+
+    let mut active_user: user::ActiveModel = user.clone().into();
+    active_user.totp_secret = Set(None);
+    active_user.totp_enabled_at = Set(None);
+    active_user.totp_last_step = Set(None);
+    active_user.update(&txn).await?;
+
+    RecoveryCode::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+#[tracing::instrument("Regenerate recovery codes", skip(db, user), fields(user_id = user.id))]
+pub async fn regenerate_recovery_codes(db: &DbPool, user: &UserModel) -> AppResult<Vec<String>> {
+    let txn = db.begin().await?;
+
+    let codes = replace_recovery_codes(&txn, user.id).await?;
+
+    txn.commit().await?;
+
+    Ok(codes)
+}
+
+/// Check a code against the TOTP secret of the user, or against its unused recovery codes.
+/// A matching recovery code is consumed, and a TOTP code is only accepted once.
+#[tracing::instrument(
+    "Verify two-factor code",
+    skip(db, config, secret_key, user, code),
+    fields(user_id = user.id)
+)]
+pub async fn verify_code(
+    db: &DbPool,
+    config: &TwoFactorConfig,
+    secret_key: &SecretKey,
+    user: &UserModel,
+    code: &str,
+) -> AppResult<bool> {
+    let Some(secret) = &user.totp_secret else {
+        return Ok(false);
+    };
+
+    let secret = two_factor::decrypt_secret(secret_key, secret)?;
+    let totp = two_factor::build_totp(&secret, &config.issuer, &user.email)?;
+
+    if let Some(step) = two_factor::verify_code(&totp, code)? {
+        // Only move the last step forward, so a replayed code is refused even by a concurrent request
+        let result = User::update_many()
+            .col_expr(user::Column::TotpLastStep, Expr::value(step))
+            .filter(user::Column::Id.eq(user.id))
+            .filter(
+                Condition::any()
+                    .add(user::Column::TotpLastStep.is_null())
+                    .add(user::Column::TotpLastStep.lt(step)),
+            )
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            tracing::warn!("TOTP code replayed");
+        }
+
+        return Ok(result.rows_affected > 0);
+    }
+
+    let recovery_codes = RecoveryCode::find()
+        .filter(recovery_code::Column::UserId.eq(user.id))
+        .filter(recovery_code::Column::UsedAt.is_null())
+        .all(db)
+        .await?;
+
+    let code = code.trim().to_lowercase();
+
+    for recovery_code in recovery_codes {
+        if hashing::verify_password(&recovery_code.code_hash, &code)? {
+            // Only consumed if still unused, so a concurrent request can't use it too
+            let result = RecoveryCode::update_many()
+                .col_expr(
+                    recovery_code::Column::UsedAt,
+                    Expr::value(OffsetDateTime::now_utc()),
+                )
+                .filter(recovery_code::Column::Id.eq(recovery_code.id))
+                .filter(recovery_code::Column::UsedAt.is_null())
+                .exec(db)
+                .await?;
+
+            if result.rows_affected == 0 {
+                tracing::warn!("Recovery code replayed");
+                return Ok(false);
+            }
+
+            tracing::info!("Recovery code used");
+
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+async fn replace_recovery_codes<C>(db: &C, user_id: i32) -> AppResult<Vec<String>>
+where
+    C: sea_orm::ConnectionTrait,
+{
+    RecoveryCode::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let codes = two_factor::generate_recovery_codes();
+
+    let models = codes
+        .iter()
+        .map(|code| {
+            Ok(recovery_code::ActiveModel {
+                user_id: Set(user_id),
+                code_hash: Set(hashing::hash(code)?),
+                ..Default::default()
+            })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    RecoveryCode::insert_many(models).exec(db).await?;
+
+    Ok(codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use time::OffsetDateTime;
+
+    use super::verify_code;
+    use crate::{
+        core::config::{SecretKey, TwoFactorConfig},
+        entities::{
+            recovery_code::Model as RecoveryCodeModel,
+            user::{Model as UserModel, UserRole},
+        },
+        helpers::{hashing, two_factor},
+    };
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    fn user(secret_key: &SecretKey, secret: &str) -> UserModel {
+        let now = OffsetDateTime::now_utc();
+
+        UserModel {
+            id: 1,
+            email: "player@example.com".to_owned(),
+            password: "hash".to_owned(),
+            role: UserRole::User,
+            totp_secret: Some(two_factor::encrypt_secret(secret_key, secret).unwrap()),
+            totp_enabled_at: Some(now),
+            totp_last_step: None,
+            sessions_valid_after: None,
+            oidc_subject: None,
+            email_verified_at: Some(now),
+            disabled_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn totp_codes_are_accepted_once() {
+        let secret_key = SecretKey("a".repeat(64));
+        let config = TwoFactorConfig::default();
+        let secret = two_factor::generate_secret();
+        let user = user(&secret_key, &secret);
+        let code = two_factor::build_totp(&secret, &config.issuer, &user.email)
+            .unwrap()
+            .generate_current()
+            .unwrap();
+
+        // The second update finds the step already recorded
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([exec(1), exec(0)])
+            .into_connection();
+
+        assert!(verify_code(&db, &config, &secret_key, &user, &code)
+            .await
+            .unwrap());
+        assert!(!verify_code(&db, &config, &secret_key, &user, &code)
+            .await
+            .unwrap());
+
+        let log = db.into_transaction_log();
+        assert!(log[0].statements()[0]
+            .sql
+            .contains(r#""totp_last_step" < $"#));
+    }
+
+    #[actix_rt::test]
+    async fn recovery_codes_are_used_once() {
+        let secret_key = SecretKey("a".repeat(64));
+        let user = user(&secret_key, &two_factor::generate_secret());
+        let recovery_code = RecoveryCodeModel {
+            id: 1,
+            user_id: user.id,
+            code_hash: hashing::hash(&"abcde-12345".to_owned()).unwrap(),
+            used_at: None,
+            created_at: OffsetDateTime::now_utc(),
+        };
+
+        // Both requests read the code as unused, only the first one consumes it
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![recovery_code.clone()]])
+            .append_exec_results([exec(1)])
+            .append_query_results([vec![recovery_code]])
+            .append_exec_results([exec(0)])
+            .into_connection();
+        let config = TwoFactorConfig::default();
+
+        assert!(verify_code(&db, &config, &secret_key, &user, "ABCDE-12345")
+            .await
+            .unwrap());
+        assert!(
+            !verify_code(&db, &config, &secret_key, &user, "abcde-12345")
+                .await
+                .unwrap()
+        );
+
+        let log = db.into_transaction_log();
+        assert!(log[1].statements()[0].sql.contains(r#""used_at" IS NULL"#));
+    }
+}
@@ -1,14 +1,29 @@
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, Set};
+use rand::Rng;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, Set};
+use time::OffsetDateTime;
 
 use crate::entities::{prelude::*, user};
+use crate::repositories::{
+    audit,
+    pagination::{self, KeyType, Keyset},
+};
 use crate::{
     core::{
+        audit::AuditContext,
+        config::OidcConfig,
         database::DbPool,
         errors::{AppError, AppResult},
+        oidc::OidcIdentity,
     },
-    entities::user::Model as UserModel,
+    entities::user::{Model as UserModel, UserRole},
     helpers::hashing,
-    models::user::{UserCreateInput, UserLoginRequest},
+    models::{
+        pagination::{Paginated, Pagination},
+        search::Search,
+        user::{
+            UserAdminCreateInput, UserCreateInput, UserFilter, UserLoginRequest, UserUpdateInput,
+        },
+    },
 };
 
 pub async fn get_users(db: &DbPool) -> AppResult<Vec<UserModel>> {
@@ -20,13 +35,37 @@ pub async fn get_users(db: &DbPool) -> AppResult<Vec<UserModel>> {
     Ok(users)
 }
 
-pub async fn count_users(db: &DbPool) -> AppResult<u64> {
-    let count = User::find()
-        .count(db)
-        .await
-        .map_err(AppError::DatabaseError)?;
+pub async fn paginate_users(
+    db: &DbPool,
+    pagination_query: &Pagination,
+    search_query: &Search,
+    filter: &UserFilter,
+) -> AppResult<Paginated<UserModel>> {
+    let mut user_query = User::find();
+
+    if let Some(search) = search_query.get_search() {
+        user_query = user_query.filter(user::Column::Email.contains(search.to_lowercase()));
+    }
+
+    if let Some(role) = &filter.role {
+        user_query = user_query.filter(user::Column::Role.eq(role.clone()));
+    }
+
+    match filter.disabled {
+        Some(true) => user_query = user_query.filter(user::Column::DisabledAt.is_not_null()),
+        Some(false) => user_query = user_query.filter(user::Column::DisabledAt.is_null()),
+        None => {}
+    }
+
+    let keyset = Keyset::column(
+        "email",
+        user::Column::Email,
+        KeyType::Text,
+        user::Column::Id,
+        search_query.get_sort_order(),
+    );
 
-    Ok(count)
+    pagination::paginate(db, user_query, pagination_query, keyset).await
 }
 
 pub async fn get_user_from_id(db: &DbPool, id: i32) -> AppResult<Option<UserModel>> {
@@ -54,6 +93,14 @@ pub async fn login(db: &DbPool, login_input: &UserLoginRequest) -> AppResult<Use
 
     if let Some(user) = user {
         if hashing::verify_password(&user.password, &login_input.password)? {
+            if user.is_disabled() {
+                return Err(AppError::AccountDisabled);
+            }
+
+            if user.email_verified_at.is_none() {
+                return Err(AppError::EmailNotVerified);
+            }
+
             return Ok(user);
         }
     }
@@ -79,3 +126,449 @@ pub async fn create_user(db: &DbPool, user: &UserCreateInput) -> AppResult<UserM
 
     Ok(user)
 }
+
+#[tracing::instrument("Verify user email", skip(db))]
+pub async fn verify_email(db: &DbPool, user_id: i32) -> AppResult<UserModel> {
+    let user = get_user_from_id(db, user_id)
+        .await?
+        .ok_or(AppError::InvalidToken)?;
+
+    if user.email_verified_at.is_some() {
+        return Ok(user);
+    }
+
+    let mut user: user::ActiveModel = user.into();
+    user.email_verified_at = Set(Some(OffsetDateTime::now_utc()));
+
+    Ok(user.update(db).await?)
+}
+
+#[tracing::instrument("Reset user password", skip(db, ctx, password))]
+pub async fn reset_password(
+    db: &DbPool,
+    ctx: &AuditContext,
+    user_id: i32,
+    password: &str,
+) -> AppResult<UserModel> {
+    let previous = get_user_from_id(db, user_id)
+        .await?
+        .ok_or(AppError::InvalidToken)?;
+
+    // Receiving the reset link proves that the user owns the email
+    let verified_at = previous
+        .email_verified_at
+        .or(Some(OffsetDateTime::now_utc()));
+
+    let mut user: user::ActiveModel = previous.clone().into();
+    user.password = Set(password.to_owned());
+    user.email_verified_at = Set(verified_at);
+    // Someone else may know the previous password
+    user.sessions_valid_after = Set(Some(OffsetDateTime::now_utc()));
+
+    let user = user.update(db).await?;
+    record(db, ctx, "user.password.reset", Some(&previous), &user).await?;
+
+    Ok(user)
+}
+
+/// Users created by an admin don't have to verify their email
+#[tracing::instrument("Create user as admin", skip(db, ctx, input))]
+pub async fn create_user_as_admin(
+    db: &DbPool,
+    ctx: &AuditContext,
+    input: &UserAdminCreateInput,
+) -> AppResult<UserModel> {
+    if get_user_from_email(db, &input.email).await?.is_some() {
+        return Err(AppError::AlreadyExists("User already exists".to_string()));
+    }
+
+    let user = user::ActiveModel {
+        email: Set(input.email.clone()),
+        password: Set(input.password.clone()),
+        role: Set(input.role.clone()),
+        email_verified_at: Set(Some(OffsetDateTime::now_utc())),
+        ..Default::default()
+    };
+
+    let user = user.insert(db).await?;
+    record(db, ctx, "user.create", None, &user).await?;
+
+    Ok(user)
+}
+
+#[tracing::instrument("Update user", skip(db, ctx, user, input), fields(user_id = user.id))]
+pub async fn update_user(
+    db: &DbPool,
+    ctx: &AuditContext,
+    user: UserModel,
+    input: &UserUpdateInput,
+) -> AppResult<UserModel> {
+    let mut active_user: user::ActiveModel = user.clone().into();
+
+    if let Some(email) = &input.email {
+        if email.to_lowercase() != user.email {
+            if get_user_from_email(db, email).await?.is_some() {
+                return Err(AppError::AlreadyExists("User already exists".to_string()));
+            }
+
+            active_user.email = Set(email.clone());
+        }
+    }
+
+    if let Some(role) = &input.role {
+        active_user.role = Set(role.clone());
+    }
+
+    let updated = active_user.update(db).await?;
+    record(db, ctx, "user.update", Some(&user), &updated).await?;
+
+    Ok(updated)
+}
+
+/// Change the email of the user, which has to be verified again
+#[tracing::instrument("Update user email", skip(db, ctx, user, email), fields(user_id = user.id))]
+pub async fn update_email(
+    db: &DbPool,
+    ctx: &AuditContext,
+    user: UserModel,
+    email: &String,
+) -> AppResult<UserModel> {
+    if email.to_lowercase() == user.email {
+        return Ok(user);
+    }
+
+    if get_user_from_email(db, email).await?.is_some() {
+        return Err(AppError::AlreadyExists("User already exists".to_string()));
+    }
+
+    let mut active_user: user::ActiveModel = user.clone().into();
+    active_user.email = Set(email.clone());
+    active_user.email_verified_at = Set(None);
+
+    let updated = active_user.update(db).await?;
+    record(db, ctx, "user.update", Some(&user), &updated).await?;
+
+    Ok(updated)
+}
+
+#[tracing::instrument("Change user password", skip(db, ctx, user, password), fields(user_id = user.id))]
+pub async fn change_password(
+    db: &DbPool,
+    ctx: &AuditContext,
+    user: UserModel,
+    password: &str,
+) -> AppResult<UserModel> {
+    let mut active_user: user::ActiveModel = user.clone().into();
+    active_user.password = Set(password.to_owned());
+    // Every session is revoked, the caller logs the current one in again if needed
+    active_user.sessions_valid_after = Set(Some(OffsetDateTime::now_utc()));
+
+    let updated = active_user.update(db).await?;
+    record(db, ctx, "user.password.change", Some(&user), &updated).await?;
+
+    Ok(updated)
+}
+
+/// Soft-disable the user, or enable it back. Disabled users keep their data but can't log in.
+#[tracing::instrument("Set user disabled", skip(db, ctx, user), fields(user_id = user.id))]
+pub async fn set_disabled(
+    db: &DbPool,
+    ctx: &AuditContext,
+    user: UserModel,
+    disabled: bool,
+) -> AppResult<UserModel> {
+    if user.is_disabled() == disabled {
+        return Ok(user);
+    }
+
+    let mut active_user: user::ActiveModel = user.clone().into();
+    active_user.disabled_at = Set(disabled.then(OffsetDateTime::now_utc));
+
+    let updated = active_user.update(db).await?;
+
+    let action = if disabled {
+        "user.disable"
+    } else {
+        "user.enable"
+    };
+    record(db, ctx, action, Some(&user), &updated).await?;
+
+    Ok(updated)
+}
+
+#[tracing::instrument("Delete user", skip(db, ctx, user), fields(user_id = user.id))]
+pub async fn delete_user(db: &DbPool, ctx: &AuditContext, user: UserModel) -> AppResult<()> {
+    audit::record(
+        db,
+        ctx,
+        "user.delete",
+        "user",
+        Some(user.id.to_string()),
+        Some(&user),
+        None,
+    )
+    .await?;
+
+    user.delete(db).await?;
+
+    Ok(())
+}
+
+async fn record(
+    db: &DbPool,
+    ctx: &AuditContext,
+    action: &str,
+    before: Option<&UserModel>,
+    after: &UserModel,
+) -> AppResult<()> {
+    audit::record(
+        db,
+        ctx,
+        action,
+        "user",
+        Some(after.id.to_string()),
+        before,
+        Some(after),
+    )
+    .await
+}
+
+/// Find the user matching the identity returned by the provider, creating it on first login.
+/// When groups are mapped in the configuration, the role of the user follows its groups.
+#[tracing::instrument("Login user with OIDC", skip(db, config, identity), fields(subject = %identity.subject))]
+pub async fn login_oidc(
+    db: &DbPool,
+    config: &OidcConfig,
+    identity: &OidcIdentity,
+) -> AppResult<UserModel> {
+    let role = map_oidc_role(config, &identity.groups);
+
+    let user = User::find()
+        .filter(user::Column::OidcSubject.eq(identity.subject.clone()))
+        .one(db)
+        .await?;
+
+    let mut user = match user {
+        Some(user) => user,
+        None => {
+            let email = identity.email.clone().ok_or(AppError::OidcError(
+                "The identity provider did not return an email".to_string(),
+            ))?;
+
+            // Accounts are only linked or created with an email verified by the provider
+            if !identity.email_verified {
+                return Err(AppError::EmailNotVerified);
+            }
+
+            match get_user_from_email(db, &email).await? {
+                Some(user) => {
+                    let verified_at = user.email_verified_at.or(Some(OffsetDateTime::now_utc()));
+
+                    let mut user: user::ActiveModel = user.into();
+                    user.oidc_subject = Set(Some(identity.subject.clone()));
+                    user.email_verified_at = Set(verified_at);
+                    user.update(db).await?
+                }
+                None => {
+                    // The account can't be used with a password until it is reset
+                    let password = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+
+                    let user = user::ActiveModel {
+                        email: Set(email),
+                        password: Set(password),
+                        role: Set(role.clone().unwrap_or(UserRole::User)),
+                        oidc_subject: Set(Some(identity.subject.clone())),
+                        email_verified_at: Set(Some(OffsetDateTime::now_utc())),
+                        ..Default::default()
+                    };
+
+                    let user = user.insert(db).await?;
+                    tracing::info!("Created user {} from OIDC login", user.id);
+
+                    user
+                }
+            }
+        }
+    };
+
+    if user.is_disabled() {
+        return Err(AppError::AccountDisabled);
+    }
+
+    // Linked accounts whose email wasn't verified yet, e.g. created before the check
+    if user.email_verified_at.is_none() {
+        let verified_by_provider = identity.email_verified
+            && identity
+                .email
+                .as_deref()
+                .is_some_and(|email| email.eq_ignore_ascii_case(&user.email));
+
+        if !verified_by_provider {
+            return Err(AppError::EmailNotVerified);
+        }
+
+        let mut verified: user::ActiveModel = user.into();
+        verified.email_verified_at = Set(Some(OffsetDateTime::now_utc()));
+        user = verified.update(db).await?;
+    }
+
+    match role {
+        Some(role) if role != user.role => {
+            let mut user: user::ActiveModel = user.into();
+            user.role = Set(role);
+            Ok(user.update(db).await?)
+        }
+        _ => Ok(user),
+    }
+}
+
+/// Roles are only managed by the provider when at least one group is mapped
+fn map_oidc_role(config: &OidcConfig, groups: &[String]) -> Option<UserRole> {
+    if config.admin_groups.is_empty() && config.publisher_groups.is_empty() {
+        return None;
+    }
+
+    let is_member = |mapped: &[String]| groups.iter().any(|group| mapped.contains(group));
+
+    if is_member(&config.admin_groups) {
+        Some(UserRole::Admin)
+    } else if is_member(&config.publisher_groups) {
+        Some(UserRole::Publisher)
+    } else {
+        Some(UserRole::User)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase, Value};
+    use time::OffsetDateTime;
+
+    use super::{login_oidc, map_oidc_role};
+    use crate::{
+        core::{config::OidcConfig, errors::AppError, oidc::OidcIdentity},
+        entities::user::{Model as UserModel, UserRole},
+    };
+
+    fn config(admin_groups: &[&str], publisher_groups: &[&str]) -> OidcConfig {
+        let groups = |groups: &[&str]| groups.iter().map(|group| group.to_string()).collect();
+
+        OidcConfig {
+            issuer_url: "https://sso.example.com".to_owned(),
+            client_id: "game-sync".to_owned(),
+            client_secret: None,
+            redirect_url: "https://game-sync.example.com/admin/auth/oidc/callback".to_owned(),
+            scopes: vec![],
+            groups_claim: "groups".to_owned(),
+            admin_groups: groups(admin_groups),
+            publisher_groups: groups(publisher_groups),
+        }
+    }
+
+    fn identity(email_verified: bool, groups: &[&str]) -> OidcIdentity {
+        OidcIdentity {
+            subject: "subject".to_owned(),
+            email: Some("Player@example.com".to_owned()),
+            email_verified,
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+        }
+    }
+
+    fn user(role: UserRole, oidc_subject: Option<&str>) -> UserModel {
+        let now = OffsetDateTime::now_utc();
+
+        UserModel {
+            id: 1,
+            email: "player@example.com".to_owned(),
+            password: "hash".to_owned(),
+            role,
+            totp_secret: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            sessions_valid_after: None,
+            oidc_subject: oidc_subject.map(str::to_owned),
+            email_verified_at: Some(now),
+            disabled_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn oidc_roles_are_not_managed_without_mapped_groups() {
+        assert_eq!(
+            map_oidc_role(&config(&[], &[]), &["admins".to_owned()]),
+            None
+        );
+    }
+
+    #[test]
+    fn oidc_groups_map_to_the_highest_role() {
+        let config = config(&["admins"], &["publishers"]);
+        let role = |groups: &[&str]| {
+            let groups: Vec<String> = groups.iter().map(|group| group.to_string()).collect();
+            map_oidc_role(&config, &groups)
+        };
+
+        assert_eq!(role(&["publishers", "admins"]), Some(UserRole::Admin));
+        assert_eq!(role(&["players", "publishers"]), Some(UserRole::Publisher));
+        assert_eq!(role(&["players"]), Some(UserRole::User));
+        assert_eq!(role(&[]), Some(UserRole::User));
+    }
+
+    #[actix_rt::test]
+    async fn oidc_first_login_creates_the_user() {
+        let created = user(UserRole::Publisher, Some("subject"));
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![], vec![], vec![created.clone()]])
+            .into_connection();
+
+        let user = login_oidc(
+            &db,
+            &config(&["admins"], &["publishers"]),
+            &identity(true, &["publishers"]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(user, created);
+
+        // Lookups by subject then by email, and the insert
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 3);
+
+        let insert = &log[2].statements()[0];
+        assert!(insert.sql.starts_with(r#"INSERT INTO "user""#));
+
+        let values = &insert.values.as_ref().unwrap().0;
+        for expected in ["player@example.com", "Publisher", "subject"] {
+            assert!(values.contains(&Value::String(Some(Box::new(expected.to_owned())))));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn oidc_login_does_not_link_or_create_an_unverified_email() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<UserModel>::new()])
+            .into_connection();
+
+        let result = login_oidc(&db, &config(&[], &[]), &identity(false, &[])).await;
+        assert!(matches!(result, Err(AppError::EmailNotVerified)));
+
+        // Nothing is looked up by email nor inserted
+        assert_eq!(db.into_transaction_log().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn oidc_login_requires_a_verified_email_for_linked_accounts() {
+        let mut unverified = user(UserRole::User, Some("subject"));
+        unverified.email_verified_at = None;
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![unverified]])
+            .into_connection();
+
+        let result = login_oidc(&db, &config(&[], &[]), &identity(false, &[])).await;
+
+        assert!(matches!(result, Err(AppError::EmailNotVerified)));
+    }
+}
@@ -0,0 +1,132 @@
+use rand::Rng;
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use time::OffsetDateTime;
+
+use crate::entities::{prelude::*, user_token};
+use crate::{
+    core::{
+        database::DbPool,
+        errors::{AppError, AppResult},
+    },
+    entities::user_token::{Model as UserTokenModel, UserTokenKind},
+    helpers::hashing,
+};
+
+/// Create a single-use token for the user and return it, only its hash is stored
+#[tracing::instrument("Create user token", skip(db))]
+pub async fn create_token(db: &DbPool, user_id: i32, kind: UserTokenKind) -> AppResult<String> {
+    let token = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+
+    let user_token = user_token::ActiveModel {
+        user_id: Set(user_id),
+        token_hash: Set(hashing::hash_token(&token)),
+        expires_at: Set(OffsetDateTime::now_utc() + kind.lifetime()),
+        kind: Set(kind),
+        ..Default::default()
+    };
+
+    user_token.insert(db).await?;
+
+    Ok(token)
+}
+
+/// Mark the token as used, failing if it is unknown, expired or already used
+#[tracing::instrument("Consume user token", skip(db, token))]
+pub async fn consume_token(
+    db: &DbPool,
+    kind: UserTokenKind,
+    token: &str,
+) -> AppResult<UserTokenModel> {
+    let token_hash = hashing::hash_token(token);
+    let now = OffsetDateTime::now_utc();
+
+    // Marked as used in a single statement, so concurrent requests can't both consume it
+    let result = UserToken::update_many()
+        .col_expr(user_token::Column::UsedAt, Expr::value(now))
+        .filter(user_token::Column::TokenHash.eq(token_hash.clone()))
+        .filter(user_token::Column::Kind.eq(kind.clone()))
+        .filter(user_token::Column::UsedAt.is_null())
+        .filter(user_token::Column::ExpiresAt.gt(now))
+        .exec(db)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::InvalidToken);
+    }
+
+    UserToken::find()
+        .filter(user_token::Column::TokenHash.eq(token_hash))
+        .filter(user_token::Column::Kind.eq(kind))
+        .one(db)
+        .await?
+        .ok_or(AppError::InvalidToken)
+}
+
+/// Invalidate all the pending tokens of the given kind, e.g. once the password has been changed
+#[tracing::instrument("Revoke user tokens", skip(db))]
+pub async fn revoke_tokens(db: &DbPool, user_id: i32, kind: UserTokenKind) -> AppResult<()> {
+    UserToken::update_many()
+        .col_expr(
+            user_token::Column::UsedAt,
+            Expr::value(OffsetDateTime::now_utc()),
+        )
+        .filter(user_token::Column::UserId.eq(user_id))
+        .filter(user_token::Column::Kind.eq(kind))
+        .filter(user_token::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use time::{Duration, OffsetDateTime};
+
+    use super::consume_token;
+    use crate::{
+        core::errors::AppError,
+        entities::user_token::{Model as UserTokenModel, UserTokenKind},
+    };
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn token_is_consumed_once() {
+        let now = OffsetDateTime::now_utc();
+        let token = UserTokenModel {
+            id: 1,
+            user_id: 1,
+            kind: UserTokenKind::PasswordReset,
+            token_hash: "hash".to_owned(),
+            expires_at: now + Duration::hours(1),
+            used_at: Some(now),
+            created_at: now,
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([exec(1), exec(0)])
+            .append_query_results([vec![token.clone()]])
+            .into_connection();
+
+        let consumed = consume_token(&db, UserTokenKind::PasswordReset, "token").await;
+        assert_eq!(consumed.unwrap(), token);
+
+        // The second request finds the token already used by the conditional update
+        let replayed = consume_token(&db, UserTokenKind::PasswordReset, "token").await;
+        assert!(matches!(replayed, Err(AppError::InvalidToken)));
+
+        let log = db.into_transaction_log();
+        let update = &log[0].statements()[0].sql;
+        assert!(update.starts_with(r#"UPDATE "user_token" SET "used_at""#));
+        assert!(update.contains(r#""used_at" IS NULL"#));
+        assert!(update.contains(r#""expires_at" >"#));
+        // Nothing is read once the update matched no token
+        assert_eq!(log.len(), 3);
+    }
+}
@@ -1,17 +1,28 @@
-use crate::data::AppData;
+use crate::core::config::SecretKey;
+use crate::core::rate_limit::{RateLimitKey, RateLimitPolicy};
+use crate::entities::user::UserRole;
 use crate::middlewares::auth::Auth;
+use crate::middlewares::rate_limit::RateLimit;
+use crate::middlewares::role::RequireRole;
+use crate::middlewares::two_factor::TwoFactor;
 use crate::{controllers::admin as admin_ctrl, middlewares::guest::Guest};
 use actix_multi_session::provider::CookieTokenProvider;
+use actix_multi_session::storage::SessionStore;
 use actix_multi_session::SessionMiddleware;
 use actix_web::cookie::Key;
+use actix_web::http::Method;
 use actix_web::web::{self, ServiceConfig};
 use time::Duration;
 
-pub fn register_route(cfg: &mut ServiceConfig, app_data: &AppData) {
-    let store = app_data.session_store.clone();
-    let key = app_data.secret_key.clone();
+use super::RouteList;
 
-    let session_provider = CookieTokenProvider::builder(Key::derive_from(key.0.as_bytes()))
+pub fn register_route<S: SessionStore + 'static>(
+    cfg: &mut ServiceConfig,
+    routes: &mut RouteList,
+    store: S,
+    secret_key: &SecretKey,
+) {
+    let session_provider = CookieTokenProvider::builder(Key::derive_from(secret_key.0.as_bytes()))
         .cookie_name("game-sync-session".to_string())
         .cookie_http_only(true)
         .cookie_secure(true)
@@ -20,21 +31,395 @@ pub fn register_route(cfg: &mut ServiceConfig, app_data: &AppData) {
 
     let session_middleware = SessionMiddleware::builder(store, session_provider).build();
 
+    // Rate limiting policies
+    let login_limit =
+        RateLimitPolicy::new("admin_login", 5, Duration::minutes(1), RateLimitKey::Ip);
+    let two_factor_limit = RateLimitPolicy::new(
+        "admin_two_factor",
+        5,
+        Duration::minutes(1),
+        RateLimitKey::Ip,
+    );
+    let passkey_limit =
+        RateLimitPolicy::new("admin_passkey", 10, Duration::minutes(1), RateLimitKey::Ip);
+    let account_limit =
+        RateLimitPolicy::new("admin_account", 5, Duration::minutes(10), RateLimitKey::Ip);
+    let setup_limit =
+        RateLimitPolicy::new("admin_setup", 5, Duration::minutes(1), RateLimitKey::Ip);
+    let register_limit =
+        RateLimitPolicy::new("admin_register", 3, Duration::hours(1), RateLimitKey::Ip);
+
+    // Registered by its attribute, it serves a page
+    routes.add_undocumented(Method::GET, "/admin");
+
     let scope = web::scope("admin")
         .service(admin_ctrl::admin::index)
+        .service(
+            web::resource("setup")
+                .route(
+                    routes
+                        .route(Method::POST, "/admin/setup")
+                        .to(admin_ctrl::setup::setup),
+                )
+                .wrap(Guest)
+                .wrap(RateLimit::new(setup_limit)),
+        )
         .service(
             web::scope("auth")
                 .service(
                     web::resource("")
-                        .route(web::get().to(admin_ctrl::auth::me).wrap(Auth))
-                        .route(web::delete().to(admin_ctrl::auth::logout).wrap(Auth))
-                        .route(web::post().to(admin_ctrl::auth::login).wrap(Guest)),
+                        .route(
+                            routes
+                                .route(Method::GET, "/admin/auth")
+                                .to(admin_ctrl::auth::me)
+                                .wrap(Auth),
+                        )
+                        .route(
+                            routes
+                                .route(Method::DELETE, "/admin/auth")
+                                .to(admin_ctrl::auth::logout)
+                                .wrap(Auth),
+                        )
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth")
+                                .to(admin_ctrl::auth::login)
+                                .wrap(Guest)
+                                .wrap(RateLimit::new(login_limit.clone())),
+                        ),
                 )
                 .route(
                     "register",
-                    web::route().to(admin_ctrl::auth::register).wrap(Guest),
+                    routes
+                        .route(Method::POST, "/admin/auth/register")
+                        .to(admin_ctrl::auth::register)
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(register_limit)),
+                )
+                .service(
+                    web::resource("verify-email")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/verify-email")
+                                .to(admin_ctrl::account::verify_email),
+                        )
+                        .wrap(RateLimit::new(account_limit.clone())),
+                )
+                .service(
+                    web::resource("verify-email/resend")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/verify-email/resend")
+                                .to(admin_ctrl::account::resend_verification),
+                        )
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(account_limit.clone())),
+                )
+                .service(
+                    web::resource("forgot-password")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/forgot-password")
+                                .to(admin_ctrl::account::forgot_password),
+                        )
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(account_limit.clone())),
+                )
+                .service(
+                    web::resource("reset-password")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/reset-password")
+                                .to(admin_ctrl::account::reset_password),
+                        )
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(account_limit)),
+                )
+                .service(
+                    web::resource("two-factor")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/two-factor")
+                                .to(admin_ctrl::two_factor::verify)
+                                .wrap(Guest)
+                                .wrap(RateLimit::new(two_factor_limit)),
+                        )
+                        .route(
+                            routes
+                                .route(Method::DELETE, "/admin/auth/two-factor")
+                                .to(admin_ctrl::two_factor::disable)
+                                .wrap(Auth),
+                        ),
+                )
+                .service(
+                    web::resource("two-factor/enroll")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/two-factor/enroll")
+                                .to(admin_ctrl::two_factor::enroll),
+                        )
+                        .wrap(Auth),
+                )
+                .service(
+                    web::resource("two-factor/enable")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/two-factor/enable")
+                                .to(admin_ctrl::two_factor::enable),
+                        )
+                        .wrap(Auth),
+                )
+                .service(
+                    web::resource("two-factor/recovery-codes")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/two-factor/recovery-codes")
+                                .to(admin_ctrl::two_factor::regenerate_recovery_codes),
+                        )
+                        .wrap(Auth),
+                )
+                .service(
+                    web::resource("oidc")
+                        .route(
+                            routes
+                                .route(Method::GET, "/admin/auth/oidc")
+                                .to(admin_ctrl::oidc::authorize),
+                        )
+                        .wrap(Guest),
+                )
+                .service(
+                    web::resource("oidc/callback")
+                        .route(
+                            routes
+                                .route(Method::GET, "/admin/auth/oidc/callback")
+                                .to(admin_ctrl::oidc::callback),
+                        )
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(login_limit)),
+                )
+                .service(
+                    web::resource("passkeys")
+                        .route(
+                            routes
+                                .route(Method::GET, "/admin/auth/passkeys")
+                                .to(admin_ctrl::passkeys::get_passkeys),
+                        )
+                        .wrap(Auth),
+                )
+                .service(
+                    web::resource("passkeys/{id}")
+                        .route(
+                            routes
+                                .route(Method::DELETE, "/admin/auth/passkeys/{id}")
+                                .to(admin_ctrl::passkeys::delete_passkey),
+                        )
+                        .wrap(Auth),
+                )
+                .service(
+                    web::resource("passkeys/register/start")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/passkeys/register/start")
+                                .to(admin_ctrl::passkeys::start_registration),
+                        )
+                        .wrap(Auth),
+                )
+                .service(
+                    web::resource("passkeys/register/finish")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/passkeys/register/finish")
+                                .to(admin_ctrl::passkeys::finish_registration),
+                        )
+                        .wrap(Auth),
+                )
+                .service(
+                    web::resource("passkeys/login/start")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/passkeys/login/start")
+                                .to(admin_ctrl::passkeys::start_authentication),
+                        )
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(passkey_limit.clone())),
+                )
+                .service(
+                    web::resource("passkeys/login/finish")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/auth/passkeys/login/finish")
+                                .to(admin_ctrl::passkeys::finish_authentication),
+                        )
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(passkey_limit)),
                 ),
         )
+        .service(
+            web::resource("profile")
+                .route(
+                    routes
+                        .route(Method::GET, "/admin/profile")
+                        .to(admin_ctrl::profile::get_profile),
+                )
+                .route(
+                    routes
+                        .route(Method::PUT, "/admin/profile")
+                        .to(admin_ctrl::profile::update_profile),
+                )
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("profile/password")
+                .route(
+                    routes
+                        .route(Method::PUT, "/admin/profile/password")
+                        .to(admin_ctrl::profile::change_password),
+                )
+                .wrap(Auth),
+        )
+        .service(
+            web::scope("users")
+                .service(
+                    web::resource("")
+                        .route(
+                            routes
+                                .route(Method::GET, "/admin/users")
+                                .to(admin_ctrl::users::get_users),
+                        )
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/users")
+                                .to(admin_ctrl::users::create_user),
+                        ),
+                )
+                .service(
+                    web::resource("{id}")
+                        .route(
+                            routes
+                                .route(Method::GET, "/admin/users/{id}")
+                                .to(admin_ctrl::users::get_user),
+                        )
+                        .route(
+                            routes
+                                .route(Method::PUT, "/admin/users/{id}")
+                                .to(admin_ctrl::users::update_user),
+                        )
+                        .route(
+                            routes
+                                .route(Method::DELETE, "/admin/users/{id}")
+                                .to(admin_ctrl::users::delete_user),
+                        ),
+                )
+                .service(
+                    web::resource("{id}/disable")
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/users/{id}/disable")
+                                .to(admin_ctrl::users::disable_user),
+                        )
+                        .route(
+                            routes
+                                .route(Method::DELETE, "/admin/users/{id}/disable")
+                                .to(admin_ctrl::users::enable_user),
+                        ),
+                )
+                .service(
+                    web::resource("{id}/password").route(
+                        routes
+                            .route(Method::PUT, "/admin/users/{id}/password")
+                            .to(admin_ctrl::users::set_password),
+                    ),
+                )
+                .wrap(RequireRole::new(UserRole::Admin))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::scope("invitations")
+                .service(
+                    web::resource("")
+                        .route(
+                            routes
+                                .route(Method::GET, "/admin/invitations")
+                                .to(admin_ctrl::invitations::get_invitations),
+                        )
+                        .route(
+                            routes
+                                .route(Method::POST, "/admin/invitations")
+                                .to(admin_ctrl::invitations::create_invitation),
+                        ),
+                )
+                .service(
+                    web::resource("{id}").route(
+                        routes
+                            .route(Method::DELETE, "/admin/invitations/{id}")
+                            .to(admin_ctrl::invitations::delete_invitation),
+                    ),
+                )
+                .wrap(RequireRole::new(UserRole::Admin))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("games/{id}")
+                .route(
+                    routes
+                        .route(Method::DELETE, "/admin/games/{id}")
+                        .to(admin_ctrl::games::purge_game),
+                )
+                .wrap(RequireRole::new(UserRole::Admin))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("storage/gc")
+                .route(
+                    routes
+                        .route(Method::GET, "/admin/storage/gc")
+                        .to(admin_ctrl::storage::get_gc_report),
+                )
+                .route(
+                    routes
+                        .route(Method::POST, "/admin/storage/gc")
+                        .to(admin_ctrl::storage::run_gc),
+                )
+                .wrap(RequireRole::new(UserRole::Admin))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("jobs")
+                .route(
+                    routes
+                        .route(Method::GET, "/admin/jobs")
+                        .to(admin_ctrl::jobs::get_jobs),
+                )
+                .wrap(RequireRole::new(UserRole::Admin))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("audit")
+                .route(
+                    routes
+                        .route(Method::GET, "/admin/audit")
+                        .to(admin_ctrl::audit::get_audit_logs),
+                )
+                .wrap(RequireRole::new(UserRole::Admin))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("rate-limits")
+                .route(
+                    routes
+                        .route(Method::GET, "/admin/rate-limits")
+                        .to(admin_ctrl::rate_limits::get_counters),
+                )
+                .wrap(RequireRole::new(UserRole::Admin))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
         .wrap(session_middleware);
 
     cfg.service(scope);
@@ -1,50 +1,266 @@
-use actix_multi_session::{provider::OpaqueTokenProvider, SessionMiddleware};
-use actix_web::web::{self, ServiceConfig};
+use actix_multi_session::{
+    provider::OpaqueTokenProvider, storage::SessionStore, SessionMiddleware,
+};
+use actix_web::{
+    http::Method,
+    web::{self, ServiceConfig},
+};
+use time::Duration;
 
 use crate::{
     controllers::api as api_ctrl,
-    data::AppData,
-    middlewares::{auth::Auth, guest::Guest},
+    core::rate_limit::{RateLimitKey, RateLimitPolicy},
+    entities::user::UserRole,
+    middlewares::{
+        auth::Auth, guest::Guest, rate_limit::RateLimit, role::RequireRole, two_factor::TwoFactor,
+    },
 };
 
-pub fn register_route(cfg: &mut ServiceConfig, app_data: &AppData) {
-    let store = app_data.session_store.clone();
+use super::RouteList;
 
+pub fn register_route<S: SessionStore + 'static>(
+    cfg: &mut ServiceConfig,
+    routes: &mut RouteList,
+    store: S,
+) {
     let session_provider = OpaqueTokenProvider::new();
 
     let session_middleware = SessionMiddleware::builder(store, session_provider).build();
 
+    // Rate limiting policies
+    let default_limit =
+        RateLimitPolicy::new("api", 300, Duration::minutes(1), RateLimitKey::ApiKey);
+    let login_limit = RateLimitPolicy::new("api_login", 5, Duration::minutes(1), RateLimitKey::Ip);
+    let two_factor_limit =
+        RateLimitPolicy::new("api_two_factor", 5, Duration::minutes(1), RateLimitKey::Ip);
+    let banner_limit = RateLimitPolicy::new(
+        "api_banner_upload",
+        10,
+        Duration::minutes(10),
+        RateLimitKey::User,
+    );
+    let media_limit = RateLimitPolicy::new(
+        "api_media_upload",
+        30,
+        Duration::minutes(10),
+        RateLimitKey::User,
+    );
+    let upload_limit = RateLimitPolicy::new(
+        "api_presigned_url",
+        30,
+        Duration::minutes(10),
+        RateLimitKey::User,
+    );
+
     let scope = web::scope("api")
         // Auth routes
         .service(
             web::resource("auth")
-                .route(web::get().to(api_ctrl::auth::me).wrap(Auth))
-                .route(web::delete().to(api_ctrl::auth::logout).wrap(Auth))
-                .route(web::post().to(api_ctrl::auth::login).wrap(Guest)),
+                .route(
+                    routes
+                        .route(Method::GET, "/api/auth")
+                        .to(api_ctrl::auth::me)
+                        .wrap(Auth),
+                )
+                .route(
+                    routes
+                        .route(Method::DELETE, "/api/auth")
+                        .to(api_ctrl::auth::logout)
+                        .wrap(Auth),
+                )
+                .route(
+                    routes
+                        .route(Method::POST, "/api/auth")
+                        .to(api_ctrl::auth::login)
+                        .wrap(Guest)
+                        .wrap(RateLimit::new(login_limit.clone())),
+                ),
+        )
+        .service(
+            web::resource("auth/two-factor").route(
+                routes
+                    .route(Method::POST, "/api/auth/two-factor")
+                    .to(api_ctrl::auth::verify_two_factor)
+                    .wrap(Guest)
+                    .wrap(RateLimit::new(two_factor_limit)),
+            ),
+        )
+        .service(
+            web::resource("auth/oidc")
+                .route(
+                    routes
+                        .route(Method::POST, "/api/auth/oidc")
+                        .to(api_ctrl::oidc::authorize),
+                )
+                .wrap(Guest),
+        )
+        .service(
+            web::resource("auth/oidc/callback")
+                .route(
+                    routes
+                        .route(Method::POST, "/api/auth/oidc/callback")
+                        .to(api_ctrl::oidc::callback),
+                )
+                .wrap(Guest)
+                .wrap(RateLimit::new(login_limit)),
         )
         .service(
             web::resource("games")
-                .route(web::get().to(api_ctrl::games::get_games))
-                .route(web::post().to(api_ctrl::games::create_game))
+                .route(
+                    routes
+                        .route(Method::GET, "/api/games")
+                        .to(api_ctrl::games::get_games),
+                )
+                .route(
+                    routes
+                        .route(Method::POST, "/api/games")
+                        .to(api_ctrl::games::create_game)
+                        .wrap(RequireRole::new(UserRole::Publisher)),
+                )
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("games/trash")
+                .route(
+                    routes
+                        .route(Method::GET, "/api/games/trash")
+                        .to(api_ctrl::games::get_deleted_games),
+                )
+                .wrap(RequireRole::new(UserRole::Publisher))
+                .wrap(TwoFactor)
                 .wrap(Auth),
         )
         .service(
             web::resource("games/{id}")
-                .route(web::get().to(api_ctrl::games::get_game))
-                .route(web::put().to(api_ctrl::games::update_game))
+                .route(
+                    routes
+                        .route(Method::GET, "/api/games/{id}")
+                        .to(api_ctrl::games::get_game),
+                )
+                .route(
+                    routes
+                        .route(Method::PUT, "/api/games/{id}")
+                        .to(api_ctrl::games::update_game)
+                        .wrap(RequireRole::new(UserRole::Publisher)),
+                )
+                .route(
+                    routes
+                        .route(Method::DELETE, "/api/games/{id}")
+                        .to(api_ctrl::games::delete_game)
+                        .wrap(RequireRole::new(UserRole::Publisher)),
+                )
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("games/{id}/restore")
+                .route(
+                    routes
+                        .route(Method::POST, "/api/games/{id}/restore")
+                        .to(api_ctrl::games::restore_game),
+                )
+                .wrap(RequireRole::new(UserRole::Publisher))
+                .wrap(TwoFactor)
                 .wrap(Auth),
         )
         .service(
-            web::resource("games/id/banner")
-                .route(web::post().to(api_ctrl::games::upload_game_banner))
-                .route(web::delete().to(api_ctrl::games::delete_game_banner))
+            web::resource("games/{id}/banner")
+                .route(
+                    routes
+                        .route(Method::POST, "/api/games/{id}/banner")
+                        .to(api_ctrl::games::upload_game_banner)
+                        .wrap(RateLimit::new(banner_limit)),
+                )
+                .route(
+                    routes
+                        .route(Method::DELETE, "/api/games/{id}/banner")
+                        .to(api_ctrl::games::delete_game_banner),
+                )
+                .wrap(RequireRole::new(UserRole::Publisher))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("games/{id}/media")
+                .route(
+                    routes
+                        .route(Method::POST, "/api/games/{id}/media")
+                        .to(api_ctrl::games::upload_game_media)
+                        .wrap(RateLimit::new(media_limit)),
+                )
+                .wrap(RequireRole::new(UserRole::Publisher))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        // Registered before `games/{id}/media/{media_id}` to take precedence
+        .service(
+            web::resource("games/{id}/media/trailer")
+                .route(
+                    routes
+                        .route(Method::PUT, "/api/games/{id}/media/trailer")
+                        .to(api_ctrl::games::set_game_trailer),
+                )
+                .wrap(RequireRole::new(UserRole::Publisher))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("games/{id}/media/order")
+                .route(
+                    routes
+                        .route(Method::PUT, "/api/games/{id}/media/order")
+                        .to(api_ctrl::games::reorder_game_screenshots),
+                )
+                .wrap(RequireRole::new(UserRole::Publisher))
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("games/{id}/media/{media_id}")
+                .route(
+                    routes
+                        .route(Method::DELETE, "/api/games/{id}/media/{media_id}")
+                        .to(api_ctrl::games::delete_game_media),
+                )
+                .wrap(RequireRole::new(UserRole::Publisher))
+                .wrap(TwoFactor)
                 .wrap(Auth),
         )
         .service(
             web::resource("game/{id}/version")
-                .route(web::post().to(api_ctrl::versions::upload_file))
+                .route(
+                    routes
+                        .route(Method::GET, "/api/game/{id}/version")
+                        .to(api_ctrl::versions::download_file),
+                )
+                .route(
+                    routes
+                        .route(Method::POST, "/api/game/{id}/version")
+                        .to(api_ctrl::versions::upload_file)
+                        .wrap(RateLimit::new(upload_limit.clone()))
+                        .wrap(RequireRole::new(UserRole::Publisher)),
+                )
+                .wrap(TwoFactor)
+                .wrap(Auth),
+        )
+        .service(
+            web::resource("game/{id}/save")
+                .route(
+                    routes
+                        .route(Method::GET, "/api/game/{id}/save")
+                        .to(api_ctrl::saves::download_save),
+                )
+                .route(
+                    routes
+                        .route(Method::POST, "/api/game/{id}/save")
+                        .to(api_ctrl::saves::upload_save)
+                        .wrap(RateLimit::new(upload_limit)),
+                )
+                .wrap(TwoFactor)
                 .wrap(Auth),
         )
+        .wrap(RateLimit::new(default_limit))
         .wrap(session_middleware);
 
     cfg.service(scope);
@@ -1,12 +1,81 @@
-use crate::{controllers, data::AppData};
-use actix_web::web;
+use crate::{
+    controllers,
+    core::{config::SecretKey, openapi::ApiDoc},
+    data::AppData,
+};
+use actix_multi_session::storage::SessionStore;
+use actix_web::{http::Method, web};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 mod admin;
 mod api;
 
 pub fn setup_routes(cfg: &mut web::ServiceConfig, app_data: &AppData) {
+    // Only served here when protected by a token, it has its own address otherwise
+    if app_data.config.server.metrics_address.is_none()
+        && app_data.config.server.metrics_token.is_some()
+    {
+        cfg.route("/metrics", web::get().to(controllers::base::metrics));
+    }
+
+    // Registered before the `api` scope, which would answer with a 404 otherwise
+    cfg.service(SwaggerUi::new("/api/docs/{_:.*}").url("/api/openapi.json", ApiDoc::openapi()));
+
+    cfg.configure(|cfg| {
+        register_documented_routes(cfg, app_data.session_store.clone(), &app_data.secret_key);
+    });
+}
+
+/// Methods and full paths of the registered routes. Actix doesn't list them, so they are
+/// recorded while the routes are built.
+#[derive(Debug, Default)]
+pub struct RouteList {
+    pub documented: Vec<(Method, String)>,
+    pub undocumented: Vec<(Method, String)>,
+}
+
+impl RouteList {
+    /// Route answering the method, recorded under the full path of its resource
+    pub fn route(&mut self, method: Method, path: &str) -> web::Route {
+        self.add(method.clone(), path);
+        web::method(method)
+    }
+
+    /// Records a route registered by its attribute
+    pub fn add(&mut self, method: Method, path: &str) {
+        self.documented.push((method, path.to_owned()));
+    }
+
+    /// Records a route which isn't described by the OpenAPI document
+    pub fn add_undocumented(&mut self, method: Method, path: &str) {
+        self.undocumented.push((method, path.to_owned()));
+    }
+}
+
+/// Routes described by the OpenAPI document, returning their list. The session store is a
+/// parameter so the routes can be built without Redis, e.g. to compare them with the document.
+pub fn register_documented_routes<S: SessionStore + Clone + 'static>(
+    cfg: &mut web::ServiceConfig,
+    store: S,
+    secret_key: &SecretKey,
+) -> RouteList {
+    let mut routes = RouteList::default();
+
     cfg.service(controllers::base::status);
+    cfg.service(controllers::health::live);
+    cfg.service(controllers::health::ready);
+    routes.add(Method::GET, "/");
+    routes.add(Method::GET, "/health/live");
+    routes.add(Method::GET, "/health/ready");
+
+    cfg.configure(|cfg| api::register_route(cfg, &mut routes, store.clone()));
+    cfg.configure(|cfg| admin::register_route(cfg, &mut routes, store, secret_key));
+
+    routes
+}
 
-    cfg.configure(|cfg| api::register_route(cfg, app_data));
-    cfg.configure(|cfg| admin::register_route(cfg, app_data));
+/// Routes of the separate metrics address
+pub fn setup_metrics_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(controllers::base::metrics));
 }
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use crate::{data::AppData, repositories};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Periodically delete the audit log entries older than the retention period
+pub fn spawn(app_data: AppData) {
+    let retention_days = app_data.config.audit.retention_days;
+
+    if retention_days == 0 {
+        tracing::info!("Audit log retention is disabled, entries are kept forever");
+        return;
+    }
+
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match repositories::audit::purge_audit_logs(&app_data.db, retention_days).await {
+                Ok(count) => tracing::info!("Purged {} audit log entries", count),
+                Err(e) => tracing::error!("Failed to purge the audit logs: {}", e),
+            }
+        }
+    });
+}
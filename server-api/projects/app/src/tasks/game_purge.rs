@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use crate::{
+    core::audit::AuditContext, data::AppData, entities::game::Model as GameModel, repositories,
+};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically purge the games whose restore window is over
+pub fn spawn(app_data: AppData) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let games = match repositories::games::get_expired_deleted_games(
+                &app_data.db,
+                app_data.config.games.restore_window_days,
+            )
+            .await
+            {
+                Ok(games) => games,
+                Err(e) => {
+                    tracing::error!("Failed to fetch the games to purge: {}", e);
+                    continue;
+                }
+            };
+
+            for game in games {
+                run(&app_data, game, &AuditContext::system()).await;
+            }
+        }
+    });
+}
+
+/// Purge the game in the background, returning the id of the job to follow its progress.
+/// Nothing is started if the game is already being purged.
+pub fn start(app_data: AppData, game: GameModel, ctx: AuditContext) -> Option<String> {
+    let job_id = job_id(&game);
+
+    if app_data
+        .jobs
+        .get(&job_id)
+        .is_some_and(|job| job.finished_at.is_none())
+    {
+        return None;
+    }
+
+    actix_rt::spawn(async move {
+        run(&app_data, game, &ctx).await;
+    });
+
+    Some(job_id)
+}
+
+async fn run(app_data: &AppData, game: GameModel, ctx: &AuditContext) {
+    let Some(job) = app_data.jobs.start(&job_id(&game)) else {
+        return;
+    };
+
+    let result = repositories::games::purge_game(&app_data.db, &app_data.s3, ctx, game, &job).await;
+
+    if let Err(e) = &result {
+        tracing::error!("Failed to purge the game: {}", e);
+    }
+
+    job.finish(result.map_err(|e| e.to_string()));
+}
+
+fn job_id(game: &GameModel) -> String {
+    format!("game-purge:{}", game.id)
+}
@@ -0,0 +1,12 @@
+use crate::data::AppData;
+
+mod audit_retention;
+pub mod game_purge;
+pub mod storage_gc;
+
+/// Start the periodic background tasks, they run for the whole life of the server
+pub fn spawn_tasks(app_data: &AppData) {
+    audit_retention::spawn(app_data.clone());
+    game_purge::spawn(app_data.clone());
+    storage_gc::spawn(app_data.clone());
+}
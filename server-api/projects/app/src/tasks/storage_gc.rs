@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use crate::{data::AppData, repositories};
+
+const JOB_ID: &str = "storage-gc";
+
+/// Periodically delete the files of the bucket which aren't referenced anymore
+pub fn spawn(app_data: AppData) {
+    if !app_data.config.gc.enabled {
+        tracing::info!("Storage garbage collector is disabled");
+        return;
+    }
+
+    let period = Duration::from_secs(u64::from(app_data.config.gc.interval_hours.max(1)) * 3600);
+
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(period);
+
+        loop {
+            interval.tick().await;
+            run(&app_data).await;
+        }
+    });
+}
+
+/// Run the garbage collector in the background, returning the id of the job.
+/// Nothing is started if it is already running.
+pub fn start(app_data: AppData) -> Option<String> {
+    if app_data
+        .jobs
+        .get(JOB_ID)
+        .is_some_and(|job| job.finished_at.is_none())
+    {
+        return None;
+    }
+
+    actix_rt::spawn(async move {
+        run(&app_data).await;
+    });
+
+    Some(JOB_ID.to_owned())
+}
+
+async fn run(app_data: &AppData) {
+    let Some(job) = app_data.jobs.start(JOB_ID) else {
+        return;
+    };
+
+    let result = async {
+        let report = repositories::storage::find_orphans(
+            &app_data.db,
+            &app_data.s3,
+            app_data.config.gc.grace_period_hours,
+        )
+        .await?;
+
+        job.set_total(report.orphans.len() as u64);
+
+        for object in &report.orphans {
+            app_data.s3.delete_file(&object.key).await?;
+            job.advance();
+        }
+
+        repositories::storage::resolve_pending_uploads(&app_data.db, &report).await?;
+
+        tracing::info!(
+            "Garbage collector deleted {} files ({} bytes) out of {}, {} uploads finished and {} aborted",
+            report.orphans.len(),
+            report.orphans_size,
+            report.scanned,
+            report.finished_uploads.len(),
+            report.aborted_uploads.len()
+        );
+
+        Ok::<_, crate::core::errors::AppError>(())
+    }
+    .await;
+
+    if let Err(e) = &result {
+        tracing::error!("Storage garbage collector failed: {}", e);
+    }
+
+    job.finish(result.map_err(|e| e.to_string()));
+}
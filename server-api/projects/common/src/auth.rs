@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Returned instead of the user when the login needs the code of the second factor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OidcAuthorization {
+    pub authorization_url: String,
+}
@@ -0,0 +1,5 @@
+//! Serde helpers for calendar dates, formatted as `YYYY-MM-DD`
+
+time::serde::format_description!(iso_date, Date, "[year]-[month]-[day]");
+
+pub use iso_date::{deserialize, option, serialize};
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Media type of the error responses
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Stable code of an error, for the clients to switch on. Codes are only ever added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    InvalidJson,
+    UnsupportedMediaType,
+    ValidationFailed,
+    InvalidMultipart,
+    Unauthorized,
+    Forbidden,
+    TwoFactorRequired,
+    PasswordLoginDisabled,
+    EmailNotVerified,
+    AccountDisabled,
+    RegistrationDisabled,
+    InvalidInvitation,
+    InvalidToken,
+    InvalidPasskey,
+    NotFound,
+    AlreadyExists,
+    RateLimited,
+    IdentityProviderError,
+    StorageError,
+    InternalError,
+    /// A code added by a newer server
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::InvalidJson => "invalid_json",
+            ErrorCode::UnsupportedMediaType => "unsupported_media_type",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::InvalidMultipart => "invalid_multipart",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::TwoFactorRequired => "two_factor_required",
+            ErrorCode::PasswordLoginDisabled => "password_login_disabled",
+            ErrorCode::EmailNotVerified => "email_not_verified",
+            ErrorCode::AccountDisabled => "account_disabled",
+            ErrorCode::RegistrationDisabled => "registration_disabled",
+            ErrorCode::InvalidInvitation => "invalid_invitation",
+            ErrorCode::InvalidToken => "invalid_token",
+            ErrorCode::InvalidPasskey => "invalid_passkey",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::AlreadyExists => "already_exists",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::IdentityProviderError => "identity_provider_error",
+            ErrorCode::StorageError => "storage_error",
+            ErrorCode::InternalError => "internal_error",
+            ErrorCode::Unknown => "unknown",
+        }
+    }
+
+    /// Problem type URI of the code
+    pub fn problem_type(&self) -> String {
+        format!("urn:game-sync:error:{}", self.as_str())
+    }
+}
+
+/// An invalid value of a field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    /// Rule which failed, e.g. `length` or `email`
+    pub code: String,
+    pub message: String,
+}
+
+/// Body of the error responses, following RFC 7807
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProblemDetails {
+    /// `urn:game-sync:error:<code>`
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Reason phrase of the status
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: ErrorCode,
+    /// ID of the request in the server logs, also sent in the `X-Request-Id` header. Only set on
+    /// internal errors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Errors of each invalid field, only set on validation errors
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Vec<FieldError>>,
+}
+
+impl ProblemDetails {
+    pub fn new(
+        status: u16,
+        title: impl Into<String>,
+        code: ErrorCode,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            problem_type: code.problem_type(),
+            title: title.into(),
+            status,
+            detail: detail.into(),
+            code,
+            correlation_id: None,
+            fields: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+
+use crate::pagination::Paginated;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    Windows,
+    Macos,
+    Linux,
+}
+
+impl Platform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Windows => "windows",
+            Platform::Macos => "macos",
+            Platform::Linux => "linux",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validation", derive(validator::Validate))]
+pub struct SystemRequirements {
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(max = 255, message = "OS is too long"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(max_length = 255))]
+    pub os: Option<String>,
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(max = 255, message = "Processor is too long"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(max_length = 255))]
+    pub processor: Option<String>,
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(max = 255, message = "Graphics is too long"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(max_length = 255))]
+    pub graphics: Option<String>,
+    /// Memory in megabytes
+    pub memory: Option<u32>,
+    /// Storage in megabytes
+    pub storage: Option<u32>,
+}
+
+/// Tags, genres and platforms of a game, by their normalized name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameTags {
+    pub tags: Vec<String>,
+    pub genres: Vec<String>,
+    pub platforms: Vec<String>,
+}
+
+/// Number of games matching the search for a value of a facet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameFacets {
+    pub developers: Vec<FacetCount>,
+    pub publishers: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+    pub genres: Vec<FacetCount>,
+    pub platforms: Vec<FacetCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "sea-orm",
+    derive(sea_orm::EnumIter, sea_orm::DeriveActiveEnum),
+    sea_orm(rs_type = "String", db_type = "Enum", enum_name = "banner_type")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BannerType {
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Color"))]
+    Color,
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Image"))]
+    Image,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "sea-orm",
+    derive(sea_orm::EnumIter, sea_orm::DeriveActiveEnum),
+    sea_orm(rs_type = "String", db_type = "Enum", enum_name = "media_kind")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Icon"))]
+    Icon,
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Logo"))]
+    Logo,
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Hero"))]
+    Hero,
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Screenshot"))]
+    Screenshot,
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Trailer"))]
+    Trailer,
+}
+
+impl MediaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaKind::Icon => "icon",
+            MediaKind::Logo => "logo",
+            MediaKind::Hero => "hero",
+            MediaKind::Screenshot => "screenshot",
+            MediaKind::Trailer => "trailer",
+        }
+    }
+
+    /// Screenshots are the only kind having multiple ordered entries per game,
+    /// the other kinds are a single slot replaced on each upload
+    pub fn is_gallery(&self) -> bool {
+        matches!(self, MediaKind::Screenshot)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFormat {
+    Webp,
+    Avif,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Webp => "webp",
+            ImageFormat::Avif => "avif",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Avif => "image/avif",
+        }
+    }
+}
+
+/// A resized version of an image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImageVariant {
+    pub size: String,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameBanner {
+    pub id: i32,
+    pub game_id: i32,
+    pub banner_type: BannerType,
+    /// Hexadecimal color, or key of the image
+    pub value: String,
+    pub accent_color: Option<String>,
+    pub variants: Vec<ImageVariant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameMedia {
+    pub id: i32,
+    pub kind: MediaKind,
+    pub position: i32,
+    /// Public URL of the largest image, or the URL of the trailer
+    pub url: String,
+    pub accent_color: Option<String>,
+    pub variants: Vec<ImageVariant>,
+}
+
+/// Media of a game, grouped by slot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameMediaSet {
+    pub icon: Option<GameMedia>,
+    pub logo: Option<GameMedia>,
+    pub hero: Option<GameMedia>,
+    pub trailer: Option<GameMedia>,
+    pub screenshots: Vec<GameMedia>,
+}
+
+/// A game as returned by the API. The server serializes its entity, which must keep this shape.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Game {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    #[serde(default, with = "crate::dates::option")]
+    pub release_date: Option<Date>,
+    pub system_requirements: Option<SystemRequirements>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+/// A game with its metadata and media
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameDetails {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    #[serde(default, with = "crate::dates::option")]
+    pub release_date: Option<Date>,
+    pub system_requirements: Option<SystemRequirements>,
+    #[serde(flatten)]
+    pub tags: GameTags,
+    pub banner: Option<GameBanner>,
+    pub media: GameMediaSet,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSearchResults {
+    #[serde(flatten)]
+    pub results: Paginated<Game>,
+    pub facets: GameFacets,
+}
+
+/// Body of the creation and update of a game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validation", derive(validator::Validate))]
+pub struct GameInput {
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(min = 1, message = "Name is required"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(min_length = 1))]
+    pub name: String,
+    pub description: Option<String>,
+    pub banner_type: Option<GameBannerInput>,
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(max = 255, message = "Developer is too long"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(max_length = 255))]
+    pub developer: Option<String>,
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(max = 255, message = "Publisher is too long"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(max_length = 255))]
+    pub publisher: Option<String>,
+    #[serde(default, with = "crate::dates::option")]
+    pub release_date: Option<Date>,
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(max = 20, message = "A game can't have more than 20 tags"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(max_items = 20))]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(max = 10, message = "A game can't have more than 10 genres"))
+    )]
+    #[cfg_attr(feature = "openapi", schema(max_items = 10))]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub platforms: Vec<Platform>,
+    #[cfg_attr(feature = "validation", validate)]
+    pub system_requirements: Option<SystemRequirements>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validation", derive(validator::Validate))]
+pub struct GameBannerInput {
+    pub banner_type: BannerType,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    Name,
+    /// Best matches of the search first, only available when searching
+    Relevance,
+    Updated,
+    Released,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortBy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortBy::Name => "name",
+            SortBy::Relevance => "relevance",
+            SortBy::Updated => "updated",
+            SortBy::Released => "released",
+        }
+    }
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+/// Search and pagination of the games listing, as sent by the clients
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    /// Paginate by cursor, an empty cursor requests the first page
+    pub cursor: Option<String>,
+    pub with_total: Option<bool>,
+    pub search: Option<String>,
+    pub sort: Option<SortBy>,
+    pub sort_order: Option<SortOrder>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    /// The games must have all the given tags, genres and platforms
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub platforms: Vec<Platform>,
+    #[serde(default, with = "crate::dates::option")]
+    pub released_after: Option<Date>,
+    #[serde(default, with = "crate::dates::option")]
+    pub released_before: Option<Date>,
+}
+
+impl GameQuery {
+    /// Query string parameters, the lists are sent comma separated
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        let mut push = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                params.push((name, value));
+            }
+        };
+        let join = |values: Vec<&str>| Some(values.join(",")).filter(|list| !list.is_empty());
+        let date = |date: Option<Date>| {
+            date.map(|date| {
+                format!(
+                    "{:04}-{:02}-{:02}",
+                    date.year(),
+                    u8::from(date.month()),
+                    date.day()
+                )
+            })
+        };
+
+        push("page", self.page.map(|page| page.to_string()));
+        push(
+            "per_page",
+            self.per_page.map(|per_page| per_page.to_string()),
+        );
+        push("cursor", self.cursor.clone());
+        push("with_total", self.with_total.map(|total| total.to_string()));
+        push("search", self.search.clone());
+        push("sort", self.sort.map(|sort| sort.as_str().to_owned()));
+        push(
+            "sort_order",
+            self.sort_order
+                .as_ref()
+                .map(|order| order.as_str().to_owned()),
+        );
+        push("developer", self.developer.clone());
+        push("publisher", self.publisher.clone());
+        push("tags", join(self.tags.iter().map(String::as_str).collect()));
+        push(
+            "genres",
+            join(self.genres.iter().map(String::as_str).collect()),
+        );
+        push(
+            "platforms",
+            join(self.platforms.iter().map(Platform::as_str).collect()),
+        );
+        push("released_after", date(self.released_after));
+        push("released_before", date(self.released_before));
+
+        params
+    }
+}
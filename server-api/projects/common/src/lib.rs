@@ -0,0 +1,12 @@
+//! Types exchanged between the server and its clients.
+//!
+//! The `openapi`, `validation` and `sea-orm` features are only enabled by the server, to
+//! document and validate them, and to store the enums in its database.
+
+pub mod auth;
+pub mod dates;
+pub mod errors;
+pub mod games;
+pub mod pagination;
+pub mod uploads;
+pub mod users;
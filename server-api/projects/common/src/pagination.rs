@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PaginationMeta {
+    /// Only set when paginating by page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_page: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<u64>,
+    /// Only set when paginating by page, or by cursor with `with_total`
+    pub total_items: Option<u64>,
+    pub per_page: u64,
+    /// Cursor of the next page, missing on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// A page of a listing. The server documents its concrete listings under their own names,
+/// e.g. `PaginatedGames`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    pub meta: PaginationMeta,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "sea-orm",
+    derive(sea_orm::EnumIter, sea_orm::DeriveActiveEnum),
+    sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_role")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Admin"))]
+    Admin,
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "Publisher"))]
+    Publisher,
+    #[cfg_attr(feature = "sea-orm", sea_orm(string_value = "User"))]
+    User,
+}
+
+impl UserRole {
+    /// Whether this role grants at least the permissions of `role`
+    pub fn includes(&self, role: &UserRole) -> bool {
+        match self {
+            UserRole::Admin => true,
+            UserRole::Publisher => matches!(role, UserRole::Publisher | UserRole::User),
+            UserRole::User => matches!(role, UserRole::User),
+        }
+    }
+
+    /// Roles allowed to manage games, which must be protected by two-factor when it is enforced
+    pub fn requires_two_factor(&self) -> bool {
+        matches!(self, UserRole::Admin | UserRole::Publisher)
+    }
+}
+
+/// A user as returned by the API. The server serializes its entity, which must keep this shape.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: i32,
+    pub email: String,
+    pub role: UserRole,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub totp_enabled_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub email_verified_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub disabled_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
@@ -6,6 +6,21 @@ mod m20230820_204552_create_user_table;
 mod m20230904_210741_create_game_table;
 mod m20230912_151812_add_banner_to_game_table;
 mod m20230920_192459_create_files_table;
+mod m20231005_184512_add_role_and_two_factor_to_user_table;
+mod m20231012_201433_create_passkey_table;
+mod m20231019_093027_add_oidc_subject_to_user_table;
+mod m20231026_174210_create_user_token_table;
+mod m20231102_151847_add_disabled_at_to_user_table;
+mod m20231109_102356_create_invitation_and_game_grant_tables;
+mod m20231116_093512_create_audit_log_table;
+mod m20231123_164205_add_deleted_at_to_game_table;
+mod m20231130_110422_add_variants_to_banner_table;
+mod m20231207_143018_create_game_media_table;
+mod m20231214_101533_add_metadata_to_game_table;
+mod m20231221_154907_add_search_vector_to_game_table;
+mod m20231228_093114_add_totp_last_step_to_user_table;
+mod m20240104_101522_add_sessions_valid_after_to_user_table;
+mod m20240111_094215_add_upload_state_to_file_table;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
@@ -15,6 +30,21 @@ impl MigratorTrait for Migrator {
             Box::new(m20230904_210741_create_game_table::Migration),
             Box::new(m20230912_151812_add_banner_to_game_table::Migration),
             Box::new(m20230920_192459_create_files_table::Migration),
+            Box::new(m20231005_184512_add_role_and_two_factor_to_user_table::Migration),
+            Box::new(m20231012_201433_create_passkey_table::Migration),
+            Box::new(m20231019_093027_add_oidc_subject_to_user_table::Migration),
+            Box::new(m20231026_174210_create_user_token_table::Migration),
+            Box::new(m20231102_151847_add_disabled_at_to_user_table::Migration),
+            Box::new(m20231109_102356_create_invitation_and_game_grant_tables::Migration),
+            Box::new(m20231116_093512_create_audit_log_table::Migration),
+            Box::new(m20231123_164205_add_deleted_at_to_game_table::Migration),
+            Box::new(m20231130_110422_add_variants_to_banner_table::Migration),
+            Box::new(m20231207_143018_create_game_media_table::Migration),
+            Box::new(m20231214_101533_add_metadata_to_game_table::Migration),
+            Box::new(m20231221_154907_add_search_vector_to_game_table::Migration),
+            Box::new(m20231228_093114_add_totp_last_step_to_user_table::Migration),
+            Box::new(m20240104_101522_add_sessions_valid_after_to_user_table::Migration),
+            Box::new(m20240111_094215_add_upload_state_to_file_table::Migration),
         ]
     }
 }
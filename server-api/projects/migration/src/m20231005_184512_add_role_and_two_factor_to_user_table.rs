@@ -0,0 +1,140 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{EnumIter, Iterable},
+    sea_query::extension::postgres::Type,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(UserRole::Type)
+                    .values(UserRole::iter().skip(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(User::Role)
+                            .enumeration(UserRole::Type, UserRole::iter().skip(1))
+                            .not_null()
+                            .default(UserRole::User.to_string()),
+                    )
+                    .add_column(ColumnDef::new(User::TotpSecret).string())
+                    .add_column(ColumnDef::new(User::TotpEnabledAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Until now, every account was able to manage the whole instance
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(
+                        User::Role,
+                        SimpleExpr::from(UserRole::Admin.to_string()).as_enum(UserRole::Type),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecoveryCode::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecoveryCode::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RecoveryCode::UserId).integer().not_null())
+                    .col(ColumnDef::new(RecoveryCode::CodeHash).string().not_null())
+                    .col(ColumnDef::new(RecoveryCode::UsedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(RecoveryCode::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_recovery_code_user")
+                            .from_col(RecoveryCode::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecoveryCode::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::Role)
+                    .drop_column(User::TotpSecret)
+                    .drop_column(User::TotpEnabledAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().if_exists().name(UserRole::Type).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+    Role,
+    TotpSecret,
+    TotpEnabledAt,
+}
+
+#[derive(DeriveIden)]
+enum RecoveryCode {
+    Table,
+    Id,
+    UserId,
+    CodeHash,
+    UsedAt,
+    CreatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum UserRole {
+    #[iden = "user_role"]
+    Type,
+    #[iden = "Admin"]
+    Admin,
+    #[iden = "Publisher"]
+    Publisher,
+    #[iden = "User"]
+    User,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Passkey::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Passkey::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Passkey::UserId).integer().not_null())
+                    .col(ColumnDef::new(Passkey::Name).string().not_null())
+                    .col(ColumnDef::new(Passkey::Credential).json_binary().not_null())
+                    .col(ColumnDef::new(Passkey::LastUsedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Passkey::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_passkey_user")
+                            .from_col(Passkey::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Passkey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Passkey {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Credential,
+    LastUsedAt,
+    CreatedAt,
+}
@@ -0,0 +1,146 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{EnumIter, Iterable},
+    sea_query::extension::postgres::Type,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::EmailVerifiedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing accounts were created before the verification, consider them as verified
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(User::EmailVerifiedAt, Expr::current_timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(UserTokenKind::Type)
+                    .values(UserTokenKind::iter().skip(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserToken::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserToken::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserToken::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(UserToken::Kind)
+                            .enumeration(UserTokenKind::Type, UserTokenKind::iter().skip(1))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserToken::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserToken::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(UserToken::UsedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(UserToken::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_token_user")
+                            .from_col(UserToken::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserToken::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(
+                Type::drop()
+                    .if_exists()
+                    .name(UserTokenKind::Type)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::EmailVerifiedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+    EmailVerifiedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserToken {
+    Table,
+    Id,
+    UserId,
+    Kind,
+    TokenHash,
+    ExpiresAt,
+    UsedAt,
+    CreatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum UserTokenKind {
+    #[iden = "user_token_kind"]
+    Type,
+    #[iden = "EmailVerification"]
+    EmailVerification,
+    #[iden = "PasswordReset"]
+    PasswordReset,
+}
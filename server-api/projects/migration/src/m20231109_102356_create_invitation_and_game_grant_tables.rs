@@ -0,0 +1,168 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{EnumIter, Iterable},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Invitation::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Invitation::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Invitation::CodeHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Invitation::Role)
+                            .enumeration(UserRole::Type, UserRole::iter().skip(1))
+                            .not_null()
+                            .default(UserRole::User.to_string()),
+                    )
+                    .col(
+                        ColumnDef::new(Invitation::GameIds)
+                            .json_binary()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .col(ColumnDef::new(Invitation::MaxUses).integer())
+                    .col(
+                        ColumnDef::new(Invitation::Uses)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Invitation::ExpiresAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Invitation::CreatedBy).integer())
+                    .col(
+                        ColumnDef::new(Invitation::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_invitation_created_by")
+                            .from_col(Invitation::CreatedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameGrant::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameGrant::UserId).integer().not_null())
+                    .col(ColumnDef::new(GameGrant::GameId).integer().not_null())
+                    .col(
+                        ColumnDef::new(GameGrant::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(GameGrant::UserId)
+                            .col(GameGrant::GameId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_grant_user")
+                            .from_col(GameGrant::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_grant_game")
+                            .from_col(GameGrant::GameId)
+                            .to(Game::Table, Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GameGrant::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Invitation::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Invitation {
+    Table,
+    Id,
+    CodeHash,
+    Role,
+    GameIds,
+    MaxUses,
+    Uses,
+    ExpiresAt,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum GameGrant {
+    Table,
+    UserId,
+    GameId,
+    CreatedAt,
+}
+
+/// Created by `m20231005_184512_add_role_and_two_factor_to_user_table`
+#[derive(Iden, EnumIter)]
+pub enum UserRole {
+    #[iden = "user_role"]
+    Type,
+    #[iden = "Admin"]
+    Admin,
+    #[iden = "Publisher"]
+    Publisher,
+    #[iden = "User"]
+    User,
+}
@@ -0,0 +1,129 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{EnumIter, Iterable},
+    sea_query::extension::postgres::Type,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(MediaKind::Type)
+                    .values(MediaKind::iter().skip(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameMedia::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GameMedia::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(GameMedia::GameId).integer().not_null())
+                    .col(
+                        ColumnDef::new(GameMedia::Kind)
+                            .enumeration(MediaKind::Type, MediaKind::iter().skip(1))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GameMedia::Position)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(GameMedia::Value).string().not_null())
+                    .col(ColumnDef::new(GameMedia::AccentColor).string())
+                    .col(ColumnDef::new(GameMedia::Variants).json_binary())
+                    .col(
+                        ColumnDef::new(GameMedia::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_media_game")
+                            .from_col(GameMedia::GameId)
+                            .to(Game::Table, Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_media_game_kind_position")
+                    .table(GameMedia::Table)
+                    .col(GameMedia::GameId)
+                    .col(GameMedia::Kind)
+                    .col(GameMedia::Position)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GameMedia::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().if_exists().name(MediaKind::Type).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum GameMedia {
+    Table,
+    Id,
+    GameId,
+    Kind,
+    Position,
+    Value,
+    AccentColor,
+    Variants,
+    CreatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum MediaKind {
+    #[iden = "media_kind"]
+    Type,
+    #[iden = "Icon"]
+    Icon,
+    #[iden = "Logo"]
+    Logo,
+    #[iden = "Hero"]
+    Hero,
+    #[iden = "Screenshot"]
+    Screenshot,
+    #[iden = "Trailer"]
+    Trailer,
+}
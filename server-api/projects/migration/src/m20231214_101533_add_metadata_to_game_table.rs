@@ -0,0 +1,137 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{EnumIter, Iterable},
+    sea_query::extension::postgres::Type,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .add_column(ColumnDef::new(Game::Developer).string())
+                    .add_column(ColumnDef::new(Game::Publisher).string())
+                    .add_column(ColumnDef::new(Game::ReleaseDate).date())
+                    .add_column(ColumnDef::new(Game::SystemRequirements).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(GameTagKind::Type)
+                    .values(GameTagKind::iter().skip(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameTag::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameTag::GameId).integer().not_null())
+                    .col(
+                        ColumnDef::new(GameTag::Kind)
+                            .enumeration(GameTagKind::Type, GameTagKind::iter().skip(1))
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GameTag::Name).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(GameTag::GameId)
+                            .col(GameTag::Kind)
+                            .col(GameTag::Name),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_tag_game")
+                            .from_col(GameTag::GameId)
+                            .to(Game::Table, Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Used by the search filters, to find the games having a tag
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_tag_kind_name")
+                    .table(GameTag::Table)
+                    .col(GameTag::Kind)
+                    .col(GameTag::Name)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GameTag::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(
+                Type::drop()
+                    .if_exists()
+                    .name(GameTagKind::Type)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .drop_column(Game::Developer)
+                    .drop_column(Game::Publisher)
+                    .drop_column(Game::ReleaseDate)
+                    .drop_column(Game::SystemRequirements)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+    Developer,
+    Publisher,
+    ReleaseDate,
+    SystemRequirements,
+}
+
+#[derive(DeriveIden)]
+enum GameTag {
+    Table,
+    GameId,
+    Kind,
+    Name,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum GameTagKind {
+    #[iden = "game_tag_kind"]
+    Type,
+    #[iden = "Tag"]
+    Tag,
+    #[iden = "Genre"]
+    Genre,
+    #[iden = "Platform"]
+    Platform,
+}
@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // `unaccent` isn't immutable because of its dictionary argument, it can't be used in an
+        // index as is. The wrapper pins the dictionary so it can be declared immutable.
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS unaccent")
+            .await?;
+        db.execute_unprepared(
+            r#"CREATE OR REPLACE FUNCTION game_sync_unaccent(text) RETURNS text
+            LANGUAGE sql IMMUTABLE PARALLEL SAFE STRICT
+            AS $$ SELECT public.unaccent('public.unaccent'::regdictionary, $1) $$"#,
+        )
+        .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .add_column(
+                        ColumnDef::new(Game::SearchVector)
+                            .custom(Alias::new("tsvector"))
+                            .not_null()
+                            .default(Expr::cust("''::tsvector")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Same document as `repositories::games::refresh_search_vector`
+        db.execute_unprepared(
+            r#"UPDATE "game" SET "search_vector" =
+                setweight(to_tsvector('simple', game_sync_unaccent(coalesce("name", ''))), 'A')
+                || setweight(to_tsvector('simple', game_sync_unaccent(
+                    coalesce("developer", '') || ' ' || coalesce("publisher", '') || ' ' ||
+                    coalesce((SELECT string_agg("name", ' ') FROM "game_tag" WHERE "game_tag"."game_id" = "game"."id"), '')
+                )), 'B')
+                || setweight(to_tsvector('simple', game_sync_unaccent(coalesce("description", ''))), 'C')"#,
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_search_vector")
+                    .table(Game::Table)
+                    .col(Game::SearchVector)
+                    .full_text()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .drop_column(Game::SearchVector)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("DROP FUNCTION IF EXISTS game_sync_unaccent(text)")
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    SearchVector,
+}
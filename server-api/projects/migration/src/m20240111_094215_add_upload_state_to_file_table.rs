@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(File::Table)
+                    .add_column(
+                        ColumnDef::new(File::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .add_column(ColumnDef::new(File::ConfirmedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The existing files can't be checked anymore, consider their uploads as finished
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(File::Table)
+                    .value(File::ConfirmedAt, Expr::current_timestamp())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(File::Table)
+                    .drop_column(File::CreatedAt)
+                    .drop_column(File::ConfirmedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum File {
+    Table,
+    CreatedAt,
+    ConfirmedAt,
+}